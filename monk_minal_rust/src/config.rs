@@ -5,12 +5,43 @@
 //! the chosen game configuration. The primary function `get_game_config` uses `dialoguer`
 //! to prompt the user for their desired settings.
 
-use anyhow::Result;
-use dialoguer::{theme::ColorfulTheme, Select, Input}; // Input is not used but was considered.
-use serde::{Serialize, Deserialize}; // For potential future config saving/loading.
+use crate::data_loader::{self, WordWeights, DEFAULT_LANGUAGE};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use dialoguer::{theme::ColorfulTheme, Confirm, Select};
+use log::warn;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Name of the directory (inside the platform config dir) where MonkMinal keeps its files.
+const APP_DIR_NAME: &str = "monk_minal_rust";
+/// File name of the saved last-used configuration.
+const CONFIG_FILE_NAME: &str = "config.json";
+/// Longest word, in characters, drawn at `Difficulty::Easy`.
+const EASY_MAX_WORD_CHARS: usize = 5;
+/// Longest word, in characters, drawn at `Difficulty::Medium`.
+const MEDIUM_MAX_WORD_CHARS: usize = 8;
+/// Weight of the error penalty in net WPM: one WPM off for each error per minute.
+pub const DEFAULT_ERROR_PENALTY: f64 = 1.0;
+/// Smoothing factor of the live WPM in the header that turns smoothing off: each new figure
+/// is shown as it is.
+pub const NO_WPM_SMOOTHING: f64 = 1.0;
+/// Most columns the typing area can be split into on wide terminals (`--columns`).
+pub const MAX_TEXT_COLUMNS: u32 = 3;
+/// Number of words in a Words run when none is given, as pre-selected by the prompt.
+const DEFAULT_WORD_COUNT: u32 = 20;
+/// Time limits offered for Time mode, in seconds.
+pub const TIME_LIMIT_CHOICES: [u32; 4] = [15, 30, 60, 120];
+/// Word counts offered for Words mode.
+pub const WORD_COUNT_CHOICES: [u32; 5] = [10, 20, 30, 40, 50];
+/// Longest quote, in words, that counts as `QuoteLength::Short`.
+const SHORT_QUOTE_MAX_WORDS: usize = 20;
+/// Longest quote, in words, that counts as `QuoteLength::Medium`.
+const MEDIUM_QUOTE_MAX_WORDS: usize = 60;
 
 /// Defines the different types of games available.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum)]
 pub enum GameType {
     /// Game mode where the user types for a fixed duration.
     Time,
@@ -18,11 +49,17 @@ pub enum GameType {
     Words,
     /// Game mode where the user types a specific quote.
     Quote,
+    /// Free practice mode with no time or word limit; the run ends when the user presses Esc.
+    Zen,
+    /// Game mode where the user types their own text, given with `--text` or `--text-file`.
+    Custom,
+    /// Like `Custom`, but for code: line breaks and indentation are kept, and Enter ends each line.
+    Code,
 }
 
 /// Defines the difficulty levels for the game.
 /// Difficulty can affect word length or other game parameters.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum)]
 pub enum Difficulty {
     /// Easy difficulty: typically shorter words or simpler text.
     Easy,
@@ -32,10 +69,172 @@ pub enum Difficulty {
     Hard,
 }
 
+impl Difficulty {
+    /// Returns the longest word, in characters, drawn at this difficulty, or `None` if any length goes.
+    pub fn max_word_chars(&self) -> Option<usize> {
+        match self {
+            Difficulty::Easy => Some(EASY_MAX_WORD_CHARS),
+            Difficulty::Medium => Some(MEDIUM_MAX_WORD_CHARS),
+            Difficulty::Hard => None,
+        }
+    }
+}
+
+/// Keyboard rows a drill is limited to, on a QWERTY layout (see `GameConfig::key_rows`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum)]
+pub enum KeyRows {
+    /// The home row: a s d f g h j k l.
+    Home,
+    /// The top letter row: q w e r t y u i o p.
+    Top,
+    /// The bottom letter row: z x c v b n m.
+    Bottom,
+    /// The home and top rows together.
+    HomeTop,
+    /// The home and bottom rows together.
+    HomeBottom,
+}
+
+impl KeyRows {
+    /// Returns the letters on these rows.
+    pub fn letters(&self) -> &'static str {
+        match self {
+            KeyRows::Home => "asdfghjkl",
+            KeyRows::Top => "qwertyuiop",
+            KeyRows::Bottom => "zxcvbnm",
+            KeyRows::HomeTop => "asdfghjklqwertyuiop",
+            KeyRows::HomeBottom => "asdfghjklzxcvbnm",
+        }
+    }
+}
+
+/// Limits which quotes Quote mode picks from, by their length in words.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum, Default)]
+pub enum QuoteLength {
+    /// Any quote, regardless of length.
+    #[default]
+    Any,
+    /// Quotes of at most 20 words.
+    Short,
+    /// Quotes of 21 to 60 words.
+    Medium,
+    /// Quotes of more than 60 words.
+    Long,
+}
+
+impl QuoteLength {
+    /// Returns whether a quote of `word_count` words falls into this length category.
+    pub fn includes(&self, word_count: usize) -> bool {
+        match self {
+            QuoteLength::Any => true,
+            QuoteLength::Short => word_count <= SHORT_QUOTE_MAX_WORDS,
+            QuoteLength::Medium => word_count > SHORT_QUOTE_MAX_WORDS && word_count <= MEDIUM_QUOTE_MAX_WORDS,
+            QuoteLength::Long => word_count > MEDIUM_QUOTE_MAX_WORDS,
+        }
+    }
+}
+
+/// Defines how the character the user should type next is marked.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum, Default)]
+pub enum CaretStyle {
+    /// Reverse video over the current character.
+    Block,
+    /// The current character is underlined.
+    Underline,
+    /// The terminal's own cursor, shaped as a bar, sits before the current character.
+    Bar,
+    /// Black text on a yellow background (the original look).
+    #[default]
+    Highlight,
+}
+
+/// Defines what happens when the user mistypes a character.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum, Default)]
+pub enum ErrorFeedback {
+    /// No feedback beyond the error coloring.
+    #[default]
+    Off,
+    /// Ring the terminal bell.
+    Bell,
+    /// Briefly invert the whole screen.
+    Flash,
+}
+
+/// How much of the text the typing area shows.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum, Default)]
+pub enum TextView {
+    /// A few lines around the current word, scrolling as lines are finished.
+    #[default]
+    Window,
+    /// The whole passage, using the height of the terminal and scrolling only when it does
+    /// not fit. Applies to Words, Quote and Custom modes, which have a fixed text.
+    Full,
+}
+
+/// What the word separator does when it is typed before the end of a word.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum, Default)]
+pub enum SpacePolicy {
+    /// The separator only completes a word typed in full, with no errors pending; anywhere
+    /// else it is a mistake like any other wrong key.
+    #[default]
+    Complete,
+    /// The separator moves on to the next word wherever it is typed, once the word has been
+    /// started. The characters left untyped count as missed: typed and wrong.
+    Skip,
+}
+
+/// How the headline WPM of a run is scored, since typing tutors penalize errors differently.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum, Default)]
+pub enum WpmScheme {
+    /// Gross WPM less `error_penalty` WPM (one by default) for each error per minute (the original scoring).
+    #[default]
+    Net,
+    /// Gross WPM scaled by accuracy.
+    Simple,
+    /// Gross WPM, with errors not penalized at all.
+    Raw,
+}
+
+impl WpmScheme {
+    /// Returns the label the scheme's WPM is shown under, e.g. "Net WPM".
+    pub fn label(&self) -> &'static str {
+        match self {
+            WpmScheme::Net => "Net WPM",
+            WpmScheme::Simple => "Simple WPM",
+            WpmScheme::Raw => "Raw WPM",
+        }
+    }
+
+    /// Returns a short description of how the scheme scores a run, for the results screen.
+    pub fn description(&self) -> &'static str {
+        match self {
+            WpmScheme::Net => "net (gross minus errors per minute)",
+            WpmScheme::Simple => "simple (gross times accuracy)",
+            WpmScheme::Raw => "raw (errors not penalized)",
+        }
+    }
+}
+
+/// Names the color themes available for the typing area (see `theme::Theme`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ValueEnum, Default)]
+pub enum ThemeName {
+    /// Green for correct, red for errors, dimmed for pending text.
+    #[default]
+    Default,
+    /// Colors from the Solarized palette.
+    Solarized,
+    /// No colors, only bold/dim/underline/reverse; for terminals without color support.
+    Mono,
+    /// Bright, bold colors for maximum legibility.
+    HighContrast,
+}
+
 /// Stores the user's chosen game configuration.
 ///
 /// This struct is populated by `get_game_config` based on user input.
+/// Missing fields in a saved config file fall back to the values from `GameConfig::new()`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct GameConfig {
     /// The type of game selected by the user (e.g., Time, Words, Quote).
     pub game_type: GameType,
@@ -45,6 +244,124 @@ pub struct GameConfig {
     pub word_count: Option<u32>,
     /// The difficulty level selected by the user.
     pub difficulty: Difficulty,
+    /// Whether Time, Words and Zen modes mix in capitalization and punctuation.
+    pub punctuation: bool,
+    /// Whether Time, Words and Zen modes mix in numeric tokens such as "42" or "1999".
+    pub numbers: bool,
+    /// Name of the language pack words are drawn from (see `data_loader::available_languages`).
+    pub language: String,
+    /// Which quotes Quote mode picks from. Ignored by the other modes.
+    pub quote_length: QuoteLength,
+    /// How the current character is marked while typing.
+    pub caret_style: CaretStyle,
+    /// The color theme of the typing area.
+    pub theme: ThemeName,
+    /// Whether a 3-2-1 countdown runs between the first key press and the start of the timer.
+    pub countdown: bool,
+    /// WPM the user aims for, as scored by `wpm_scheme`, if any.
+    pub target_wpm: Option<u32>,
+    /// Accuracy (in percent) below which the run fails and ends early, if any.
+    pub min_accuracy: Option<f64>,
+    /// Number of mistakes in a row, without a correct key in between, that fail the run, if any.
+    pub max_consecutive_errors: Option<u32>,
+    /// Whether wrong keys are rejected instead of recorded, so each character must be typed correctly.
+    pub strict: bool,
+    /// Whether mistakes look like correct input while typing, so they are only seen in the results.
+    pub blind: bool,
+    /// Whether a Time run is scored by the number of words completed rather than by characters.
+    /// Ignored by the other modes (see `is_time_attack`).
+    pub time_attack: bool,
+    /// Whether Backspace and the delete-word shortcuts are ignored, so mistakes are permanent.
+    /// A wrong key then takes the place of the character it was meant for instead of blocking.
+    pub no_backspace: bool,
+    /// Whether the first mistake restarts the run on the same text, so only flawless runs finish.
+    pub restart_on_error: bool,
+    /// Whether a word typed with mistakes has to be typed again, up to `MAX_WORD_RETRIES` times,
+    /// before the run moves on (see `GameState::word_needs_retry`).
+    pub retry_mistyped: bool,
+    /// Whether restarts keep adding every attempt's counters and time to running session totals,
+    /// shown on a session summary when the session ends, instead of starting from a clean slate.
+    pub cumulative: bool,
+    /// Keyboard rows Time, Words and Zen modes draw their words from, for drilling them, or
+    /// `None` for words using any keys. Punctuation and numbers are left out of drills.
+    pub key_rows: Option<KeyRows>,
+    /// Whether Time, Words and Zen modes favor words with the characters missed most in the
+    /// results history (see `weak_chars`).
+    pub weak_keys: bool,
+    /// Number of quotes a Quote run chains back to back as one run, or `None` for a single quote.
+    pub marathon: Option<u32>,
+    /// The quote a Quote run types, by its `id` or else its index in the quotes file, or `None`
+    /// for a randomly chosen one (see `select_quote`).
+    pub quote_number: Option<u64>,
+    /// Extra feedback given on each mistyped character.
+    pub error_feedback: ErrorFeedback,
+    /// How the headline WPM, and so the WPM target, is scored.
+    pub wpm_scheme: WpmScheme,
+    /// WPM taken off net WPM for each error per minute; 0 makes net WPM the same as gross.
+    pub error_penalty: f64,
+    /// Weight of each new figure in the live WPM shown in the header, from just above 0 (very
+    /// smooth) to `NO_WPM_SMOOTHING` (raw). See `GameState::update_live_wpm`. The results are
+    /// never smoothed.
+    pub wpm_smoothing: f64,
+    /// In Words and Quote mode, the word progress in the header is highlighted once fewer than
+    /// this many words are left, to signal the finish line. 0 never highlights it.
+    pub finish_warning_words: u32,
+    /// Most words shown in the typing area at once, or `None` to fit as many as the terminal
+    /// allows. Ignored in Code mode, which shows whole lines, and with the full text view.
+    pub window_words: Option<u32>,
+    /// How much of the text the typing area shows (see `shows_full_text`).
+    pub text_view: TextView,
+    /// Number of columns, from 1 to `MAX_TEXT_COLUMNS`, the typing area flows the words through,
+    /// where the terminal is wide enough (see `ui::fitting_columns`). Ignored in Code mode.
+    pub text_columns: u32,
+    /// What the word separator does in the middle of a word.
+    pub space_policy: SpacePolicy,
+    /// Whether the terminal's own cursor is also placed on the character to type next, whatever
+    /// the caret style, so a blinking cursor shows where typing continues.
+    pub show_cursor: bool,
+    /// Whether Enter finishes a Words, Quote or Custom run where it stands, scored like a
+    /// completed run, instead of being ignored.
+    pub enter_finishes: bool,
+    /// Seconds without a key press after which a running test pauses itself, or `None` to
+    /// never pause on its own.
+    pub idle_timeout: Option<u32>,
+    /// Minutes of running time after which any run ends, as a safety cap for modes that could
+    /// otherwise go on indefinitely, or `None` for no cap. Paused time does not count.
+    pub max_minutes: Option<u32>,
+    /// Fewest characters a word in Time, Words and Zen modes may have, or `None` for no minimum.
+    /// Applies on top of `difficulty`; see `word_length_bounds`.
+    pub min_word_length: Option<u32>,
+    /// Most characters a word in Time, Words and Zen modes may have, or `None` to go by `difficulty`.
+    pub max_word_length: Option<u32>,
+    /// Whether the welcome banner is shown at startup. Never prompted for: turn it off in the
+    /// saved config file, or for one session with `--no-banner`.
+    pub show_banner: bool,
+    /// The text typed in Custom and Code modes, exactly as given. `None` for the other modes.
+    /// Only ever set for a session from the command line, so it is not saved.
+    #[serde(skip)]
+    pub custom_text: Option<String>,
+    /// Seed for every random choice of words, quotes, punctuation and numbers in the session,
+    /// or `None` to seed from system entropy. Only ever set from the command line, so it is not saved.
+    #[serde(skip)]
+    pub seed: Option<u64>,
+    /// CSV file each finished run is appended to (see `results_csv`), or `None` for no CSV.
+    /// Only ever set from the command line, so it is not saved.
+    #[serde(skip)]
+    pub csv_path: Option<PathBuf>,
+    /// File each finished run's recording is saved to, for `--replay` (see `replay`), or `None`
+    /// to record nothing. Only ever set from the command line, so it is not saved.
+    #[serde(skip)]
+    pub record_path: Option<PathBuf>,
+    /// The characters a weak-key drill favors, worst first. Filled from the results history when
+    /// the game starts (see `stats::weak_chars`); empty when `weak_keys` is off or nothing has been
+    /// missed often enough yet, which leaves word selection unchanged.
+    #[serde(skip)]
+    pub weak_chars: Vec<char>,
+    /// Frequency weights of the loaded word list, so Time, Words and Zen modes pick common words
+    /// more often. Filled when the words are loaded; empty for lists without weights, whose words
+    /// are then picked uniformly.
+    #[serde(skip)]
+    pub word_weights: WordWeights,
 }
 
 impl GameConfig {
@@ -58,77 +375,728 @@ impl GameConfig {
             time_seconds: Some(30),    // Default time for Time mode
             word_count: None,          // No default word count for Words mode (user must choose)
             difficulty: Difficulty::Medium, // Default difficulty
+            punctuation: false,        // Plain lowercase words by default
+            numbers: false,            // No numeric tokens by default
+            language: DEFAULT_LANGUAGE.to_string(),
+            quote_length: QuoteLength::Any,
+            caret_style: CaretStyle::Highlight,
+            theme: ThemeName::Default,
+            countdown: false,
+            target_wpm: None,
+            min_accuracy: None,
+            max_consecutive_errors: None,
+            strict: false,
+            blind: false,
+            time_attack: false,
+            no_backspace: false,
+            restart_on_error: false,
+            retry_mistyped: false,
+            cumulative: false,
+            key_rows: None,
+            weak_keys: false,
+            marathon: None,
+            quote_number: None,
+            error_feedback: ErrorFeedback::Off,
+            wpm_scheme: WpmScheme::Net,
+            error_penalty: DEFAULT_ERROR_PENALTY,
+            wpm_smoothing: NO_WPM_SMOOTHING,
+            finish_warning_words: 0,
+            window_words: None,
+            text_view: TextView::Window,
+            text_columns: 1,
+            space_policy: SpacePolicy::Complete,
+            show_cursor: false,
+            enter_finishes: false,
+            idle_timeout: None,
+            max_minutes: None,
+            min_word_length: None,
+            max_word_length: None,
+            show_banner: true,
+            custom_text: None,
+            seed: None,
+            csv_path: None,
+            record_path: None,
+            weak_chars: Vec::new(),
+            word_weights: WordWeights::default(),
+        }
+    }
+
+    /// Returns the fewest and most characters a word may have, where `None` means no limit.
+    ///
+    /// Explicit bounds win over the difficulty: `max_word_length` replaces the difficulty's
+    /// limit, and that limit is also dropped when it is below `min_word_length`.
+    pub fn word_length_bounds(&self) -> (usize, Option<usize>) {
+        let min_chars = self.min_word_length.map_or(0, |min| min as usize);
+        let max_chars = match self.max_word_length {
+            Some(max) => Some(max as usize),
+            None => self.difficulty.max_word_chars().filter(|&max| max >= min_chars),
+        };
+        (min_chars, max_chars)
+    }
+
+    /// Returns whether the typing area shows the whole text: with the full text view, in the
+    /// modes whose text is fixed. Time and Zen modes keep adding words, so they stay windowed.
+    pub fn shows_full_text(&self) -> bool {
+        self.text_view == TextView::Full && matches!(self.game_type, GameType::Words | GameType::Quote | GameType::Custom)
+    }
+
+    /// Returns whether this is a time attack: a Time run scored by words completed.
+    pub fn is_time_attack(&self) -> bool {
+        self.time_attack && self.game_type == GameType::Time
+    }
+
+    /// Returns whether a run with `wpm` (as scored by `wpm_scheme`) and `accuracy` meets the
+    /// configured goals, or `None` if no goal is set. Reaching a goal exactly counts as meeting it.
+    pub fn meets_goals(&self, wpm: f64, accuracy: f64) -> Option<bool> {
+        if self.target_wpm.is_none() && self.min_accuracy.is_none() {
+            return None;
+        }
+        let wpm_ok = self.target_wpm.is_none_or(|target| wpm >= target as f64);
+        let accuracy_ok = self.min_accuracy.is_none_or(|floor| accuracy >= floor);
+        Some(wpm_ok && accuracy_ok)
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the directory used for MonkMinal's persistent files, if the platform has one.
+pub fn app_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME))
+}
+
+/// Returns the directory MonkMinal looks in for replacement data files, if the platform has one.
+///
+/// On Linux this follows `$XDG_DATA_HOME`, e.g. `~/.local/share/monk_minal_rust`.
+pub fn app_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR_NAME))
+}
+
+/// Returns the path of the saved configuration file, if the platform has a config dir.
+fn config_file_path() -> Option<PathBuf> {
+    app_config_dir().map(|dir| dir.join(CONFIG_FILE_NAME))
+}
+
+/// Saves `config` as the last-used configuration in the platform config dir.
+///
+/// The directory is created if it does not exist yet.
+pub fn save_config(config: &GameConfig) -> Result<()> {
+    let path = config_file_path().context("Could not determine the config directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(config).context("Failed to serialize config")?;
+    fs::write(&path, json).with_context(|| format!("Failed to write config file {}", path.display()))?;
+    Ok(())
+}
+
+/// Loads the last-used configuration saved by `save_config`.
+///
+/// Returns `None` if there is no saved config yet or if the file cannot be read or parsed;
+/// a corrupt file is logged and otherwise ignored.
+pub fn load_saved_config() -> Option<GameConfig> {
+    let path = config_file_path()?;
+    let contents = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            warn!("Ignoring unreadable config file {}: {}", path.display(), e);
+            None
         }
     }
 }
 
+/// Game settings supplied up front, typically from command-line flags.
+///
+/// Any setting left as `None` is asked for interactively by `get_game_config`.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    /// The requested game type (`--mode`).
+    pub game_type: Option<GameType>,
+    /// The requested duration in seconds for Time mode (`--time`).
+    pub time_seconds: Option<u32>,
+    /// The requested number of words for Words mode (`--words`).
+    pub word_count: Option<u32>,
+    /// The requested difficulty (`--difficulty`).
+    pub difficulty: Option<Difficulty>,
+    /// Whether punctuation was requested (`--punctuation`).
+    pub punctuation: Option<bool>,
+    /// Whether numbers were requested (`--numbers`).
+    pub numbers: Option<bool>,
+    /// The requested language pack (`--language`).
+    pub language: Option<String>,
+    /// The requested caret style (`--caret`).
+    pub caret_style: Option<CaretStyle>,
+    /// The requested color theme (`--theme`).
+    pub theme: Option<ThemeName>,
+    /// Whether the start countdown was requested (`--countdown`).
+    pub countdown: Option<bool>,
+    /// The WPM goal under the chosen `--wpm-scheme` (`--target-wpm`).
+    pub target_wpm: Option<u32>,
+    /// The accuracy floor in percent (`--min-accuracy`).
+    pub min_accuracy: Option<f64>,
+    /// The number of mistakes in a row that fail the run (`--max-consecutive-errors`).
+    pub max_consecutive_errors: Option<u32>,
+    /// Whether strict mode was requested (`--strict`).
+    pub strict: Option<bool>,
+    /// Whether blind mode was requested (`--blind`).
+    pub blind: Option<bool>,
+    /// Whether a time attack was requested (`--time-attack`).
+    pub time_attack: Option<bool>,
+    /// Whether corrections were turned off (`--no-backspace`).
+    pub no_backspace: Option<bool>,
+    /// Whether restarting on the first mistake was requested (`--restart-on-error`).
+    pub restart_on_error: Option<bool>,
+    /// Whether retrying mistyped words was requested (`--retry-mistyped`).
+    pub retry_mistyped: Option<bool>,
+    /// Whether session totals across restarts were requested (`--cumulative`).
+    pub cumulative: Option<bool>,
+    /// The keyboard rows to drill (`--key-rows`).
+    pub key_rows: Option<KeyRows>,
+    /// Whether a weak-key drill was requested (`--weak-keys`).
+    pub weak_keys: Option<bool>,
+    /// The number of quotes to chain in Quote mode (`--marathon`).
+    pub marathon: Option<u32>,
+    /// The id or index of the quote to type (`--quote`).
+    pub quote_number: Option<u64>,
+    /// The requested feedback on mistakes (`--error-feedback`).
+    pub error_feedback: Option<ErrorFeedback>,
+    /// The requested WPM scoring (`--wpm-scheme`).
+    pub wpm_scheme: Option<WpmScheme>,
+    /// The requested weight of the error penalty in net WPM (`--error-penalty`).
+    pub error_penalty: Option<f64>,
+    /// The requested smoothing factor of the live WPM (`--wpm-smoothing`).
+    pub wpm_smoothing: Option<f64>,
+    /// The requested finish-line threshold in words (`--finish-warning`), where 0 turns it off.
+    pub finish_warning_words: Option<u32>,
+    /// The requested word window size (`--window-words`), where 0 asks to fit the terminal again.
+    pub window_words: Option<u32>,
+    /// The requested text view (`--text-view`).
+    pub text_view: Option<TextView>,
+    /// The requested number of text columns (`--columns`).
+    pub text_columns: Option<u32>,
+    /// The requested space policy (`--space-policy`).
+    pub space_policy: Option<SpacePolicy>,
+    /// Whether the terminal cursor was requested on the caret (`--show-cursor`).
+    pub show_cursor: Option<bool>,
+    /// Whether Enter was requested to finish the run (`--enter-finishes`).
+    pub enter_finishes: Option<bool>,
+    /// The requested idle timeout in seconds (`--idle-timeout`), where 0 turns it off.
+    pub idle_timeout: Option<u32>,
+    /// The requested run length cap in minutes (`--max-minutes`), where 0 removes it.
+    pub max_minutes: Option<u32>,
+    /// The requested minimum word length (`--min-word-length`), where 0 removes it.
+    pub min_word_length: Option<u32>,
+    /// The requested maximum word length (`--max-word-length`), where 0 goes back to the difficulty's.
+    pub max_word_length: Option<u32>,
+    /// The text to type in Custom or Code mode (`--text`, or the contents of `--text-file`).
+    pub custom_text: Option<String>,
+    /// The seed for all random choices (`--seed`).
+    pub seed: Option<u64>,
+    /// The CSV file to append results to (`--csv`).
+    pub csv_path: Option<PathBuf>,
+    /// The file to save run recordings to (`--record`).
+    pub record_path: Option<PathBuf>,
+}
+
+impl ConfigOverrides {
+    /// Checks that the supplied settings are consistent with each other.
+    ///
+    /// Returns an error for combinations such as `--time` together with `--mode words`,
+    /// or for zero-valued durations and word counts.
+    pub fn validate(&self) -> Result<()> {
+        if self.time_seconds.is_some() && self.word_count.is_some() {
+            bail!("--time and --words cannot be used together");
+        }
+        if let Some(text) = &self.custom_text {
+            if self.game_type.as_ref().is_some_and(|game_type| !matches!(game_type, GameType::Custom | GameType::Code)) {
+                bail!("--text and --text-file can only be used with --mode custom or --mode code");
+            }
+            if self.time_seconds.is_some() || self.word_count.is_some() {
+                bail!("--time and --words cannot be used with --text or --text-file");
+            }
+            if text.split_whitespace().next().is_none() {
+                bail!("The custom text is empty");
+            }
+        }
+        match self.game_type {
+            Some(GameType::Custom) | Some(GameType::Code) if self.custom_text.is_none() => {
+                bail!("--mode custom and --mode code need the text to type, given with --text or --text-file");
+            }
+            Some(GameType::Words) | Some(GameType::Quote) | Some(GameType::Zen) if self.time_seconds.is_some() => {
+                bail!("--time can only be used with --mode time");
+            }
+            Some(GameType::Time) | Some(GameType::Quote) | Some(GameType::Zen) if self.word_count.is_some() => {
+                bail!("--words can only be used with --mode words");
+            }
+            Some(GameType::Quote) if self.punctuation == Some(true) => {
+                bail!("--punctuation cannot be used with --mode quote");
+            }
+            Some(GameType::Quote) if self.numbers == Some(true) => {
+                bail!("--numbers cannot be used with --mode quote");
+            }
+            Some(GameType::Quote) if self.language.is_some() => {
+                bail!("--language cannot be used with --mode quote");
+            }
+            _ => {}
+        }
+        if self.custom_text.is_some() {
+            if self.punctuation == Some(true) || self.numbers == Some(true) {
+                bail!("--punctuation and --numbers cannot be used with custom text");
+            }
+            if self.language.is_some() {
+                bail!("--language cannot be used with custom text");
+            }
+        }
+        if self.time_seconds == Some(0) {
+            bail!("--time must be greater than zero");
+        }
+        if self.word_count == Some(0) {
+            bail!("--words must be greater than zero");
+        }
+        if self.target_wpm == Some(0) {
+            bail!("--target-wpm must be greater than zero");
+        }
+        if self.min_accuracy.is_some_and(|floor| !(0.0..=100.0).contains(&floor)) {
+            bail!("--min-accuracy must be between 0 and 100");
+        }
+        if self.max_consecutive_errors == Some(0) {
+            bail!("--max-consecutive-errors must be greater than zero");
+        }
+        if self.error_penalty.is_some_and(|weight| !(weight.is_finite() && weight >= 0.0)) {
+            bail!("--error-penalty must be zero or more");
+        }
+        if self.text_columns.is_some_and(|columns| !(1..=MAX_TEXT_COLUMNS).contains(&columns)) {
+            bail!("--columns must be between 1 and {}", MAX_TEXT_COLUMNS);
+        }
+        if self.wpm_smoothing.is_some_and(|factor| !(factor > 0.0 && factor <= NO_WPM_SMOOTHING)) {
+            bail!("--wpm-smoothing must be greater than 0 and at most 1");
+        }
+        if self.time_attack == Some(true) && self.implied_game_type().is_some_and(|game_type| game_type != GameType::Time) {
+            bail!("--time-attack can only be used with --mode time");
+        }
+        if self.key_rows.is_some() {
+            if self.implied_game_type().is_some_and(|game_type| !matches!(game_type, GameType::Time | GameType::Words | GameType::Zen)) {
+                bail!("--key-rows can only be used with --mode time, words or zen");
+            }
+            if self.punctuation == Some(true) || self.numbers == Some(true) {
+                bail!("--punctuation and --numbers cannot be used with --key-rows");
+            }
+        }
+        if let Some(quotes) = self.marathon {
+            if self.implied_game_type().is_some_and(|game_type| game_type != GameType::Quote) {
+                bail!("--marathon can only be used with --mode quote");
+            }
+            if quotes == 0 {
+                bail!("--marathon must be greater than zero");
+            }
+        }
+        if self.quote_number.is_some() {
+            if self.implied_game_type().is_some_and(|game_type| game_type != GameType::Quote) {
+                bail!("--quote can only be used with --mode quote");
+            }
+            if self.marathon.is_some() {
+                bail!("--quote cannot be used with --marathon");
+            }
+        }
+        if self.min_word_length.is_some() || self.max_word_length.is_some() {
+            if self.implied_game_type().is_some_and(|game_type| !matches!(game_type, GameType::Time | GameType::Words | GameType::Zen)) {
+                bail!("--min-word-length and --max-word-length can only be used with --mode time, words or zen");
+            }
+            if let (Some(min), Some(max)) = (self.min_word_length, self.max_word_length) {
+                if max > 0 && min > max {
+                    bail!("--min-word-length ({}) cannot be greater than --max-word-length ({})", min, max);
+                }
+            }
+        }
+        if self.weak_keys == Some(true) {
+            if self.implied_game_type().is_some_and(|game_type| !matches!(game_type, GameType::Time | GameType::Words | GameType::Zen)) {
+                bail!("--weak-keys can only be used with --mode time, words or zen");
+            }
+            if self.key_rows.is_some() {
+                bail!("--weak-keys cannot be used with --key-rows");
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns these settings for choosing a new game with "Change settings": everything the
+    /// player is asked for again is cleared, and every other flag is kept for the rest of the session.
+    ///
+    /// The text to type (`--text`, `--quote`) is cleared too, since it would imply the game type.
+    pub fn for_new_settings(&self) -> ConfigOverrides {
+        ConfigOverrides {
+            game_type: None,
+            time_seconds: None,
+            word_count: None,
+            difficulty: None,
+            punctuation: None,
+            numbers: None,
+            language: None,
+            caret_style: None,
+            theme: None,
+            countdown: None,
+            custom_text: None,
+            quote_number: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the game type requested explicitly, or implied by `--time`/`--words`/`--text`.
+    pub fn implied_game_type(&self) -> Option<GameType> {
+        self.game_type.clone().or_else(|| {
+            if self.custom_text.is_some() {
+                Some(GameType::Custom)
+            } else if self.time_seconds.is_some() {
+                Some(GameType::Time)
+            } else if self.word_count.is_some() {
+                Some(GameType::Words)
+            } else if self.quote_number.is_some() {
+                Some(GameType::Quote)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns these settings with whatever `to_complete_config` still needs taken from the
+    /// defaults: Time mode, 30 seconds or 20 words, and medium difficulty.
+    ///
+    /// Custom and Code modes cannot be filled in, since there is no default text.
+    pub fn with_defaults(&self) -> ConfigOverrides {
+        let defaults = GameConfig::new();
+        let mut filled = self.clone();
+        let game_type = self.implied_game_type().unwrap_or(defaults.game_type);
+        match game_type {
+            GameType::Time => filled.time_seconds = self.time_seconds.or(defaults.time_seconds),
+            GameType::Words => filled.word_count = Some(self.word_count.unwrap_or(DEFAULT_WORD_COUNT)),
+            _ => {}
+        }
+        filled.game_type = Some(game_type);
+        filled.difficulty = Some(self.difficulty.clone().unwrap_or(defaults.difficulty));
+        filled
+    }
+
+    /// Builds a `GameConfig` without prompting if every setting required by the
+    /// chosen mode is present.
+    ///
+    /// Time and Words modes need their duration/word count and a difficulty; Zen mode only a difficulty.
+    /// Quote mode only needs the mode itself, since difficulty does not apply to quotes, and
+    /// Custom and Code modes only their text.
+    /// Punctuation, numbers and the countdown are off unless requested, and the language, caret
+    /// style and theme fall back to their defaults, so they never force a prompt.
+    pub fn to_complete_config(&self) -> Option<GameConfig> {
+        let mut config = GameConfig::new();
+        config.game_type = self.implied_game_type()?;
+        config.punctuation = self.punctuation.unwrap_or(false);
+        config.numbers = self.numbers.unwrap_or(false);
+        if let Some(language) = &self.language {
+            config.language = language.clone();
+        }
+        if let Some(caret_style) = &self.caret_style {
+            config.caret_style = caret_style.clone();
+        }
+        if let Some(theme) = &self.theme {
+            config.theme = theme.clone();
+        }
+        config.countdown = self.countdown.unwrap_or(false);
+        config.target_wpm = self.target_wpm;
+        config.min_accuracy = self.min_accuracy;
+        config.max_consecutive_errors = self.max_consecutive_errors;
+        config.strict = self.strict.unwrap_or(false);
+        config.blind = self.blind.unwrap_or(false);
+        config.time_attack = self.time_attack.unwrap_or(false);
+        config.no_backspace = self.no_backspace.unwrap_or(false);
+        config.restart_on_error = self.restart_on_error.unwrap_or(false);
+        config.retry_mistyped = self.retry_mistyped.unwrap_or(false);
+        config.cumulative = self.cumulative.unwrap_or(false);
+        config.key_rows = self.key_rows.clone();
+        config.weak_keys = self.weak_keys.unwrap_or(false);
+        config.marathon = self.marathon;
+        config.quote_number = self.quote_number;
+        if let Some(error_feedback) = &self.error_feedback {
+            config.error_feedback = error_feedback.clone();
+        }
+        if let Some(wpm_scheme) = &self.wpm_scheme {
+            config.wpm_scheme = wpm_scheme.clone();
+        }
+        if let Some(error_penalty) = self.error_penalty {
+            config.error_penalty = error_penalty;
+        }
+        if let Some(wpm_smoothing) = self.wpm_smoothing {
+            config.wpm_smoothing = wpm_smoothing;
+        }
+        if let Some(finish_warning_words) = self.finish_warning_words {
+            config.finish_warning_words = finish_warning_words;
+        }
+        config.window_words = self.window_words.filter(|&count| count > 0);
+        if let Some(text_view) = &self.text_view {
+            config.text_view = text_view.clone();
+        }
+        if let Some(text_columns) = self.text_columns {
+            config.text_columns = text_columns;
+        }
+        if let Some(space_policy) = &self.space_policy {
+            config.space_policy = space_policy.clone();
+        }
+        if let Some(show_cursor) = self.show_cursor {
+            config.show_cursor = show_cursor;
+        }
+        if let Some(enter_finishes) = self.enter_finishes {
+            config.enter_finishes = enter_finishes;
+        }
+        config.idle_timeout = self.idle_timeout.filter(|&seconds| seconds > 0);
+        config.max_minutes = self.max_minutes.filter(|&minutes| minutes > 0);
+        config.min_word_length = self.min_word_length.filter(|&chars| chars > 0);
+        config.max_word_length = self.max_word_length.filter(|&chars| chars > 0);
+        config.seed = self.seed;
+        config.csv_path = self.csv_path.clone();
+        config.record_path = self.record_path.clone();
+        match config.game_type {
+            GameType::Time => {
+                config.time_seconds = Some(self.time_seconds?);
+                config.word_count = None;
+                config.difficulty = self.difficulty.clone()?;
+            }
+            GameType::Words => {
+                config.word_count = Some(self.word_count?);
+                config.time_seconds = None;
+                config.difficulty = self.difficulty.clone()?;
+            }
+            GameType::Zen => {
+                config.time_seconds = None;
+                config.word_count = None;
+                config.difficulty = self.difficulty.clone()?;
+            }
+            GameType::Quote => {
+                config.time_seconds = None;
+                config.word_count = None;
+                if let Some(difficulty) = &self.difficulty {
+                    config.difficulty = difficulty.clone();
+                }
+                config.punctuation = false;
+                config.numbers = false;
+            }
+            GameType::Custom | GameType::Code => {
+                config.time_seconds = None;
+                config.word_count = None;
+                if let Some(difficulty) = &self.difficulty {
+                    config.difficulty = difficulty.clone();
+                }
+                config.punctuation = false;
+                config.numbers = false;
+                config.custom_text = Some(self.custom_text.clone()?);
+            }
+        }
+        Some(config)
+    }
+}
+
 /// Prompts the user to select game configuration options interactively.
 ///
-/// Uses `dialoguer` to present menus for game type, time/word count (if applicable),
-/// and difficulty.
+/// Uses `dialoguer` to present menus for game type, time/word count or quote length (if applicable),
+/// difficulty, punctuation, numbers and language (all modes but Quote), caret style, theme and countdown.
+/// Settings already present in `overrides` are used as-is and not prompted for.
+/// The previously saved configuration (see `load_saved_config`) determines which option is
+/// pre-selected in each menu, and the chosen configuration is saved back on success (see
+/// `finish_game_config`). The settings screen (see `settings_screen`) asks the same questions
+/// without leaving raw mode; these prompts are its fallback.
 ///
 /// # Returns
 ///
 /// Returns a `Result<GameConfig>` which is `Ok` if the user successfully completes
 /// the configuration, or an `Err` if an error occurs during the interaction (e.g., user cancels).
-pub fn get_game_config() -> Result<GameConfig> {
+pub fn get_game_config(overrides: &ConfigOverrides) -> Result<GameConfig> {
+    let previous = load_saved_config().unwrap_or_default();
+    let mut config = prompt_game_config(overrides, &previous)?;
+    // Set before the word options, which are not asked for drills.
+    config.key_rows = overrides.key_rows.clone();
+    config.punctuation = prompt_word_option(
+        &config, overrides.punctuation, "Include punctuation and capitals?", previous.punctuation)?;
+    config.numbers = prompt_word_option(
+        &config, overrides.numbers, "Include numbers?", previous.numbers)?;
+    config.language = prompt_language(&config, overrides, previous.language.as_str())?;
+    config.caret_style = prompt_caret_style(overrides, &previous.caret_style)?;
+    config.theme = prompt_theme(overrides, &previous.theme)?;
+    config.countdown = prompt_countdown(overrides, previous.countdown)?;
+    Ok(finish_game_config(config, overrides, &previous))
+}
+
+/// Completes the `config` the player chose, however it was asked for, with the settings that are
+/// never prompted for, and saves it as the new `previous` configuration.
+///
+/// Session-only settings come from `overrides`; the sticky ones from `overrides` or else `previous`.
+/// Failing to save is logged but does not fail the configuration.
+pub fn finish_game_config(config: GameConfig, overrides: &ConfigOverrides, previous: &GameConfig) -> GameConfig {
+    let config = complete_game_config(config, overrides, previous);
+    if let Err(e) = save_config(&config) {
+        warn!("Failed to save configuration: {:?}", e);
+    }
+    config
+}
+
+/// Like `finish_game_config`, but without saving the result.
+fn complete_game_config(mut config: GameConfig, overrides: &ConfigOverrides, previous: &GameConfig) -> GameConfig {
+    // Session-only settings come from the command line and are never prompted for.
+    config.target_wpm = overrides.target_wpm;
+    config.min_accuracy = overrides.min_accuracy;
+    config.max_consecutive_errors = overrides.max_consecutive_errors;
+    config.strict = overrides.strict.unwrap_or(false);
+    config.blind = overrides.blind.unwrap_or(false);
+    config.time_attack = overrides.time_attack.unwrap_or(false);
+    config.no_backspace = overrides.no_backspace.unwrap_or(false);
+    config.restart_on_error = overrides.restart_on_error.unwrap_or(false);
+    config.retry_mistyped = overrides.retry_mistyped.unwrap_or(false);
+    config.cumulative = overrides.cumulative.unwrap_or(false);
+    config.weak_keys = overrides.weak_keys.unwrap_or(false);
+    config.marathon = overrides.marathon;
+    config.quote_number = overrides.quote_number;
+    config.seed = overrides.seed;
+    config.csv_path = overrides.csv_path.clone();
+    config.record_path = overrides.record_path.clone();
+    // Sticky settings aren't prompted for either, but fall back to `previous`.
+    config.error_feedback = overrides.error_feedback.clone().unwrap_or_else(|| previous.error_feedback.clone());
+    config.wpm_scheme = overrides.wpm_scheme.clone().unwrap_or_else(|| previous.wpm_scheme.clone());
+    config.error_penalty = overrides.error_penalty.unwrap_or(previous.error_penalty);
+    config.wpm_smoothing = overrides.wpm_smoothing.unwrap_or(previous.wpm_smoothing);
+    config.finish_warning_words = overrides.finish_warning_words.unwrap_or(previous.finish_warning_words);
+    config.window_words = overrides.window_words.map_or(previous.window_words, |count| (count > 0).then_some(count));
+    config.text_view = overrides.text_view.clone().unwrap_or_else(|| previous.text_view.clone());
+    config.text_columns = overrides.text_columns.unwrap_or(previous.text_columns);
+    config.space_policy = overrides.space_policy.clone().unwrap_or_else(|| previous.space_policy.clone());
+    config.show_cursor = overrides.show_cursor.unwrap_or(previous.show_cursor);
+    config.enter_finishes = overrides.enter_finishes.unwrap_or(previous.enter_finishes);
+    config.idle_timeout = overrides.idle_timeout.map_or(previous.idle_timeout, |seconds| (seconds > 0).then_some(seconds));
+    config.max_minutes = overrides.max_minutes.map_or(previous.max_minutes, |minutes| (minutes > 0).then_some(minutes));
+    config.min_word_length = overrides.min_word_length.map_or(previous.min_word_length, |chars| (chars > 0).then_some(chars));
+    config.max_word_length = overrides.max_word_length.map_or(previous.max_word_length, |chars| (chars > 0).then_some(chars));
+    config.show_banner = previous.show_banner;
+    config
+}
+
+/// Runs the interactive prompts, pre-selecting the choices stored in `previous`.
+fn prompt_game_config(overrides: &ConfigOverrides, previous: &GameConfig) -> Result<GameConfig> {
     let theme = ColorfulTheme::default(); // Use dialoguer's colorful theme for prompts.
     let mut config = GameConfig::new(); // Initialize with default config.
 
     // 1. Pick game type
-    let game_types = ["Time", "Words", "Quote"];
-    let selection_idx = Select::with_theme(&theme)
-        .with_prompt("Pick a game type:")
-        .items(&game_types)
-        .default(0) // Default to "Time"
-        .interact()?; // This can return an error if the user cancels (e.g., Esc)
-
-    match game_types[selection_idx] {
+    let game_types = ["Time", "Words", "Quote", "Zen"];
+    let game_type_name = match overrides.implied_game_type() {
+        Some(GameType::Time) => "Time",
+        Some(GameType::Words) => "Words",
+        Some(GameType::Quote) => "Quote",
+        Some(GameType::Zen) => "Zen",
+        // Custom and Code modes always come with their text, so `to_complete_config` handles them without prompting.
+        Some(GameType::Custom) | Some(GameType::Code) => {
+            bail!("Custom and Code modes need the text to type, given with --text or --text-file")
+        }
+        None => {
+            let selection_idx = Select::with_theme(&theme)
+                .with_prompt("Pick a game type:")
+                .items(&game_types)
+                .default(match previous.game_type {
+                    GameType::Time => 0,
+                    GameType::Words => 1,
+                    GameType::Quote => 2,
+                    GameType::Zen => 3,
+                    GameType::Custom | GameType::Code => 0,
+                })
+                .interact()?; // This can return an error if the user cancels (e.g., Esc)
+            game_types[selection_idx]
+        }
+    };
+
+    match game_type_name {
         "Time" => {
             config.game_type = GameType::Time;
-            let time_options = ["15s", "30s", "60s", "120s"];
+            if let Some(time_seconds) = overrides.time_seconds {
+                config.time_seconds = Some(time_seconds);
+                config.word_count = None;
+                return finish_with_difficulty(config, overrides, previous, &theme);
+            }
+            let time_options: Vec<String> = TIME_LIMIT_CHOICES.iter().map(|seconds| format!("{}s", seconds)).collect();
             let time_selection_idx = Select::with_theme(&theme)
                 .with_prompt("Pick a time limit:")
                 .items(&time_options)
-                .default(1) // Default to "30s"
+                .default(previous.time_seconds
+                    .and_then(|t| TIME_LIMIT_CHOICES.iter().position(|&seconds| seconds == t))
+                    .unwrap_or(1)) // Previous choice, else "30s"
                 .interact()?;
-            
-            // Parse the selected time string (e.g., "30s") into u32.
-            let time_str = time_options[time_selection_idx].trim_end_matches('s');
-            config.time_seconds = Some(time_str.parse::<u32>()?); // This can fail if parse is invalid.
+            config.time_seconds = Some(TIME_LIMIT_CHOICES[time_selection_idx]);
             config.word_count = None; // Ensure word_count is None for Time mode.
         }
         "Words" => {
             config.game_type = GameType::Words;
-            let word_count_options = ["10", "20", "30", "40", "50"];
+            if let Some(word_count) = overrides.word_count {
+                config.word_count = Some(word_count);
+                config.time_seconds = None;
+                return finish_with_difficulty(config, overrides, previous, &theme);
+            }
             let count_selection_idx = Select::with_theme(&theme)
                 .with_prompt("Pick a number of words:")
-                .items(&word_count_options)
-                .default(1) // Default to "20" words
+                .items(&WORD_COUNT_CHOICES)
+                .default(previous.word_count
+                    .and_then(|n| WORD_COUNT_CHOICES.iter().position(|&count| count == n))
+                    .unwrap_or(1)) // Previous choice, else 20 words
                 .interact()?;
-
-            // Parse the selected word count string into u32.
-            config.word_count = Some(word_count_options[count_selection_idx].parse::<u32>()?);
+            config.word_count = Some(WORD_COUNT_CHOICES[count_selection_idx]);
             config.time_seconds = None; // Ensure time_seconds is None for Words mode.
         }
         "Quote" => {
             config.game_type = GameType::Quote;
-            // For Quote mode, specific options like choosing a quote source or length
-            // could be added here in the future.
             config.time_seconds = None;
             config.word_count = None;
-            // Inform user that quote selection is not yet implemented if desired.
-            // println!("{}", "Quote mode selected. Specific quote selection will be added later.".italic());
+            // A quote picked with --quote has its own length, so there is nothing to ask.
+            if overrides.quote_number.is_some() {
+                config.quote_length = previous.quote_length.clone();
+            } else {
+                let lengths = [QuoteLength::Any, QuoteLength::Short, QuoteLength::Medium, QuoteLength::Long];
+                let length_selection_idx = Select::with_theme(&theme)
+                    .with_prompt("Pick a quote length:")
+                    .items(&["Any", "Short (up to 20 words)", "Medium (21-60 words)", "Long (over 60 words)"])
+                    .default(lengths.iter().position(|length| *length == previous.quote_length).unwrap_or(0))
+                    .interact()?;
+                config.quote_length = lengths[length_selection_idx].clone();
+            }
+        }
+        "Zen" => {
+            config.game_type = GameType::Zen;
+            // Zen mode has no limit; the run lasts until the user presses Esc.
+            config.time_seconds = None;
+            config.word_count = None;
         }
         _ => unreachable!(), // This case should not be reached due to `Select` behavior.
     }
 
+    finish_with_difficulty(config, overrides, previous, &theme)
+}
+
+/// Fills in the difficulty, either from `overrides` or by prompting, and returns the finished config.
+fn finish_with_difficulty(
+    mut config: GameConfig,
+    overrides: &ConfigOverrides,
+    previous: &GameConfig,
+    theme: &ColorfulTheme,
+) -> Result<GameConfig> {
+    if let Some(difficulty) = &overrides.difficulty {
+        config.difficulty = difficulty.clone();
+        return Ok(config);
+    }
+
     // 2. Pick difficulty
     let difficulties = ["Easy", "Medium", "Hard"];
-    let difficulty_selection_idx = Select::with_theme(&theme)
+    let difficulty_selection_idx = Select::with_theme(theme)
         .with_prompt("Pick a difficulty:")
         .items(&difficulties)
-        .default(1) // Default to "Medium"
+        .default(match previous.difficulty {
+            Difficulty::Easy => 0,
+            Difficulty::Medium => 1,
+            Difficulty::Hard => 2,
+        })
         .interact()?;
 
     config.difficulty = match difficulties[difficulty_selection_idx] {
@@ -140,3 +1108,305 @@ pub fn get_game_config() -> Result<GameConfig> {
     
     Ok(config) // Return the populated GameConfig.
 }
+
+/// Asks a yes/no question about the word list, unless `overridden` already answers it.
+///
+/// These options only transform generated word lists; Quote mode always uses the quote
+/// text as written, and key-row drills only the drilled letters, so they are never asked
+/// there and are always off.
+fn prompt_word_option(config: &GameConfig, overridden: Option<bool>, prompt: &str, previous: bool) -> Result<bool> {
+    if config.game_type == GameType::Quote || config.key_rows.is_some() {
+        return Ok(false);
+    }
+    if let Some(enabled) = overridden {
+        return Ok(enabled);
+    }
+    let enabled = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(previous)
+        .interact()?;
+    Ok(enabled)
+}
+
+/// Asks which language pack to draw words from, unless `overrides` already names one.
+///
+/// Quote mode does not use word lists, so it is not asked there and the previous choice is kept.
+/// Nothing is asked either when only one language is available.
+fn prompt_language(config: &GameConfig, overrides: &ConfigOverrides, previous: &str) -> Result<String> {
+    if let Some(language) = &overrides.language {
+        return Ok(language.clone());
+    }
+    let languages = data_loader::available_languages();
+    if config.game_type == GameType::Quote || languages.len() <= 1 {
+        return Ok(previous.to_string());
+    }
+    let selection_idx = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Pick a language:")
+        .items(&languages)
+        .default(languages.iter().position(|language| language == previous).unwrap_or(0))
+        .interact()?;
+    Ok(languages[selection_idx].clone())
+}
+
+/// Asks how the current character should be marked, unless `overrides` already says.
+fn prompt_caret_style(overrides: &ConfigOverrides, previous: &CaretStyle) -> Result<CaretStyle> {
+    if let Some(caret_style) = &overrides.caret_style {
+        return Ok(caret_style.clone());
+    }
+    let styles = [CaretStyle::Highlight, CaretStyle::Block, CaretStyle::Underline, CaretStyle::Bar];
+    let selection_idx = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Pick a caret style:")
+        .items(&["Highlight", "Block", "Underline", "Bar"])
+        .default(styles.iter().position(|style| style == previous).unwrap_or(0))
+        .interact()?;
+    Ok(styles[selection_idx].clone())
+}
+
+/// Asks which color theme to use, unless `overrides` already says.
+fn prompt_theme(overrides: &ConfigOverrides, previous: &ThemeName) -> Result<ThemeName> {
+    if let Some(theme) = &overrides.theme {
+        return Ok(theme.clone());
+    }
+    let themes = [ThemeName::Default, ThemeName::Solarized, ThemeName::Mono, ThemeName::HighContrast];
+    let selection_idx = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Pick a color theme:")
+        .items(&["Default", "Solarized", "Mono", "High contrast"])
+        .default(themes.iter().position(|theme| theme == previous).unwrap_or(0))
+        .interact()?;
+    Ok(themes[selection_idx].clone())
+}
+
+/// Asks whether to count down before the timer starts, unless `overrides` already says.
+fn prompt_countdown(overrides: &ConfigOverrides, previous: bool) -> Result<bool> {
+    if let Some(countdown) = overrides.countdown {
+        return Ok(countdown);
+    }
+    let countdown = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Count down 3-2-1 before starting?")
+        .default(previous)
+        .interact()?;
+    Ok(countdown)
+}
+
+/// What to do after a game has finished, as chosen in the menu.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NextAction {
+    /// Play another game with the same settings.
+    NewGame,
+    /// Go through the configuration prompts again, then play.
+    ChangeSettings,
+    /// Leave the program.
+    Quit,
+}
+
+/// Asks what to do after a game: play again, change settings or quit.
+pub fn prompt_next_action() -> Result<NextAction> {
+    let actions = [NextAction::NewGame, NextAction::ChangeSettings, NextAction::Quit];
+    let selection_idx = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("What next?")
+        .items(&["New game", "Change settings", "Quit"])
+        .default(0)
+        .interact()?;
+    Ok(actions[selection_idx].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_flags_still_restore_sticky_settings() {
+        let previous = GameConfig { error_feedback: ErrorFeedback::Bell, text_columns: 2, ..GameConfig::new() };
+        let overrides = ConfigOverrides { game_type: Some(GameType::Quote), ..ConfigOverrides::default() };
+        let config = complete_game_config(overrides.to_complete_config().unwrap(), &overrides, &previous);
+        assert_eq!((config.game_type, config.error_feedback, config.text_columns), (GameType::Quote, ErrorFeedback::Bell, 2));
+    }
+
+    #[test]
+    fn session_flags_survive_a_settings_change() {
+        let overrides = ConfigOverrides {
+            time_seconds: Some(60), difficulty: Some(Difficulty::Easy), quote_number: Some(3),
+            target_wpm: Some(70), strict: Some(true), max_minutes: Some(5), seed: Some(9),
+            csv_path: Some(PathBuf::from("runs.csv")), record_path: Some(PathBuf::from("run.jsonl")),
+            error_feedback: Some(ErrorFeedback::Bell),
+            ..ConfigOverrides::default()
+        };
+        let cleared = overrides.for_new_settings();
+        assert_eq!(cleared.implied_game_type(), None, "the game is chosen again");
+        assert_eq!((cleared.time_seconds, cleared.difficulty.clone(), cleared.quote_number), (None, None, None));
+
+        let chosen = GameConfig { game_type: GameType::Words, word_count: Some(25), time_seconds: None, ..GameConfig::new() };
+        let config = complete_game_config(chosen, &cleared, &GameConfig::new());
+        assert_eq!((config.game_type, config.word_count), (GameType::Words, Some(25)));
+        assert_eq!((config.target_wpm, config.strict, config.max_minutes, config.seed), (Some(70), true, Some(5), Some(9)));
+        assert_eq!(config.csv_path, Some(PathBuf::from("runs.csv")));
+        assert_eq!(config.record_path, Some(PathBuf::from("run.jsonl")));
+        assert_eq!(config.error_feedback, ErrorFeedback::Bell);
+    }
+
+    #[test]
+    fn goals_pass_at_and_above_their_boundaries() {
+        let mut config = GameConfig::new();
+        assert_eq!(config.meets_goals(10.0, 50.0), None);
+
+        config.target_wpm = Some(80);
+        assert_eq!(config.meets_goals(79.99, 100.0), Some(false));
+        assert_eq!(config.meets_goals(80.0, 100.0), Some(true));
+        assert_eq!(config.meets_goals(80.01, 0.0), Some(true));
+
+        config.min_accuracy = Some(95.0);
+        assert_eq!(config.meets_goals(80.0, 94.99), Some(false));
+        assert_eq!(config.meets_goals(80.0, 95.0), Some(true));
+        assert_eq!(config.meets_goals(79.0, 95.0), Some(false));
+
+        config.target_wpm = None;
+        assert_eq!(config.meets_goals(0.0, 95.0), Some(true));
+    }
+
+    #[test]
+    fn defaults_complete_any_partial_settings() {
+        let config = ConfigOverrides::default().with_defaults().to_complete_config().unwrap();
+        assert_eq!((config.game_type, config.time_seconds, config.difficulty), (GameType::Time, Some(30), Difficulty::Medium));
+        let words = ConfigOverrides { word_count: Some(50), ..ConfigOverrides::default() };
+        let config = words.with_defaults().to_complete_config().unwrap();
+        assert_eq!((config.game_type, config.word_count, config.difficulty), (GameType::Words, Some(50), Difficulty::Medium));
+        let words = ConfigOverrides { game_type: Some(GameType::Words), difficulty: Some(Difficulty::Hard), ..ConfigOverrides::default() };
+        let config = words.with_defaults().to_complete_config().unwrap();
+        assert_eq!((config.word_count, config.difficulty), (Some(DEFAULT_WORD_COUNT), Difficulty::Hard));
+        let zen = ConfigOverrides { game_type: Some(GameType::Zen), ..ConfigOverrides::default() };
+        assert_eq!(zen.with_defaults().to_complete_config().unwrap().time_seconds, None);
+        let custom = ConfigOverrides { game_type: Some(GameType::Custom), ..ConfigOverrides::default() };
+        assert!(custom.with_defaults().to_complete_config().is_none());
+    }
+
+    #[test]
+    fn goal_flags_are_validated() {
+        let valid = ConfigOverrides { target_wpm: Some(80), min_accuracy: Some(95.0), ..ConfigOverrides::default() };
+        assert!(valid.validate().is_ok());
+        assert!(ConfigOverrides { target_wpm: Some(0), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(ConfigOverrides { min_accuracy: Some(100.5), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(ConfigOverrides { min_accuracy: Some(-1.0), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(ConfigOverrides { max_consecutive_errors: Some(0), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(ConfigOverrides { max_consecutive_errors: Some(3), ..ConfigOverrides::default() }.validate().is_ok());
+    }
+
+    #[test]
+    fn error_penalty_is_validated() {
+        assert!(ConfigOverrides { error_penalty: Some(0.0), ..ConfigOverrides::default() }.validate().is_ok());
+        assert!(ConfigOverrides { error_penalty: Some(-0.5), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(ConfigOverrides { error_penalty: Some(f64::NAN), ..ConfigOverrides::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn wpm_smoothing_is_validated() {
+        assert!(ConfigOverrides { wpm_smoothing: Some(0.3), ..ConfigOverrides::default() }.validate().is_ok());
+        assert!(ConfigOverrides { wpm_smoothing: Some(1.0), ..ConfigOverrides::default() }.validate().is_ok());
+        assert!(ConfigOverrides { wpm_smoothing: Some(0.0), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(ConfigOverrides { wpm_smoothing: Some(1.5), ..ConfigOverrides::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn columns_are_validated() {
+        assert!(ConfigOverrides { text_columns: Some(3), ..ConfigOverrides::default() }.validate().is_ok());
+        assert!(ConfigOverrides { text_columns: Some(0), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(ConfigOverrides { text_columns: Some(4), ..ConfigOverrides::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn time_attack_is_validated() {
+        let time_attack = |overrides: ConfigOverrides| ConfigOverrides { time_attack: Some(true), ..overrides };
+        assert!(time_attack(ConfigOverrides { time_seconds: Some(60), ..ConfigOverrides::default() }).validate().is_ok());
+        assert!(time_attack(ConfigOverrides::default()).validate().is_ok());
+        assert!(time_attack(ConfigOverrides { word_count: Some(10), ..ConfigOverrides::default() }).validate().is_err());
+        assert!(time_attack(ConfigOverrides { game_type: Some(GameType::Zen), ..ConfigOverrides::default() }).validate().is_err());
+    }
+
+    #[test]
+    fn key_rows_are_validated() {
+        let drill = |overrides: ConfigOverrides| ConfigOverrides { key_rows: Some(KeyRows::Home), ..overrides };
+        assert!(drill(ConfigOverrides { word_count: Some(10), ..ConfigOverrides::default() }).validate().is_ok());
+        assert!(drill(ConfigOverrides { game_type: Some(GameType::Quote), ..ConfigOverrides::default() }).validate().is_err());
+        assert!(drill(ConfigOverrides { punctuation: Some(true), ..ConfigOverrides::default() }).validate().is_err());
+    }
+
+    #[test]
+    fn weak_keys_are_validated() {
+        let weak_keys = |overrides: ConfigOverrides| ConfigOverrides { weak_keys: Some(true), ..overrides };
+        assert!(weak_keys(ConfigOverrides { time_seconds: Some(60), ..ConfigOverrides::default() }).validate().is_ok());
+        assert!(weak_keys(ConfigOverrides { game_type: Some(GameType::Quote), ..ConfigOverrides::default() }).validate().is_err());
+        assert!(weak_keys(ConfigOverrides { key_rows: Some(KeyRows::Home), ..ConfigOverrides::default() }).validate().is_err());
+    }
+
+    #[test]
+    fn custom_text_implies_custom_mode_and_needs_no_prompt() {
+        let text = "Hello, World! naïve café".to_string();
+        let overrides = ConfigOverrides { custom_text: Some(text.clone()), ..ConfigOverrides::default() };
+        assert!(overrides.validate().is_ok());
+        assert_eq!(overrides.implied_game_type(), Some(GameType::Custom));
+        let config = overrides.to_complete_config().unwrap();
+        assert_eq!(config.game_type, GameType::Custom);
+        assert_eq!(config.custom_text, Some(text));
+        assert_eq!((config.time_seconds, config.word_count), (None, None));
+    }
+
+    #[test]
+    fn zero_window_words_fits_the_terminal() {
+        let overrides = |window_words| ConfigOverrides {
+            time_seconds: Some(30), difficulty: Some(Difficulty::Easy), window_words, ..ConfigOverrides::default()
+        };
+        assert_eq!(overrides(Some(8)).to_complete_config().unwrap().window_words, Some(8));
+        assert_eq!(overrides(Some(0)).to_complete_config().unwrap().window_words, None);
+        assert_eq!(overrides(None).to_complete_config().unwrap().window_words, None);
+    }
+
+    #[test]
+    fn explicit_word_lengths_win_over_difficulty() {
+        let bounds = |difficulty, min_word_length, max_word_length| {
+            GameConfig { difficulty, min_word_length, max_word_length, ..GameConfig::new() }.word_length_bounds()
+        };
+        assert_eq!(bounds(Difficulty::Easy, None, None), (0, Some(EASY_MAX_WORD_CHARS)));
+        assert_eq!(bounds(Difficulty::Easy, Some(3), None), (3, Some(EASY_MAX_WORD_CHARS)));
+        assert_eq!(bounds(Difficulty::Easy, Some(7), None), (7, None));
+        assert_eq!(bounds(Difficulty::Easy, None, Some(10)), (0, Some(10)));
+        assert_eq!(bounds(Difficulty::Hard, Some(4), Some(6)), (4, Some(6)));
+
+        let overrides = |min_word_length, max_word_length| ConfigOverrides {
+            time_seconds: Some(30), min_word_length, max_word_length, ..ConfigOverrides::default()
+        };
+        assert!(overrides(Some(4), Some(6)).validate().is_ok());
+        assert!(overrides(Some(6), Some(6)).validate().is_ok());
+        assert!(overrides(Some(7), Some(6)).validate().is_err());
+        assert!(overrides(Some(7), Some(0)).validate().is_ok());
+        let quote = ConfigOverrides { game_type: Some(GameType::Quote), ..overrides(Some(4), None) };
+        assert!(quote.validate().is_err());
+    }
+
+    #[test]
+    fn custom_text_flags_are_validated() {
+        let text = Some("some text".to_string());
+        let with_text = |overrides: ConfigOverrides| ConfigOverrides { custom_text: text.clone(), ..overrides };
+        assert!(ConfigOverrides { game_type: Some(GameType::Custom), ..ConfigOverrides::default() }.validate().is_err());
+        assert!(with_text(ConfigOverrides { game_type: Some(GameType::Quote), ..ConfigOverrides::default() }).validate().is_err());
+        assert!(with_text(ConfigOverrides { word_count: Some(10), ..ConfigOverrides::default() }).validate().is_err());
+        assert!(with_text(ConfigOverrides { punctuation: Some(true), ..ConfigOverrides::default() }).validate().is_err());
+        assert!(with_text(ConfigOverrides { game_type: Some(GameType::Custom), ..ConfigOverrides::default() }).validate().is_ok());
+        assert!(with_text(ConfigOverrides { game_type: Some(GameType::Code), ..ConfigOverrides::default() }).validate().is_ok());
+        assert!(ConfigOverrides { game_type: Some(GameType::Code), ..ConfigOverrides::default() }.validate().is_err());
+        let blank = ConfigOverrides { custom_text: Some(" \n\t".to_string()), ..ConfigOverrides::default() };
+        assert!(blank.validate().is_err());
+    }
+
+    #[test]
+    fn quote_length_buckets_split_at_20_and_60_words() {
+        assert!(QuoteLength::Short.includes(1));
+        assert!(QuoteLength::Short.includes(20));
+        assert!(!QuoteLength::Short.includes(21));
+        assert!(!QuoteLength::Medium.includes(20));
+        assert!(QuoteLength::Medium.includes(21));
+        assert!(QuoteLength::Medium.includes(60));
+        assert!(!QuoteLength::Medium.includes(61));
+        assert!(!QuoteLength::Long.includes(60));
+        assert!(QuoteLength::Long.includes(61));
+        assert!([1, 20, 21, 60, 61, 500].iter().all(|&n| QuoteLength::Any.includes(n)));
+    }
+}
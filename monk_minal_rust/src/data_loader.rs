@@ -3,51 +3,258 @@
 //! This module is responsible for loading external data required by the MonkMinal Rust application.
 //! Currently, it handles loading lists of words for typing tests and quotes for the quote typing mode.
 //! Data is loaded from JSON files embedded in the binary at compile time using `include_str!`.
+//! A data directory (`--data-dir`, or else the platform data dir) can hold its own `allWords.json`
+//! and `quotes.json`, which replace the embedded copies when present; see `data_file`.
+//! A custom word list can also be loaded from a file at runtime with `load_words_from_path`.
+//! JSON word lists may give each word a frequency weight, so common words are picked more often;
+//! see `WordList`.
+//!
+//! Word lists for other languages ("language packs") are either embedded alongside the English
+//! list or dropped into the `languages/` folder of the app config directory as `<name>.json`
+//! or `<name>.txt`. See `available_languages` and `load_words_for_language`.
 
-use anyhow::Result;
+use crate::config::{app_config_dir, app_data_dir, Difficulty, QuoteLength};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Name of the language used when none is chosen, matching the embedded `allWords.json`.
+pub const DEFAULT_LANGUAGE: &str = "english";
+/// Name of the folder (inside the app config dir) scanned for extra language packs.
+const LANGUAGES_DIR_NAME: &str = "languages";
+/// File name of the English word list, embedded and in a data directory.
+const WORDS_FILE_NAME: &str = "allWords.json";
+/// File name of the quotes, embedded and in a data directory.
+const QUOTES_FILE_NAME: &str = "quotes.json";
+/// The embedded English word list.
+const ENGLISH_WORDS_JSON: &str = include_str!("../../data/allWords.json");
+/// Language packs compiled into the binary, as `(name, words JSON)` pairs.
+const EMBEDDED_LANGUAGES: [(&str, &str); 3] = [
+    (DEFAULT_LANGUAGE, ENGLISH_WORDS_JSON),
+    ("german", include_str!("../../data/languages/german.json")),
+    ("spanish", include_str!("../../data/languages/spanish.json")),
+];
 
 /// Represents the structure of `allWords.json`.
 ///
-/// Contains a single field `words` which is a vector of strings.
+/// Contains a field `words` which is a vector of strings, and optionally `weights`, how often
+/// each of those words occurs in real text.
 #[derive(Deserialize, Debug)]
 pub struct AllWords {
     /// A list of words to be used in typing games.
     words: Vec<String>,
+    /// The relative frequency of each word, in the same order as `words`.
+    #[serde(default)]
+    weights: Option<Vec<f64>>,
+}
+
+/// A loaded word list, with the frequency weights of its words if the list gives them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WordList {
+    /// The words, in the order of the file.
+    pub words: Vec<String>,
+    /// How often each word is picked relative to the others; empty for lists without weights,
+    /// whose words are all picked alike.
+    pub weights: WordWeights,
+}
+
+impl WordList {
+    /// Creates a list of `words` without weights.
+    pub fn unweighted(words: Vec<String>) -> Self {
+        WordList { words, weights: WordWeights::default() }
+    }
+}
+
+/// Frequency weights of words, so common words can be picked more often than rare ones.
+///
+/// The weights are shared rather than copied, since they travel with every clone of the game
+/// config and a real frequency list has thousands of entries.
+#[derive(Clone, Default, PartialEq)]
+pub struct WordWeights(Arc<HashMap<String, f64>>);
+
+impl WordWeights {
+    /// Pairs each of `words` with the weight at the same position in `weights`.
+    pub fn new(words: &[String], weights: &[f64]) -> Self {
+        WordWeights(Arc::new(words.iter().cloned().zip(weights.iter().copied()).collect()))
+    }
+
+    /// Returns whether no word has a weight.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the weight of `word`, or 1 if it has none.
+    pub fn weight(&self, word: &str) -> f64 {
+        self.0.get(word).copied().unwrap_or(1.0)
+    }
+}
+
+impl fmt::Debug for WordWeights {
+    // The whole table would swamp any log line that prints a game config.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WordWeights({} words)", self.0.len())
+    }
 }
 
 /// Represents the structure of a single quote in `quotes.json`.
 ///
-/// Each quote has a text content and a source.
-#[derive(Deserialize, Debug, Clone)]
+/// Each quote has a text content and a source. The other fields are optional metadata, so
+/// quote files without them still load.
+#[derive(Deserialize, Debug, Clone, Default)]
 pub struct Quote {
     /// The text content of the quote.
     pub text: String,
     /// The source or author of the quote.
     pub source: String,
+    /// A number identifying the quote within its file.
+    #[serde(default)]
+    pub id: Option<u64>,
+    /// The length of the quote, as declared in the file.
+    #[serde(default)]
+    pub length: Option<DeclaredLength>,
+    /// The language the quote is written in.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+/// The `length` of a quote as declared in a quotes file.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum DeclaredLength {
+    /// A length in characters, as `quotes.json` gives it.
+    Characters(usize),
+    /// A length category such as "short", matched against `QuoteLength` ignoring case.
+    Category(String),
+}
+
+impl Quote {
+    /// Returns the number of words in the quote's text.
+    pub fn word_count(&self) -> usize {
+        self.text.split_whitespace().count()
+    }
+
+    /// Returns the length category declared for the quote, if it names one of Short, Medium
+    /// or Long. Character counts declare no category, since categories are counted in words.
+    pub fn declared_category(&self) -> Option<QuoteLength> {
+        match &self.length {
+            Some(DeclaredLength::Category(name)) => QuoteLength::from_str(name, true)
+                .ok()
+                .filter(|length| *length != QuoteLength::Any),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the quote belongs to the `length` category: the declared one if there is
+    /// one (see `declared_category`), or else the one its word count falls into.
+    pub fn has_length(&self, length: &QuoteLength) -> bool {
+        match self.declared_category() {
+            Some(declared) => *length == QuoteLength::Any || declared == *length,
+            None => length.includes(self.word_count()),
+        }
+    }
 }
 
 // Note on `include_str!`:
 // The paths used in `include_str!` are relative to the current source file (`src/data_loader.rs`).
 // - `../../data/allWords.json` translates to `<project_root>/data/allWords.json`.
 // - `../../data/quotes.json` translates to `<project_root>/data/quotes.json`.
+// - `../../data/languages/*.json` translates to `<project_root>/data/languages/*.json`.
 // Cargo is configured to rebuild the crate if these external files change, ensuring
 // the binary always includes the latest version of the data.
 
+/// Parses a word list in the `{ "words": [...] }` shape of `allWords.json` and checks its contents.
+///
+/// The `words` array must be present and non-empty, and every entry must be a single word:
+/// not empty and free of whitespace, since each entry is typed as one word. An optional
+/// `weights` array gives each word a positive frequency weight, in the same order. `name`
+/// identifies the list in the error messages, e.g. "allWords.json: 'words' array is empty".
+pub fn parse_word_list(json: &str, name: &str) -> Result<WordList> {
+    let value: serde_json::Value = serde_json::from_str(json)
+        .with_context(|| format!("{}: not valid JSON", name))?;
+    if value.get("words").is_none() {
+        bail!("{}: missing 'words' key, expected {{ \"words\": [...] }}", name);
+    }
+    let all_words_data: AllWords = serde_json::from_value(value)
+        .with_context(|| format!("{}: 'words' must be an array of strings, and 'weights' an array of numbers", name))?;
+    let words = all_words_data.words;
+    if words.is_empty() {
+        bail!("{}: 'words' array is empty; add at least one word, e.g. {{ \"words\": [\"hello\"] }}", name);
+    }
+    if let Some(index) = words.iter().position(|word| word.is_empty()) {
+        bail!("{}: entry {} of 'words' is an empty string", name, index);
+    }
+    if let Some((index, word)) = words.iter().enumerate().find(|(_, word)| word.chars().any(char::is_whitespace)) {
+        bail!("{}: entry {} of 'words' ({:?}) contains whitespace", name, index, word);
+    }
+    let Some(weights) = all_words_data.weights else {
+        return Ok(WordList::unweighted(words));
+    };
+    if weights.len() != words.len() {
+        bail!("{}: 'weights' has {} entries but 'words' has {}; give one weight per word", name, weights.len(), words.len());
+    }
+    if let Some((index, weight)) = weights.iter().enumerate().find(|(_, weight)| !(weight.is_finite() && **weight > 0.0)) {
+        bail!("{}: entry {} of 'weights' ({}) is not a positive number", name, index, weight);
+    }
+    let weights = WordWeights::new(&words, &weights);
+    Ok(WordList { words, weights })
+}
+
 /// Loads all words from the embedded `allWords.json` file.
 ///
 /// The JSON file is expected to have a single key "words" containing a list of strings.
 ///
 /// # Returns
 ///
-/// Returns a `Result<Vec<String>>` which is `Ok` with a vector of words if loading and
-/// parsing are successful, or an `Err` if the file cannot be read or parsed.
-pub fn load_all_words() -> Result<Vec<String>> {
+/// Returns a `Result<WordList>` which is `Ok` with the words if loading and
+/// parsing are successful, or an `Err` if the file cannot be parsed or fails validation
+/// (see `parse_word_list`).
+pub fn load_all_words() -> Result<WordList> {
     // Embed the content of allWords.json directly into the binary at compile time.
     // If allWords.json changes, Cargo will rebuild the crate.
-    let words_json = include_str!("../../data/allWords.json"); 
-    let all_words_data: AllWords = serde_json::from_str(words_json)?; // Parse the JSON string.
-    Ok(all_words_data.words) // Return the list of words.
+    parse_word_list(ENGLISH_WORDS_JSON, WORDS_FILE_NAME)
+}
+
+/// Returns the data directory searched when `--data-dir` is not given, if the platform has one.
+pub fn default_data_dir() -> Option<PathBuf> {
+    app_data_dir()
+}
+
+/// Returns the path of `file_name` inside `data_dir`, if the directory is given and has such a file.
+///
+/// `None` means the embedded copy should be used instead.
+pub fn data_file(data_dir: Option<&Path>, file_name: &str) -> Option<PathBuf> {
+    data_dir.map(|dir| dir.join(file_name)).filter(|path| path.is_file())
+}
+
+/// Loads the English word list from `allWords.json` in `data_dir` if there is one, or else
+/// the embedded copy.
+///
+/// A data file that is present but broken is an error rather than a silent fallback, so a typo
+/// in it is noticed.
+pub fn load_all_words_from(data_dir: Option<&Path>) -> Result<WordList> {
+    match data_file(data_dir, WORDS_FILE_NAME) {
+        Some(path) => load_words_from_path(&path),
+        None => load_all_words(),
+    }
+}
+
+/// Parses a list of quotes in the shape of `quotes.json`: an array of objects with "text" and
+/// "source" fields.
+///
+/// The array must not be empty, so a broken quotes file is caught when it is loaded rather
+/// than when Quote mode first picks a quote. `name` identifies the file in the error messages.
+pub fn parse_quotes(json: &str, name: &str) -> Result<Vec<Quote>> {
+    let quotes: Vec<Quote> = serde_json::from_str(json)
+        .with_context(|| format!("{}: expected an array of {{ \"text\": ..., \"source\": ... }} objects", name))?;
+    if quotes.is_empty() {
+        bail!("{}: the quote list is empty; add at least one {{ \"text\": ..., \"source\": ... }} object", name);
+    }
+    Ok(quotes)
 }
 
 /// Loads all quotes from the embedded `quotes.json` file.
@@ -57,11 +264,363 @@ pub fn load_all_words() -> Result<Vec<String>> {
 /// # Returns
 ///
 /// Returns a `Result<Vec<Quote>>` which is `Ok` with a vector of quotes if loading and
-/// parsing are successful, or an `Err` if the file cannot be read or parsed.
+/// parsing are successful, or an `Err` if the file cannot be parsed or has no quotes
+/// (see `parse_quotes`).
 pub fn load_quotes() -> Result<Vec<Quote>> {
     // Embed the content of quotes.json directly into the binary at compile time.
     // If quotes.json changes, Cargo will rebuild the crate.
-    let quotes_json = include_str!("../../data/quotes.json");
-    let quotes_data: Vec<Quote> = serde_json::from_str(quotes_json)?; // Parse the JSON string.
-    Ok(quotes_data) // Return the list of quotes.
+    parse_quotes(include_str!("../../data/quotes.json"), QUOTES_FILE_NAME)
+}
+
+/// Loads the quotes from `quotes.json` in `data_dir` if there is one, or else the embedded copy.
+///
+/// As with `load_all_words_from`, a data file that is present but broken is an error.
+pub fn load_quotes_from(data_dir: Option<&Path>) -> Result<Vec<Quote>> {
+    let Some(path) = data_file(data_dir, QUOTES_FILE_NAME) else {
+        return load_quotes();
+    };
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read quotes file {}", path.display()))?;
+    parse_quotes(&contents, &path.display().to_string())
+}
+
+/// Loads a word list from a file at `path`.
+///
+/// Two formats are accepted:
+/// - JSON in the same `{ "words": [...] }` shape as `allWords.json`, used when the file has a
+///   `.json` extension or its content starts with `{`. Only this format can give weights.
+/// - Plain text with one word per line. Surrounding whitespace is trimmed and blank lines are
+///   skipped, but, as in JSON lists, a line with whitespace inside is an error.
+///
+/// # Returns
+///
+/// Returns an `Err` naming `path` if the file cannot be read, is malformed, or contains no words.
+pub fn load_words_from_path(path: &Path) -> Result<WordList> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read word list {}", path.display()))?;
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        || contents.trim_start().starts_with('{');
+    let words = if is_json {
+        parse_word_list(&contents, &path.display().to_string())?
+    } else {
+        let lines = contents.lines().enumerate().map(|(i, line)| (i, line.trim())).filter(|(_, line)| !line.is_empty());
+        let mut words = Vec::new();
+        for (i, word) in lines {
+            if word.chars().any(char::is_whitespace) {
+                bail!("{}: line {} ({:?}) contains whitespace; put one word on each line", path.display(), i + 1, word);
+            }
+            words.push(word.to_string());
+        }
+        WordList::unweighted(words)
+    };
+    if words.words.is_empty() {
+        bail!("Word list {} contains no words; put one word on each line", path.display());
+    }
+    Ok(words)
+}
+
+/// Describes anything in `words` that loads fine but changes how games play.
+///
+/// Currently that is a list without any words short enough for Easy or Medium difficulty,
+/// which then fall back to the whole list.
+pub fn word_list_warnings(words: &[String]) -> Vec<String> {
+    [Difficulty::Easy, Difficulty::Medium]
+        .iter()
+        .filter_map(|difficulty| {
+            let max_chars = difficulty.max_word_chars()?;
+            (!words.iter().any(|word| word.chars().count() <= max_chars)).then(|| {
+                format!("No words of {} characters or fewer: {:?} difficulty will use the whole list", max_chars, difficulty)
+            })
+        })
+        .collect()
+}
+
+/// Describes anything in `quotes` that loads fine but changes how games play.
+///
+/// Quotes without text or source, and quotes whose declared length does not match their text,
+/// are counted, and a missing length category is reported, since Quote mode then falls back
+/// to any quote.
+pub fn quote_warnings(quotes: &[Quote]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let without_text = quotes.iter().filter(|quote| quote.text.split_whitespace().next().is_none()).count();
+    if without_text > 0 {
+        warnings.push(format!("{} quotes have no text", without_text));
+    }
+    let without_source = quotes.iter().filter(|quote| quote.source.trim().is_empty()).count();
+    if without_source > 0 {
+        warnings.push(format!("{} quotes have no source", without_source));
+    }
+    let wrong_length = quotes.iter().filter(|quote| match &quote.length {
+        Some(DeclaredLength::Characters(chars)) => *chars != quote.text.chars().count(),
+        Some(DeclaredLength::Category(_)) => quote.declared_category().is_none(),
+        None => false,
+    }).count();
+    if wrong_length > 0 {
+        warnings.push(format!("{} quotes declare a length that does not match their text", wrong_length));
+    }
+    for length in [QuoteLength::Short, QuoteLength::Medium, QuoteLength::Long] {
+        if !quotes.iter().any(|quote| quote.has_length(&length)) {
+            warnings.push(format!("No {:?} quotes: Quote mode will pick from all quotes instead", length));
+        }
+    }
+    warnings
+}
+
+/// Returns the folder where users can add their own language packs, if the platform has a config dir.
+pub fn user_languages_dir() -> Option<PathBuf> {
+    app_config_dir().map(|dir| dir.join(LANGUAGES_DIR_NAME))
+}
+
+/// Finds a language pack named `name` (`<name>.json` or `<name>.txt`) in `languages_dir`, if any.
+fn user_language_file(name: &str, languages_dir: Option<&Path>) -> Option<PathBuf> {
+    let dir = languages_dir?;
+    ["json", "txt"].iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+}
+
+/// Lists the names of all language packs, embedded and user-supplied, sorted alphabetically.
+pub fn available_languages() -> Vec<String> {
+    available_languages_in(user_languages_dir().as_deref())
+}
+
+/// Like `available_languages`, with the user-supplied packs looked up in `languages_dir`.
+pub fn available_languages_in(languages_dir: Option<&Path>) -> Vec<String> {
+    let mut languages: Vec<String> = EMBEDDED_LANGUAGES.iter().map(|(name, _)| name.to_string()).collect();
+    if let Some(entries) = languages_dir.and_then(|dir| fs::read_dir(dir).ok()) {
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            let is_word_list = path.extension().is_some_and(|ext| ext == "json" || ext == "txt");
+            if let (true, Some(stem)) = (is_word_list, path.file_stem().and_then(|stem| stem.to_str())) {
+                languages.push(stem.to_string());
+            }
+        }
+    }
+    languages.sort();
+    languages.dedup();
+    languages
+}
+
+/// Loads the word list of the language pack called `name`.
+///
+/// A user-supplied pack takes precedence over an embedded one of the same name, and for English,
+/// an `allWords.json` in `data_dir` comes next (see `load_all_words_from`).
+/// Returns an `Err` if no such pack exists or it cannot be loaded.
+pub fn load_words_for_language(name: &str, data_dir: Option<&Path>) -> Result<WordList> {
+    load_words_for_language_in(name, user_languages_dir().as_deref(), data_dir)
+}
+
+/// Like `load_words_for_language`, with the user-supplied packs looked up in `languages_dir`.
+pub fn load_words_for_language_in(name: &str, languages_dir: Option<&Path>, data_dir: Option<&Path>) -> Result<WordList> {
+    if let Some(path) = user_language_file(name, languages_dir) {
+        return load_words_from_path(&path);
+    }
+    if name == DEFAULT_LANGUAGE {
+        return load_all_words_from(data_dir);
+    }
+    let Some((_, words_json)) = EMBEDDED_LANGUAGES.iter().find(|(embedded, _)| *embedded == name) else {
+        bail!("Language pack '{}' not found. Available: {}", name, available_languages_in(languages_dir).join(", "));
+    };
+    parse_word_list(words_json, &format!("embedded language pack '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Writes `contents` to a file named `name` in a temp dir unique to this test run.
+    fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("monk_minal_rust_words_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_json_and_plain_text_word_lists() {
+        let json = write_temp_file("list.json", r#"{ "words": ["alpha", "beta"] }"#);
+        assert_eq!(load_words_from_path(&json).unwrap().words, ["alpha", "beta"]);
+
+        // JSON content is detected even without a .json extension.
+        let json_no_ext = write_temp_file("list_json", r#"{ "words": ["gamma"] }"#);
+        assert_eq!(load_words_from_path(&json_no_ext).unwrap().words, ["gamma"]);
+
+        let text = write_temp_file("list.txt", "  café \n\nnaïve\r\nzebra\n");
+        assert_eq!(load_words_from_path(&text).unwrap().words, ["café", "naïve", "zebra"]);
+    }
+
+    #[test]
+    fn plain_text_lines_must_hold_one_word() {
+        let text = write_temp_file("two_per_line.txt", "alpha\n\nhello world\nzebra\n");
+        let message = format!("{:#}", load_words_from_path(&text).unwrap_err());
+        assert!(message.ends_with(r#"line 3 ("hello world") contains whitespace; put one word on each line"#), "{message}");
+        let tab = write_temp_file("tab.txt", "one\ttwo\n");
+        assert!(load_words_from_path(&tab).is_err());
+    }
+
+    /// Creates an empty directory unique to this test run and test.
+    fn empty_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("monk_minal_rust_data_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn data_dir_files_replace_the_embedded_copies() {
+        let dir = empty_temp_dir("present");
+        fs::write(dir.join(WORDS_FILE_NAME), r#"{ "words": ["alpha", "beta"] }"#).unwrap();
+        fs::write(dir.join(QUOTES_FILE_NAME), r#"[{ "text": "Hello there.", "source": "Me" }]"#).unwrap();
+        assert_eq!(load_all_words_from(Some(&dir)).unwrap().words, ["alpha", "beta"]);
+        assert_eq!(load_words_for_language_in(DEFAULT_LANGUAGE, None, Some(&dir)).unwrap().words, ["alpha", "beta"]);
+        let quotes = load_quotes_from(Some(&dir)).unwrap();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].text, "Hello there.");
+    }
+
+    #[test]
+    fn missing_data_files_fall_back_to_the_embedded_copies() {
+        let dir = empty_temp_dir("absent");
+        let embedded_words = load_all_words().unwrap();
+        let embedded_quotes = load_quotes().unwrap();
+        for data_dir in [Some(dir.as_path()), Some(dir.join("missing").as_path()), None] {
+            assert_eq!(load_all_words_from(data_dir).unwrap(), embedded_words);
+            assert_eq!(load_quotes_from(data_dir).unwrap().len(), embedded_quotes.len());
+        }
+    }
+
+    #[test]
+    fn broken_data_files_are_errors() {
+        let dir = empty_temp_dir("broken");
+        fs::write(dir.join(WORDS_FILE_NAME), r#"{ "words": [] }"#).unwrap();
+        fs::write(dir.join(QUOTES_FILE_NAME), "[]").unwrap();
+        assert!(load_all_words_from(Some(&dir)).is_err());
+        let message = format!("{:#}", load_quotes_from(Some(&dir)).unwrap_err());
+        assert!(message.contains(QUOTES_FILE_NAME), "{message}");
+    }
+
+    #[test]
+    fn bad_word_lists_report_the_path() {
+        let missing = std::env::temp_dir().join("monk_minal_rust_no_such_list.txt");
+        let malformed = write_temp_file("malformed.json", r#"{ "words": "oops" }"#);
+        let empty = write_temp_file("empty.txt", "\n  \n");
+        for path in [missing, malformed, empty] {
+            let message = format!("{:#}", load_words_from_path(&path).unwrap_err());
+            assert!(message.contains(&path.display().to_string()), "{message}");
+        }
+    }
+
+    #[test]
+    fn word_list_validation_names_the_problem() {
+        let error = |json: &str| format!("{:#}", parse_word_list(json, "allWords.json").unwrap_err());
+        assert_eq!(error(r#"{ "words": [] }"#), r#"allWords.json: 'words' array is empty; add at least one word, e.g. { "words": ["hello"] }"#);
+        assert!(error(r#"{ "wrods": ["a"] }"#).starts_with("allWords.json: missing 'words' key"));
+        assert!(error(r#"["a"]"#).starts_with("allWords.json: missing 'words' key"));
+        assert!(error(r#"{ "words": ["a", 1] }"#).starts_with("allWords.json: 'words' must be an array of strings"));
+        assert!(error("{ not json").starts_with("allWords.json: not valid JSON"));
+        assert_eq!(error(r#"{ "words": ["a", ""] }"#), "allWords.json: entry 1 of 'words' is an empty string");
+        assert_eq!(error(r#"{ "words": ["a", " "] }"#), r#"allWords.json: entry 1 of 'words' (" ") contains whitespace"#);
+        assert_eq!(error(r#"{ "words": ["two words"] }"#), r#"allWords.json: entry 0 of 'words' ("two words") contains whitespace"#);
+        assert_eq!(error(r#"{ "words": ["line\n"] }"#), r#"allWords.json: entry 0 of 'words' ("line\n") contains whitespace"#);
+        assert_eq!(parse_word_list(r#"{ "words": ["café", "naïve"] }"#, "list").unwrap().words, ["café", "naïve"]);
+    }
+
+    #[test]
+    fn word_lists_can_weigh_their_words() {
+        let list = parse_word_list(r#"{ "words": ["the", "of", "zephyr"], "weights": [50, 20.5, 0.1] }"#, "list").unwrap();
+        assert_eq!(list.words, ["the", "of", "zephyr"]);
+        assert_eq!([list.weights.weight("the"), list.weights.weight("zephyr")], [50.0, 0.1]);
+        assert_eq!(format!("{:?}", list.weights), "WordWeights(3 words)");
+        let unweighted = parse_word_list(r#"{ "words": ["the"] }"#, "list").unwrap();
+        assert!(unweighted.weights.is_empty());
+        assert_eq!(unweighted.weights.weight("the"), 1.0);
+
+        let error = |json: &str| format!("{:#}", parse_word_list(json, "list").unwrap_err());
+        assert_eq!(error(r#"{ "words": ["a", "b"], "weights": [1] }"#), "list: 'weights' has 1 entries but 'words' has 2; give one weight per word");
+        assert_eq!(error(r#"{ "words": ["a", "b"], "weights": [1, 0] }"#), "list: entry 1 of 'weights' (0) is not a positive number");
+        assert_eq!(error(r#"{ "words": ["a"], "weights": [-2] }"#), "list: entry 0 of 'weights' (-2) is not a positive number");
+        assert!(error(r#"{ "words": ["a"], "weights": ["often"] }"#).contains("'weights' an array of numbers"));
+    }
+
+    #[test]
+    fn empty_quote_lists_are_rejected_with_a_fix() {
+        let error = |json: &str| format!("{:#}", parse_quotes(json, "quotes.json").unwrap_err());
+        assert_eq!(error("[]"), r#"quotes.json: the quote list is empty; add at least one { "text": ..., "source": ... } object"#);
+        assert!(error(r#"{ "words": [] }"#).starts_with("quotes.json: expected an array of"));
+        let quotes = parse_quotes(r#"[{ "text": "Hello there.", "source": "Someone" }]"#, "quotes.json").unwrap();
+        assert_eq!(quotes[0].text, "Hello there.");
+        assert!(!load_quotes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn quotes_load_with_and_without_metadata() {
+        let quotes = parse_quotes(r#"[
+            { "text": "Plain quote.", "source": "Someone" },
+            { "text": "Counted quote.", "source": "Someone", "length": 14, "id": 7, "language": "english", "approvedBy": "x" },
+            { "text": "Declared quote.", "source": "Someone", "length": "Long" }
+        ]"#, "quotes.json").unwrap();
+        assert_eq!((quotes[0].id, &quotes[0].length, &quotes[0].language), (None, &None, &None));
+        assert_eq!((quotes[1].id, &quotes[1].length), (Some(7), &Some(DeclaredLength::Characters(14))));
+        assert_eq!(quotes[1].language.as_deref(), Some("english"));
+        assert_eq!(quotes[2].length, Some(DeclaredLength::Category("Long".to_string())));
+
+        // A declared category wins over the word count; a character count does not.
+        assert_eq!(quotes[2].declared_category(), Some(QuoteLength::Long));
+        assert!(quotes[2].has_length(&QuoteLength::Long) && !quotes[2].has_length(&QuoteLength::Short));
+        assert!(quotes[1].has_length(&QuoteLength::Short) && quotes[2].has_length(&QuoteLength::Any));
+        let unknown = Quote { length: Some(DeclaredLength::Category("huge".to_string())), ..quotes[0].clone() };
+        assert_eq!(unknown.declared_category(), None);
+        assert!(unknown.has_length(&QuoteLength::Short));
+        assert_eq!(quote_warnings(&[quotes[1].clone(), unknown])[0], "1 quotes declare a length that does not match their text");
+    }
+
+    #[test]
+    fn embedded_word_list_is_valid() {
+        let words = load_all_words().unwrap().words;
+        assert!(!words.is_empty());
+        assert!(word_list_warnings(&words).is_empty());
+    }
+
+    #[test]
+    fn word_list_warnings_flag_missing_difficulty_buckets() {
+        let medium_only = vec!["sixsix".to_string(), "seven77".to_string()];
+        assert_eq!(word_list_warnings(&medium_only), ["No words of 5 characters or fewer: Easy difficulty will use the whole list"]);
+        let long_only = vec!["ninechars".to_string()];
+        assert_eq!(word_list_warnings(&long_only).len(), 2);
+        assert!(word_list_warnings(&["short".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn quote_warnings_count_problems() {
+        let quote = |text: &str, source: &str| Quote { text: text.to_string(), source: source.to_string(), ..Quote::default() };
+        let quotes = vec![quote("one two", ""), quote(" ", "Someone"), quote(&"word ".repeat(30), "Someone")];
+        assert_eq!(quote_warnings(&quotes), [
+            "1 quotes have no text",
+            "1 quotes have no source",
+            "No Long quotes: Quote mode will pick from all quotes instead",
+        ]);
+        assert!(quote_warnings(&load_quotes().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn embedded_language_packs_load() {
+        // No user languages dir, so packs in the developer's own config dir cannot replace these.
+        let languages = available_languages_in(None);
+        for name in ["english", "german", "spanish"] {
+            assert!(languages.contains(&name.to_string()), "{languages:?}");
+            assert!(!load_words_for_language_in(name, None, None).unwrap().words.is_empty());
+        }
+        assert!(load_words_for_language_in("german", None, None).unwrap().words.contains(&"grün".to_string()));
+        assert!(load_words_for_language_in("klingon", None, None).is_err());
+    }
+
+    #[test]
+    fn user_language_packs_replace_embedded_ones() {
+        let dir = std::env::temp_dir().join(format!("monk_minal_rust_languages_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("german.txt"), "hallo\nwelt\n").unwrap();
+        assert!(available_languages_in(Some(&dir)).contains(&"german".to_string()));
+        assert_eq!(load_words_for_language_in("german", Some(&dir), None).unwrap().words, ["hallo", "welt"]);
+    }
 }
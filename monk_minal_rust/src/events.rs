@@ -0,0 +1,132 @@
+//! # Event Source Module
+//!
+//! This module defines where the game loop gets its input from: key presses, pastes and
+//! resizes, the size of the terminal, and the current time. `TerminalEvents` reads them from
+//! crossterm and the system clock. Tests drive the whole game with `ScriptedEvents` instead,
+//! a canned sequence of events on a fake clock, so the event loop runs without a terminal.
+
+use crossterm::event::{self, Event};
+use crossterm::terminal;
+#[cfg(test)]
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// A source of terminal events, the terminal size and the time, as seen by the game loop.
+pub trait EventSource {
+    /// Waits up to `timeout` for an event, and returns whether one is ready to `read`.
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+    /// Returns the next event, waiting for one if none is ready.
+    fn read(&mut self) -> io::Result<Event>;
+    /// Returns the size of the terminal as `(columns, rows)`.
+    fn size(&self) -> io::Result<(u16, u16)>;
+    /// Returns the current time, which every timing in the game is measured against.
+    fn now(&self) -> Instant;
+}
+
+/// The real terminal, read through crossterm, and the system clock.
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)> {
+        terminal::size()
+    }
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A scripted sequence of events on a fake clock, for tests.
+///
+/// Each event arrives a given delay after the one before it. Waiting in `poll` moves the
+/// clock forward instead of sleeping, so a test runs instantly however long the script spans.
+/// A resize event also changes the reported terminal size. Once the script has run out, `poll`
+/// and `read` fail, so a game that never ends makes its test fail instead of hanging.
+#[cfg(test)]
+pub struct ScriptedEvents {
+    /// The events still to come, each with the time left until it arrives.
+    script: VecDeque<(Duration, Event)>,
+    /// The fake current time.
+    now: Instant,
+    /// The terminal size reported, as `(columns, rows)`.
+    size: (u16, u16),
+}
+
+#[cfg(test)]
+impl ScriptedEvents {
+    /// Creates a script of `events`, each given with its delay after the previous one, on a
+    /// terminal of `size`.
+    pub fn new(size: (u16, u16), events: impl IntoIterator<Item = (Duration, Event)>) -> Self {
+        ScriptedEvents { script: events.into_iter().collect(), now: Instant::now(), size }
+    }
+
+    /// Returns the error given once the script has run out.
+    fn script_ended() -> io::Error {
+        io::Error::new(io::ErrorKind::UnexpectedEof, "the event script ran out")
+    }
+}
+
+#[cfg(test)]
+impl EventSource for ScriptedEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        let (delay, _) = self.script.front_mut().ok_or_else(Self::script_ended)?;
+        let waited = (*delay).min(timeout);
+        self.now += waited;
+        *delay -= waited;
+        Ok(delay.is_zero())
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        let (delay, event) = self.script.pop_front().ok_or_else(Self::script_ended)?;
+        self.now += delay;
+        if let Event::Resize(columns, rows) = event {
+            self.size = (columns, rows);
+        }
+        Ok(event)
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)> {
+        Ok(self.size)
+    }
+
+    fn now(&self) -> Instant {
+        self.now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn scripted_events_arrive_on_the_fake_clock() {
+        let key = Event::Key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
+        let mut events = ScriptedEvents::new((80, 24), [
+            (Duration::from_millis(250), key.clone()),
+            (Duration::ZERO, Event::Resize(100, 30)),
+        ]);
+        let start = events.now();
+        assert!(!events.poll(Duration::from_millis(100)).unwrap());
+        assert!(!events.poll(Duration::from_millis(100)).unwrap());
+        assert_eq!(events.now() - start, Duration::from_millis(200));
+        assert!(events.poll(Duration::from_millis(100)).unwrap());
+        assert_eq!(events.now() - start, Duration::from_millis(250));
+        assert_eq!(events.read().unwrap(), key);
+        // The next event is due at once, so even a zero timeout finds it.
+        assert!(events.poll(Duration::ZERO).unwrap());
+        assert_eq!(events.read().unwrap(), Event::Resize(100, 30));
+        assert_eq!(events.size().unwrap(), (100, 30));
+        assert!(events.poll(Duration::from_millis(100)).is_err());
+        assert!(events.read().is_err());
+    }
+}
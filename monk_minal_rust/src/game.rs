@@ -2,32 +2,371 @@
 //!
 //! This module orchestrates the main typing game experience in MonkMinal Rust.
 //! It manages the game state, processes user input, calculates performance metrics (WPM, accuracy),
-//! and handles the display of the game interface and game over screen.
-//!
-//! ## Potential Refactor:
-//! The UI rendering parts could be moved to a dedicated `ui.rs` module for better SoC.
+//! and runs the event loop. Drawing the game interface and game over screen is left to the
+//! `ui` module.
 
-use crate::config::{GameConfig, GameType, Difficulty};
+use crate::config::{ErrorFeedback, GameConfig, GameType, Difficulty, KeyRows, SpacePolicy, WpmScheme, DEFAULT_ERROR_PENALTY};
 use crate::data_loader::Quote;
+use crate::events::{EventSource, TerminalEvents};
+use crate::quote_marks::{self, QuoteMarks};
+use crate::replay::{self, Recording};
+use crate::results_csv;
+use crate::stats::{self, RunResult};
+use crate::ui;
 use anyhow::{Result, anyhow, Context}; // Added anyhow! and Context
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers}, 
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
-    style::{Print}, 
+    style::Print,
     cursor,
     terminal,
 };
-use figlet_rs::FIGfont; 
 use rand::seq::SliceRandom; 
-use std::io::{stdout, Stdout, Write}; 
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::{stdout, Write};
+use std::path::Path;
 use std::time::{Duration, Instant}; 
-use textwrap::wrap; 
-use colored::Colorize; 
+use unicode_segmentation::UnicodeSegmentation;
 use log::{warn, debug, trace}; // Added log macros
 
 /// Standard word length used for WPM calculation (average characters per word).
+/// Characters here are Unicode scalar values (`char`s), never bytes.
 const STANDARD_WORD_LENGTH: f64 = 5.0;
 
+/// Punctuation marks that may be appended to a word in punctuation mode, with the
+/// cumulative probability (per word) of each being picked. Roughly one word in six ends
+/// in punctuation, which is close to ordinary English prose.
+const TRAILING_PUNCTUATION: [(f64, char); 5] = [
+    (0.08, ','),
+    (0.14, '.'),
+    (0.155, '?'),
+    (0.165, '!'),
+    (0.175, ';'),
+];
+/// Chance that a word in punctuation mode is wrapped in double quotes.
+const QUOTE_WRAP_CHANCE: f64 = 0.02;
+/// Chance that a word in punctuation mode is wrapped in parentheses.
+const PAREN_WRAP_CHANCE: f64 = 0.01;
+/// Chance that a word not starting a sentence is capitalized anyway (e.g. a name).
+const EXTRA_CAPITAL_CHANCE: f64 = 0.05;
+/// Fraction of words replaced by a number token in numbers mode.
+const NUMBER_TOKEN_RATE: f64 = 0.15;
+/// Longest number token (in digits) generated in numbers mode.
+const MAX_NUMBER_DIGITS: u32 = 4;
+/// Columns a tab in Code mode text is expanded to, since the Tab key restarts the game.
+const CODE_TAB_WIDTH: usize = 4;
+/// How much more likely a word is to be picked in a weak-key drill for each weak key it contains.
+const WEAK_KEY_WEIGHT: f64 = 3.0;
+/// Fewest distinct words a key-row drill draws from; made-up words fill any shortfall.
+const MIN_DRILL_WORDS: usize = 20;
+/// Shortest made-up word generated for a key-row drill.
+const MIN_DRILL_WORD_CHARS: usize = 2;
+/// Longest made-up word generated for a key-row drill when the difficulty sets no limit.
+const MAX_DRILL_WORD_CHARS: usize = 8;
+/// Number of words generated at a time for Zen mode.
+const WORD_BATCH_SIZE: usize = 300;
+/// Fastest typing speed Time mode plans for when deciding how many words to generate.
+const MAX_EXPECTED_WPM: u32 = 250;
+/// Fewest words generated for a Time mode run, however short.
+const MIN_TIME_MODE_WORDS: usize = 50;
+/// In Time and Zen modes, more words are appended once fewer than this many remain to be typed.
+const REFILL_THRESHOLD: usize = 50;
+/// Seconds between WPM samples recorded for the results graph.
+const WPM_SAMPLE_INTERVAL_SECONDS: f64 = 1.0;
+/// The quotes a text is made of, each with the index of its first word.
+pub type QuoteStarts = Vec<(usize, Quote)>;
+
+/// Seconds of recent typing the header's current speed covers (see `GameState::rolling_wpm`).
+const ROLLING_WPM_WINDOW_SECONDS: f64 = 3.0;
+/// Seconds a run must have gone on before its current speed is shown, so the first few keys
+/// do not produce a wild figure.
+const MIN_ROLLING_WPM_SECONDS: f64 = 1.0;
+/// Length of the optional countdown shown before the timer starts.
+const COUNTDOWN_SECONDS: u64 = 3;
+/// Most times a mistyped word is typed again with `GameConfig::retry_mistyped`, so a word that
+/// keeps going wrong cannot hold up the run forever.
+pub const MAX_WORD_RETRIES: u32 = 3;
+/// Characters that must be typed before `GameConfig::min_accuracy` can end a run, so a single
+/// early mistake does not end it at once.
+const MIN_CHARS_FOR_ACCURACY_FLOOR: usize = 20;
+/// Seconds wrong keys must have been blocking the word before the footer suggests Backspace.
+const BACKSPACE_HINT_SECONDS: f64 = 1.0;
+/// Most wrong keys kept in `GameState::errors`. Further ones still count as typed and wrong, but
+/// are not added, so a held key or a burst of mashing never leaves more than this to erase.
+pub const MAX_PENDING_ERRORS: usize = 20;
+/// How long the screen stays inverted for `ErrorFeedback::Flash`.
+const ERROR_FLASH_DURATION: Duration = Duration::from_millis(80);
+/// Turns on the terminal's reverse-video screen mode (DECSCNM), used for the error flash.
+const SCREEN_INVERT_ON: &str = "\x1b[?5h";
+/// Turns the reverse-video screen mode off again.
+const SCREEN_INVERT_OFF: &str = "\x1b[?5l";
+
+/// Measures active typing time across any number of start/stop cycles.
+///
+/// Time only accumulates while the timer is running, so stopping it (e.g. for a pause)
+/// freezes `elapsed_secs`. The `*_at` variants take the current instant explicitly for testing.
+#[derive(Debug, Clone, Default)]
+pub struct GameTimer {
+    /// When the current running stretch began; `None` while stopped.
+    running_since: Option<Instant>,
+    /// Time accumulated by previous running stretches.
+    accumulated: Duration,
+}
+
+impl GameTimer {
+    /// Creates a stopped timer with no time accumulated.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts (or resumes) the timer as of `now`. Does nothing if it is already running.
+    pub fn start_at(&mut self, now: Instant) {
+        if self.running_since.is_none() {
+            self.running_since = Some(now);
+        }
+    }
+
+    /// Starts (or resumes) the timer now.
+    pub fn start(&mut self) {
+        self.start_at(Instant::now());
+    }
+
+    /// Stops the timer as of `now`, keeping the time accumulated so far. Does nothing if already stopped.
+    pub fn stop_at(&mut self, now: Instant) {
+        if let Some(since) = self.running_since.take() {
+            self.accumulated += now.saturating_duration_since(since);
+        }
+    }
+
+    /// Returns whether the timer is currently running.
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Returns whether the timer has ever been started.
+    pub fn has_started(&self) -> bool {
+        self.is_running() || self.accumulated > Duration::ZERO
+    }
+
+    /// Returns the total running time in seconds as of `now`.
+    pub fn elapsed_secs_at(&self, now: Instant) -> f64 {
+        let running = self.running_since.map_or(Duration::ZERO, |since| now.saturating_duration_since(since));
+        (self.accumulated + running).as_secs_f64()
+    }
+
+    /// Returns the total running time in seconds so far.
+    pub fn elapsed_secs(&self) -> f64 {
+        self.elapsed_secs_at(Instant::now())
+    }
+}
+
+/// Why a run failed and ended before it was complete.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunFailure {
+    /// Accuracy dropped below `GameConfig::min_accuracy`.
+    AccuracyFloor,
+    /// `GameConfig::max_consecutive_errors` mistakes were made in a row.
+    ConsecutiveErrors,
+}
+
+/// The final statistics of a finished run, as returned by `run_game`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameResult {
+    /// The game type that was played.
+    pub mode: GameType,
+    /// The difficulty that was played.
+    pub difficulty: Difficulty,
+    /// Gross words per minute.
+    pub gross_wpm: f64,
+    /// Net words per minute, after the error penalty.
+    pub net_wpm: f64,
+    /// Accuracy as a percentage from 0 to 100.
+    pub accuracy: f64,
+    /// How long the run took, in seconds.
+    pub duration: f64,
+    /// Characters typed correctly, including the spaces between words.
+    pub correct_chars: usize,
+    /// All characters typed, correct or not.
+    pub typed_chars: usize,
+    /// Words completed with a correct space (or Enter in Code mode).
+    pub words_completed: usize,
+    /// Seconds spent on each completed word, in order (see `GameState::word_seconds`).
+    pub word_seconds: Vec<f64>,
+    /// Whether the run was finished, rather than abandoned with Esc or failed.
+    pub completed: bool,
+    /// Whether the run was finished early with Enter (see `GameConfig::enter_finishes`).
+    pub finished_with_enter: bool,
+    /// Whether the run failed on `--min-accuracy` or `--max-consecutive-errors`.
+    pub failed: bool,
+    /// Totals over every attempt of the session, with `--cumulative`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionResult>,
+    /// Whether the user pressed Ctrl+C to leave the program altogether.
+    #[serde(skip)]
+    pub exit_requested: bool,
+    /// Whether that Ctrl+C cut a run short, so its partial results were never shown on the
+    /// game-over screen (see `interrupted_summary`).
+    #[serde(skip)]
+    pub interrupted: bool,
+}
+
+impl GameResult {
+    /// Builds the result of the run in `game_state`, timed up to its final elapsed time.
+    pub fn from_state(game_state: &GameState) -> Self {
+        let duration = game_state.final_elapsed_time_seconds.unwrap_or_else(|| game_state.timer.elapsed_secs());
+        let stats = calculate_wpm(game_state.correct_chars_total, game_state.typed_chars_total, duration,
+            &game_state.config.wpm_scheme, game_state.config.error_penalty);
+        GameResult {
+            mode: game_state.config.game_type.clone(),
+            difficulty: game_state.config.difficulty.clone(),
+            gross_wpm: stats.gross_wpm,
+            net_wpm: stats.net_wpm,
+            accuracy: stats.accuracy,
+            duration,
+            correct_chars: game_state.correct_chars_total,
+            typed_chars: game_state.typed_chars_total,
+            words_completed: game_state.words_completed,
+            word_seconds: game_state.word_seconds.clone(),
+            completed: game_state.game_over && !game_state.quit_early && game_state.failure.is_none(),
+            finished_with_enter: game_state.finished_with_enter,
+            failed: game_state.failure.is_some(),
+            session: None,
+            exit_requested: false,
+            interrupted: false,
+        }
+    }
+
+    /// Describes a run cut short by Ctrl+C, printed once the terminal is back to normal.
+    pub fn interrupted_summary(&self) -> String {
+        format!(
+            "Stopped after {:.1}s: {:.1} WPM ({:.1} gross), {:.1}% accuracy, {} words typed.",
+            self.duration, self.net_wpm, self.gross_wpm, self.accuracy, self.words_completed
+        )
+    }
+}
+
+/// Counters and typing time added up over the attempts of a session (see `GameConfig::cumulative`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionTotals {
+    /// Attempts added so far; attempts where nothing was typed are left out.
+    pub attempts: usize,
+    /// Characters typed correctly, over all attempts.
+    pub correct_chars: usize,
+    /// All characters typed, over all attempts.
+    pub typed_chars: usize,
+    /// Keystrokes that were mistakes, over all attempts.
+    pub wrong_keystrokes: usize,
+    /// Words completed, over all attempts.
+    pub words_completed: usize,
+    /// Seconds spent typing, over all attempts.
+    pub seconds: f64,
+}
+
+impl SessionTotals {
+    /// Adds the counters of the attempt in `game_state`, which took `seconds`.
+    pub fn add_attempt(&mut self, game_state: &GameState, seconds: f64) {
+        self.attempts += 1;
+        self.correct_chars += game_state.correct_chars_total;
+        self.typed_chars += game_state.typed_chars_total;
+        self.wrong_keystrokes += game_state.wrong_keystrokes;
+        self.words_completed += game_state.words_completed;
+        self.seconds += seconds;
+    }
+
+    /// Scores the totals as one long run, with `error_penalty` for the net WPM.
+    pub fn result(&self, error_penalty: f64) -> SessionResult {
+        let stats = calculate_wpm(self.correct_chars, self.typed_chars, self.seconds, &WpmScheme::Net, error_penalty);
+        SessionResult {
+            attempts: self.attempts,
+            gross_wpm: stats.gross_wpm,
+            net_wpm: stats.net_wpm,
+            accuracy: stats.accuracy,
+            duration: self.seconds,
+            words_completed: self.words_completed,
+        }
+    }
+}
+
+/// The statistics of a whole session, as if all its attempts were one run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionResult {
+    /// Attempts that were typed in.
+    pub attempts: usize,
+    /// Gross words per minute.
+    pub gross_wpm: f64,
+    /// Net words per minute, after the error penalty.
+    pub net_wpm: f64,
+    /// Accuracy as a percentage from 0 to 100.
+    pub accuracy: f64,
+    /// Seconds spent typing.
+    pub duration: f64,
+    /// Words completed.
+    pub words_completed: usize,
+}
+
+impl SessionResult {
+    /// Describes the session in one line, printed when Ctrl+C skipped the summary screen.
+    pub fn summary(&self) -> String {
+        format!(
+            "Session of {} attempts over {:.1}s: {:.1} WPM ({:.1} gross), {:.1}% accuracy, {} words typed.",
+            self.attempts, self.duration, self.net_wpm, self.gross_wpm, self.accuracy, self.words_completed
+        )
+    }
+}
+
+/// Puts the terminal into raw mode and restores it when dropped.
+///
+/// Dropping the guard, whether normally, through `?` or while unwinding from a panic, shows
+/// the cursor again and leaves raw mode, so the shell is never left unusable.
+pub struct TerminalGuard {
+    /// Undoes the terminal changes; `restore_terminal` outside of tests.
+    restore: fn() -> Result<()>,
+    /// Whether the terminal still needs restoring.
+    active: bool,
+}
+
+impl TerminalGuard {
+    /// Enables raw mode and returns a guard that disables it again.
+    pub fn enable() -> Result<Self> {
+        terminal::enable_raw_mode().context("Failed to enable raw mode")?;
+        let guard = Self::with_restore(restore_terminal);
+        // Pastes arrive as a single event instead of a flood of key presses.
+        execute!(stdout(), EnableBracketedPaste).context("Failed to enable bracketed paste")?;
+        Ok(guard)
+    }
+
+    /// Creates an active guard that calls `restore` when restored or dropped.
+    fn with_restore(restore: fn() -> Result<()>) -> Self {
+        TerminalGuard { restore, active: true }
+    }
+
+    /// Restores the terminal now, reporting any failure instead of ignoring it as `drop` does.
+    pub fn restore(mut self) -> Result<()> {
+        self.active = false;
+        (self.restore)()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if self.active {
+            if let Err(e) = (self.restore)() {
+                warn!("Failed to restore terminal: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Shows the cursor (in the user's own shape), turns bracketed paste and any error flash off and leaves raw mode.
+pub fn restore_terminal() -> Result<()> {
+    execute!(stdout(), cursor::SetCursorStyle::DefaultUserShape, cursor::Show, DisableBracketedPaste, Print(SCREEN_INVERT_OFF))
+        .context("Failed to show cursor")?;
+    terminal::disable_raw_mode().context("Failed to disable raw mode")?;
+    Ok(())
+}
+
 /// Represents the current state of the typing game.
 #[derive(Debug)]
 pub struct GameState {
@@ -35,28 +374,107 @@ pub struct GameState {
     pub words_to_type: Vec<String>,
     /// Index of the current word the user is expected to type from `words_to_type`.
     pub current_word_index: usize,
-    /// Index of the current character within the current word.
+    /// Index of the current character within the current word, counted in `char`s (not bytes).
     pub current_char_index: usize, 
     /// The characters typed by the user for the current word so far that are correct.
     pub user_input: String,      
     /// The characters typed by the user for the current word that are incorrect.
     pub errors: String, 
-    /// Timestamp of when the game (typing) officially started.
-    pub start_time: Option<Instant>,
+    /// Indices of the characters in the current word that were mistyped with
+    /// `GameConfig::no_backspace` on. Such a mistake still fills its place in `user_input`.
+    pub missed_char_indices: Vec<usize>,
+    /// What the user typed for each completed word, indexed like `words_to_type`.
+    /// Used to restore a word when backspacing into it.
+    pub typed_words: Vec<String>,
+    /// Measures active typing time; started on the first key press, stopped while paused.
+    pub timer: GameTimer,
     /// The configuration for the current game session.
     pub config: GameConfig,
     /// All words loaded from `allWords.json`.
     pub all_loaded_words: Vec<String>, 
     /// All quotes loaded from `quotes.json`.
     pub all_loaded_quotes: Vec<Quote>,
-    /// Total number of characters correctly typed by the user across all words.
+    /// Total number of characters correctly typed by the user across all words,
+    /// including the spaces that complete each word. Counted in `char`s, one per keystroke.
     pub correct_chars_total: usize, 
     /// Total number of characters (correct or incorrect) attempted by the user.
+    /// Counted in `char`s, one per keystroke, so multibyte text is not over-counted.
     pub typed_chars_total: usize,
+    /// Number of keystrokes that were mistakes, whether recorded in `errors` or rejected in strict mode.
+    pub wrong_keystrokes: usize,
+    /// Words completed so far, each by typing the separator after it correctly. Backspacing
+    /// into a completed word takes it back out.
+    pub words_completed: usize,
+    /// Mistakes made since the last correct keystroke. Backspace neither adds to nor resets it.
+    pub consecutive_errors: usize,
+    /// `(attempts, errors)` per expected character: how often it was due, and how often the key
+    /// pressed for it was wrong. The key between words counts as `' '`, or `'\n'` in Code mode.
+    pub char_stats: HashMap<char, (usize, usize)>,
     /// Flag indicating whether the game has ended.
     pub game_over: bool,
     /// Stores the final elapsed time in seconds when the game ends.
     pub final_elapsed_time_seconds: Option<f64>,
+    /// Whether the run was abandoned with Esc before it was complete. Esc is how Zen mode
+    /// normally ends, so it never counts as quitting there.
+    pub quit_early: bool,
+    /// Why the run failed, if it ended because of `min_accuracy` or `max_consecutive_errors`.
+    pub failure: Option<RunFailure>,
+    /// `(elapsed_seconds, wpm)` samples, scored by `config.wpm_scheme`, taken about once per second while typing.
+    /// Each WPM value covers only the interval since the previous sample.
+    pub wpm_samples: Vec<(f64, f64)>,
+    /// `correct_chars_total` at the time of the last WPM sample.
+    sampled_correct_chars: usize,
+    /// `typed_chars_total` at the time of the last WPM sample.
+    sampled_typed_chars: usize,
+    /// Elapsed seconds at which each correct key of the last `ROLLING_WPM_WINDOW_SECONDS` was
+    /// typed, oldest first, for `rolling_wpm`.
+    pub recent_keystrokes: VecDeque<f64>,
+    /// Whether this run was saved to the results history.
+    pub result_recorded: bool,
+    /// Best net WPM for this mode and difficulty before this run, if it was recorded
+    /// and the combination had been played before.
+    pub previous_best_wpm: Option<f64>,
+    /// Average net WPM for this mode and difficulty before this run, likewise.
+    pub previous_average_wpm: Option<f64>,
+    /// The quote being typed in Quote mode, or the last one of a marathon; `None` in the other modes.
+    pub quote: Option<Quote>,
+    /// Every quote in the text, each with the index of its first word: one in Quote mode, more
+    /// in a marathon (see `GameConfig::marathon`), and none in the other modes.
+    pub quote_starts: QuoteStarts,
+    /// Source of every random choice made for the session (see `session_rng`). Kept across
+    /// restarts, so a seeded session repeats its whole sequence of tests.
+    pub rng: StdRng,
+    /// The player's favorite and blacklisted quotes, used when picking a quote.
+    pub quote_marks: QuoteMarks,
+    /// When the last key press or paste arrived, for the idle timeout; `None` before the start.
+    pub last_input_at: Option<Instant>,
+    /// Whether the game was paused by the idle timeout rather than with Ctrl+P.
+    pub idle_paused: bool,
+    /// Whether a mistake just restarted the run (see `GameConfig::restart_on_error`), so the
+    /// start prompt is due again.
+    pub restarted_on_error: bool,
+    /// Whether a wrong key was typed since the current word was started, corrected or not.
+    pub word_mistyped: bool,
+    /// How many times the current word has been typed again (see `word_needs_retry`).
+    pub word_retries: u32,
+    /// Seconds spent on each completed word, from the completion of the word before it (or the
+    /// start) to the separator that completed it. Indexed like `typed_words`.
+    pub word_seconds: Vec<f64>,
+    /// Elapsed seconds at which the last completed word was completed, or 0 before the first.
+    pub last_word_completed_at: f64,
+    /// Earlier attempts of the session added up, with `config.cumulative` on. Kept across restarts.
+    pub session_totals: SessionTotals,
+    /// Whether Enter was pressed to finish the run where it stands (see `finish_with_enter`).
+    pub finished_with_enter: bool,
+    /// Elapsed seconds at which the wrong keys now in `errors` began piling up, for the
+    /// backspace hint. Only meaningful while `errors` is not empty.
+    pub errors_started_at: Option<f64>,
+    /// The `(gross, headline)` WPM shown in the header, smoothed by `config.wpm_smoothing`, or
+    /// `None` before the first update (see `update_live_wpm`).
+    pub live_wpm: Option<(f64, f64)>,
+    /// Every edit of the run so far, for `--record`. Kept in memory and only written out once
+    /// the run is over, so recording costs the run nothing.
+    pub edit_log: Vec<LoggedEdit>,
 }
 
 impl GameState {
@@ -67,37 +485,842 @@ impl GameState {
         all_loaded_quotes: Vec<Quote>,
         words_for_current_game: Vec<String>,
     ) -> Self {
+        let rng = session_rng(config.seed);
         GameState {
             words_to_type: words_for_current_game,
             current_word_index: 0,
             current_char_index: 0,
             user_input: String::new(),
             errors: String::new(),
-            start_time: None,
+            missed_char_indices: Vec::new(),
+            typed_words: Vec::new(),
+            timer: GameTimer::new(),
             config,
             all_loaded_words,
             all_loaded_quotes,
             correct_chars_total: 0,
             typed_chars_total: 0,
+            wrong_keystrokes: 0,
+            words_completed: 0,
+            consecutive_errors: 0,
+            char_stats: HashMap::new(),
             game_over: false,
             final_elapsed_time_seconds: None,
+            quit_early: false,
+            failure: None,
+            wpm_samples: Vec::new(),
+            sampled_correct_chars: 0,
+            sampled_typed_chars: 0,
+            recent_keystrokes: VecDeque::new(),
+            result_recorded: false,
+            previous_best_wpm: None,
+            previous_average_wpm: None,
+            quote: None,
+            quote_starts: Vec::new(),
+            rng,
+            quote_marks: QuoteMarks::default(),
+            last_input_at: None,
+            idle_paused: false,
+            restarted_on_error: false,
+            word_mistyped: false,
+            word_retries: 0,
+            word_seconds: Vec::new(),
+            last_word_completed_at: 0.0,
+            session_totals: SessionTotals::default(),
+            finished_with_enter: false,
+            errors_started_at: None,
+            edit_log: Vec::new(),
+            live_wpm: None,
+        }
+    }
+
+    /// Returns whether the game has started and its timer is currently stopped.
+    pub fn is_paused(&self) -> bool {
+        !self.game_over && self.timer.has_started() && !self.timer.is_running()
+    }
+
+    /// Pauses the timer if it is running, or resumes it if paused, as of `now`.
+    ///
+    /// Does nothing before the game has started or after it has ended.
+    pub fn toggle_pause_at(&mut self, now: Instant) {
+        if self.is_paused() {
+            self.timer.start_at(now);
+        } else {
+            self.timer.stop_at(now);
+        }
+        self.idle_paused = false;
+    }
+
+    /// Records that a key press or paste arrived at `now`, for the idle timeout.
+    pub fn note_input_at(&mut self, now: Instant) {
+        self.last_input_at = Some(now);
+    }
+
+    /// Pauses the game if it is running and nothing was typed for `config.idle_timeout` seconds
+    /// as of `now`. Returns whether it paused.
+    ///
+    /// The timer is stopped as of the last input, so the idle time does not count towards the run.
+    pub fn pause_if_idle_at(&mut self, now: Instant) -> bool {
+        let (Some(timeout), Some(last_input)) = (self.config.idle_timeout, self.last_input_at) else {
+            return false;
+        };
+        if self.game_over || !self.timer.is_running()
+            || now.saturating_duration_since(last_input) < Duration::from_secs(timeout as u64) {
+            return false;
+        }
+        self.timer.stop_at(last_input);
+        self.idle_paused = true;
+        true
+    }
+
+    /// Returns whether Enter finishes the run: with `config.enter_finishes`, in the modes where
+    /// Enter is not part of the text, once something has been typed. The last condition keeps
+    /// a repeated Enter from the start prompt from ending the run at once.
+    pub fn enter_finishes(&self) -> bool {
+        self.config.enter_finishes
+            && matches!(self.config.game_type, GameType::Words | GameType::Quote | GameType::Custom)
+            && self.typed_chars_total > 0
+    }
+
+    /// Marks the run as finished with Enter, if `enter_finishes` allows it, for the game loop
+    /// to end it.
+    pub fn finish_with_enter(&mut self) {
+        if self.enter_finishes() {
+            self.finished_with_enter = true;
+        }
+    }
+
+    /// Folds the WPM at `elapsed_seconds` into `live_wpm`, as an exponential moving average
+    /// weighting the new figures by `config.wpm_smoothing`. Called before every redraw.
+    pub fn update_live_wpm(&mut self, elapsed_seconds: f64) {
+        let stats = calculate_wpm(self.correct_chars_total, self.typed_chars_total, elapsed_seconds,
+            &self.config.wpm_scheme, self.config.error_penalty);
+        let factor = self.config.wpm_smoothing;
+        self.live_wpm = Some(match self.live_wpm {
+            Some((gross, wpm)) => (ema(gross, stats.gross_wpm, factor), ema(wpm, stats.wpm, factor)),
+            None => (stats.gross_wpm, stats.wpm),
+        });
+    }
+
+    /// Returns whether to remind the user, `elapsed_seconds` into the run, that the word is
+    /// blocked until the wrong keys are erased: they have been there for `BACKSPACE_HINT_SECONDS`.
+    pub fn backspace_hint_due(&self, elapsed_seconds: f64) -> bool {
+        !self.errors.is_empty()
+            && self.errors_started_at.is_some_and(|since| elapsed_seconds - since >= BACKSPACE_HINT_SECONDS)
+    }
+
+    /// Returns whether the run has reached `config.max_minutes` after `elapsed_seconds` of
+    /// running time, and so has to end whatever the mode. Paused time is not counted.
+    pub fn reached_max_duration(&self, elapsed_seconds: f64) -> bool {
+        self.config.max_minutes.is_some_and(|minutes| elapsed_seconds >= minutes as f64 * 60.0)
+    }
+
+    /// Ends the game now, stopping the timer and recording the final elapsed time.
+    ///
+    /// Returns the final elapsed time in seconds.
+    pub fn end_game(&mut self) -> f64 {
+        self.end_game_at(Instant::now())
+    }
+
+    /// Like `end_game`, but stops the timer as of `now`.
+    pub fn end_game_at(&mut self, now: Instant) -> f64 {
+        self.timer.stop_at(now);
+        let elapsed_seconds = self.timer.elapsed_secs();
+        self.game_over = true;
+        self.final_elapsed_time_seconds = Some(elapsed_seconds);
+        elapsed_seconds
+    }
+
+    /// Records a WPM sample if at least `WPM_SAMPLE_INTERVAL_SECONDS` passed since the last one.
+    ///
+    /// The sample's WPM is computed from the characters typed since the previous sample,
+    /// so it reflects the current pace rather than the running average.
+    pub fn record_wpm_sample(&mut self, elapsed_seconds: f64) {
+        let last_sample_time = self.wpm_samples.last().map_or(0.0, |&(time, _)| time);
+        let interval = elapsed_seconds - last_sample_time;
+        if interval < WPM_SAMPLE_INTERVAL_SECONDS {
+            return;
+        }
+        let stats = calculate_wpm(
+            self.correct_chars_total - self.sampled_correct_chars,
+            self.typed_chars_total - self.sampled_typed_chars,
+            interval,
+            &self.config.wpm_scheme,
+            self.config.error_penalty,
+        );
+        self.wpm_samples.push((elapsed_seconds, stats.wpm));
+        self.sampled_correct_chars = self.correct_chars_total;
+        self.sampled_typed_chars = self.typed_chars_total;
+    }
+
+    /// Returns the elapsed seconds as of the key being handled: when the last input arrived, or
+    /// now if no input was noted.
+    fn input_elapsed_secs(&self) -> f64 {
+        self.last_input_at.map_or_else(|| self.timer.elapsed_secs(), |at| self.timer.elapsed_secs_at(at))
+    }
+
+    /// Records a correct key typed at `elapsed_seconds`, and forgets the keys that fell out of
+    /// the rolling window.
+    pub fn record_keystroke(&mut self, elapsed_seconds: f64) {
+        self.recent_keystrokes.push_back(elapsed_seconds);
+        while self.recent_keystrokes.front().is_some_and(|&time| time <= elapsed_seconds - ROLLING_WPM_WINDOW_SECONDS) {
+            self.recent_keystrokes.pop_front();
+        }
+    }
+
+    /// Returns the current typing speed at `elapsed_seconds`: the WPM of the correct keys typed
+    /// in the last `ROLLING_WPM_WINDOW_SECONDS`.
+    ///
+    /// Early in a run the window is only as long as the run so far, and before
+    /// `MIN_ROLLING_WPM_SECONDS` there is no figure at all.
+    pub fn rolling_wpm(&self, elapsed_seconds: f64) -> Option<f64> {
+        let window = elapsed_seconds.min(ROLLING_WPM_WINDOW_SECONDS);
+        if window < MIN_ROLLING_WPM_SECONDS {
+            return None;
+        }
+        let keys = self.recent_keystrokes.iter()
+            .filter(|&&time| time > elapsed_seconds - window && time <= elapsed_seconds)
+            .count();
+        Some(keys as f64 / STANDARD_WORD_LENGTH / (window / 60.0))
+    }
+
+    /// Advances the start countdown that began at `countdown_started`, as of `now`.
+    ///
+    /// Returns the number of whole seconds still to show (3, 2, 1), or `None` once the
+    /// countdown has elapsed, in which case the timer is started at the moment it ended.
+    pub fn tick_countdown_at(&mut self, countdown_started: Instant, now: Instant) -> Option<u64> {
+        let countdown = Duration::from_secs(COUNTDOWN_SECONDS);
+        let elapsed = now.saturating_duration_since(countdown_started);
+        if elapsed >= countdown {
+            self.timer.start_at(countdown_started + countdown);
+            return None;
+        }
+        Some(COUNTDOWN_SECONDS - elapsed.as_secs())
+    }
+
+    /// Returns whether every word of a Words or Quote run has been typed.
+    ///
+    /// Words mode covers the first `word_count` words, Quote, Custom and Code modes the whole text. The run is
+    /// complete once the last of those words is typed in full without pending errors; the
+    /// trailing space after it is optional.
+    pub fn is_text_complete(&self) -> bool {
+        let total_words = match self.config.game_type {
+            GameType::Words => self.config.word_count.map_or(self.words_to_type.len(), |count| {
+                (count as usize).min(self.words_to_type.len())
+            }),
+            GameType::Quote | GameType::Custom | GameType::Code => self.words_to_type.len(),
+            GameType::Time | GameType::Zen => return false,
+        };
+        if total_words == 0 || self.current_word_index >= total_words {
+            return total_words > 0;
+        }
+        self.current_word_index == total_words - 1
+            && self.errors.is_empty()
+            && self.current_char_index == self.words_to_type[self.current_word_index].chars().count()
+            && !self.word_needs_retry()
+    }
+
+    /// Returns whether the current word starts over instead of completing when its separator is
+    /// typed: with `retry_mistyped` on, after a mistake in it, while it has been retried fewer
+    /// than `MAX_WORD_RETRIES` times. A word that must be retried never completes the text.
+    pub fn word_needs_retry(&self) -> bool {
+        self.config.retry_mistyped && self.word_mistyped && self.word_retries < MAX_WORD_RETRIES
+    }
+
+    /// Returns whether accuracy has dropped below the configured floor, once enough has been typed to judge.
+    pub fn accuracy_below_floor(&self) -> bool {
+        let Some(floor) = self.config.min_accuracy else { return false };
+        if self.typed_chars_total < MIN_CHARS_FOR_ACCURACY_FLOOR {
+            return false;
+        }
+        let accuracy = self.correct_chars_total as f64 / self.typed_chars_total as f64 * 100.0;
+        accuracy < floor
+    }
+
+    /// Fails and ends the game if the last change broke the accuracy floor or the limit on
+    /// mistakes in a row. Called after every keystroke that changes the character totals.
+    fn end_if_failed(&mut self) {
+        if self.game_over {
+            return;
+        }
+        let too_many_in_a_row = self.config.max_consecutive_errors
+            .is_some_and(|limit| self.consecutive_errors >= limit as usize);
+        self.failure = if self.accuracy_below_floor() {
+            Some(RunFailure::AccuracyFloor)
+        } else if too_many_in_a_row {
+            Some(RunFailure::ConsecutiveErrors)
+        } else {
+            return;
+        };
+        debug!("Run failed: {:?}. Ending game.", self.failure);
+        self.end_game();
+    }
+
+    /// Types pasted `text` as if each character had been typed, stopping once the text is complete.
+    ///
+    /// Any whitespace (e.g. a newline) counts as a space; other control characters are skipped.
+    /// In Code mode newlines are kept, as Enter, and carriage returns are dropped.
+    pub fn handle_paste(&mut self, text: &str) {
+        let keep_newlines = self.config.game_type == GameType::Code;
+        for c in text.chars() {
+            if self.game_over || self.is_text_complete() || self.restarted_on_error {
+                break;
+            }
+            if keep_newlines && (c == '\n' || c == '\r') {
+                if c == '\n' {
+                    self.handle_char('\n');
+                }
+            } else if c.is_whitespace() {
+                self.handle_char(' ');
+            } else if !c.is_control() {
+                self.handle_char(c);
+            }
+        }
+    }
+
+    /// Returns the sources of the quotes typed, in order: every quote of a marathon, or the one
+    /// quote otherwise. Blank sources are left out, and runs without a quote have none.
+    pub fn quote_sources(&self) -> Vec<&str> {
+        let sources: Vec<&str> = if self.quote_starts.len() > 1 {
+            self.quote_starts.iter().map(|(_, quote)| quote.source.trim()).collect()
+        } else {
+            self.quote.iter().map(|quote| quote.source.trim()).collect()
+        };
+        sources.into_iter().filter(|source| !source.is_empty()).collect()
+    }
+
+    /// Returns the key that completes each word: a space, or Enter (`'\n'`) in Code mode,
+    /// where each "word" is a whole line.
+    pub fn word_separator(&self) -> char {
+        if self.config.game_type == GameType::Code { '\n' } else { ' ' }
+    }
+
+    /// Returns the character to type next: the next one of the current word, or the separator
+    /// after it. `None` once every word is done.
+    pub fn expected_char(&self) -> Option<char> {
+        let word = self.words_to_type.get(self.current_word_index)?;
+        Some(word.chars().nth(self.current_char_index).unwrap_or_else(|| self.word_separator()))
+    }
+
+    /// Processes a typed character against the current word.
+    ///
+    /// A correct character advances `current_char_index`; the word separator (see
+    /// `word_separator`) at the end of a word moves on to the next word. Anything else is
+    /// recorded in `errors`, rejected in strict mode, or with `no_backspace` takes the place of
+    /// the expected character (see `missed_char_indices`).
+    /// Indices are in `char`s, so multibyte words are matched one keystroke per character.
+    ///
+    /// Every keystroke is counted exactly once in `typed_chars_total`, and once in
+    /// `correct_chars_total` if it was the expected key: the next character of the word, or
+    /// the space completing it, with no errors pending. The completing space is a keystroke
+    /// like any other, so it is never credited more than once.
+    ///
+    /// With `SpacePolicy::Skip`, the separator typed in a started word moves on anyway (see
+    /// `skip_word`).
+    ///
+    /// After each keystroke the run fails and ends if accuracy fell below `min_accuracy` or
+    /// `max_consecutive_errors` mistakes were made in a row (see `end_if_failed`). With
+    /// `restart_on_error`, a mistake instead restarts the run on the same text. With
+    /// `retry_mistyped`, the separator after a mistyped word starts that word over (see
+    /// `word_needs_retry`).
+    pub fn handle_char(&mut self, c: char) {
+        let Some(target_word) = self.words_to_type.get(self.current_word_index) else {
+            warn!("Character typed after all words completed. Current index: {}, Total words: {}",
+                self.current_word_index, self.words_to_type.len());
+            return;
+        };
+        // Past the last character of the word, the expected key is the separator.
+        let expected_char = target_word.chars().nth(self.current_char_index);
+        let separator = self.word_separator();
+        // Skipping needs a started word: some of it typed, or a mistake made in it.
+        let skips_word = c == separator
+            && self.config.space_policy == SpacePolicy::Skip
+            && (!self.errors.is_empty() || (expected_char.is_some() && self.current_char_index > 0));
+        let is_correct = skips_word || (self.errors.is_empty() && c == expected_char.unwrap_or(separator));
+        self.typed_chars_total += 1;
+        if is_correct {
+            self.correct_chars_total += 1;
+            self.consecutive_errors = 0;
+            self.record_keystroke(self.input_elapsed_secs());
+        } else {
+            self.consecutive_errors += 1;
+            self.word_mistyped = true;
+        }
+        let stats_char = if skips_word { separator } else { expected_char.unwrap_or(separator) };
+        let stats = self.char_stats.entry(stats_char).or_insert((0, 0));
+        stats.0 += 1;
+        if !is_correct {
+            stats.1 += 1;
+        }
+        let mut missed = 0;
+        match expected_char {
+            _ if skips_word => {
+                missed = self.skip_word();
+            }
+            Some(_) if is_correct => {
+                self.user_input.push(c);
+                self.current_char_index += 1;
+            }
+            None if is_correct && self.word_needs_retry() => self.retry_word(),
+            None if is_correct => self.advance_word(),
+            // Strict mode rejects the wrong key outright (it still counts as typed), so
+            // mistakes can never pile up and the word cannot be left until it is right.
+            _ if self.config.strict => {
+                trace!("Strict mode rejected '{}'.", c);
+                self.wrong_keystrokes += 1;
+            }
+            // Without backspace a mistake could never be fixed, so it does not block either: the
+            // wrong key uses up the character it was meant for. In place of the separator there
+            // is nothing to use up, so it is only counted.
+            Some(expected) if self.config.no_backspace => {
+                self.user_input.push(expected);
+                self.missed_char_indices.push(self.current_char_index);
+                self.current_char_index += 1;
+                self.wrong_keystrokes += 1;
+            }
+            None if self.config.no_backspace => {
+                trace!("No-backspace mode ignored '{}' in place of the separator.", c);
+                self.wrong_keystrokes += 1;
+            }
+            _ => {
+                if self.errors.is_empty() {
+                    self.errors_started_at = Some(self.input_elapsed_secs());
+                }
+                if self.errors.chars().count() < MAX_PENDING_ERRORS {
+                    self.errors.push(c);
+                }
+                self.wrong_keystrokes += 1;
+            }
+        }
+        if (!is_correct || missed > 0) && self.config.restart_on_error {
+            debug!("Mistyped '{}' with restart on error. Restarting.", c);
+            self.restart_same_text();
+            self.restarted_on_error = true;
+            return;
+        }
+        self.end_if_failed();
+    }
+
+    /// Resets the state for a fresh run of the same configuration with `words_for_new_game`.
+    ///
+    /// Everything tied to the previous run (input, indices, character totals, timing, samples)
+    /// is cleared; only the configuration, the loaded word and quote lists, the quote marks,
+    /// the RNG and the session totals are kept. With `config.cumulative`, the previous run is
+    /// added to the session totals first.
+    pub fn restart(&mut self, words_for_new_game: Vec<String>) {
+        self.add_attempt_to_session();
+        let session_totals = std::mem::take(&mut self.session_totals);
+        let config = self.config.clone();
+        let all_loaded_words = std::mem::take(&mut self.all_loaded_words);
+        let all_loaded_quotes = std::mem::take(&mut self.all_loaded_quotes);
+        let rng = self.rng.clone();
+        let quote_marks = std::mem::take(&mut self.quote_marks);
+        *self = GameState::new(config, all_loaded_words, all_loaded_quotes, words_for_new_game);
+        self.rng = rng;
+        self.quote_marks = quote_marks;
+        self.session_totals = session_totals;
+    }
+
+    /// With `config.cumulative`, adds the current attempt to `session_totals`, timed up to its
+    /// end or, if it is still going, up to the last input. Does nothing if nothing was typed.
+    pub fn add_attempt_to_session(&mut self) {
+        if !self.config.cumulative || self.typed_chars_total == 0 {
+            return;
+        }
+        let seconds = self.final_elapsed_time_seconds.unwrap_or_else(|| self.input_elapsed_secs());
+        let mut totals = std::mem::take(&mut self.session_totals);
+        totals.add_attempt(self, seconds);
+        self.session_totals = totals;
+    }
+
+    /// Resets the state for a fresh run of the same words, and of the same quotes in Quote mode.
+    pub fn restart_same_text(&mut self) {
+        let quote = self.quote.take();
+        let quote_starts = std::mem::take(&mut self.quote_starts);
+        let words = std::mem::take(&mut self.words_to_type);
+        self.restart(words);
+        self.quote = quote;
+        self.quote_starts = quote_starts;
+    }
+
+    /// Sets the quotes the text comes from, as chosen by `choose_game_text`.
+    fn set_quotes(&mut self, quote_starts: QuoteStarts) {
+        self.quote = quote_starts.last().map(|(_, quote)| quote.clone());
+        self.quote_starts = quote_starts;
+    }
+
+    /// Returns the number of the quote being typed (counting from 1) and the number of quotes in
+    /// the text, or `None` outside Quote mode.
+    pub fn quote_progress(&self) -> Option<(usize, usize)> {
+        let total = self.quote_starts.len();
+        let current = self.quote_starts.iter().filter(|(start, _)| *start <= self.current_word_index).count();
+        (total > 0).then_some((current.max(1), total))
+    }
+
+    /// In Time and Zen modes, appends a fresh batch of words once the user nears the end of the list.
+    ///
+    /// Words are only ever appended, so `current_word_index` and the typed character totals
+    /// are unaffected by a refill.
+    pub fn refill_words_if_needed(&mut self) -> Result<()> {
+        if !matches!(self.config.game_type, GameType::Time | GameType::Zen)
+            || self.current_word_index + REFILL_THRESHOLD < self.words_to_type.len() {
+            return Ok(());
+        }
+        let mut more_words = get_words_for_game_with_rng(&self.config, &self.all_loaded_words, &self.all_loaded_quotes, &mut self.rng)?;
+        // Each batch is free of repeats on its own, but may start with the word the last one ended on.
+        separate_repeats(&mut more_words, self.words_to_type.last().map(String::as_str));
+        debug!("Refilling {:?} word list with {} words at index {}.", self.config.game_type, more_words.len(), self.current_word_index);
+        self.words_to_type.extend(more_words);
+        Ok(())
+    }
+
+    /// Removes the last typed character, clearing errors before correct input.
+    ///
+    /// A character here is what the user sees as one: a whole grapheme cluster, so a letter and
+    /// the combining accents on it go together, even though each `char` took its own keystroke.
+    ///
+    /// Removed correct characters are also taken back out of the character totals, since they
+    /// will be counted again when retyped. Erased errors stay counted as typed, so they still
+    /// cost accuracy.
+    ///
+    /// At the start of a word, backspace moves back into the previous word, restoring what was
+    /// typed there and taking back the space that completed it.
+    ///
+    /// Does nothing with `GameConfig::no_backspace` on.
+    pub fn handle_backspace(&mut self) {
+        if self.config.no_backspace {
+            trace!("No-backspace mode ignored Backspace.");
+            return;
+        }
+        if !self.errors.is_empty() { pop_grapheme(&mut self.errors); }
+        else if !self.user_input.is_empty() {
+            let removed = pop_grapheme(&mut self.user_input);
+            self.current_char_index = self.current_char_index.saturating_sub(removed);
+            self.uncount_correct_chars(removed);
+        } else if let Some(previous_input) = self.typed_words.pop() {
+            self.current_word_index -= 1;
+            self.current_char_index = previous_input.chars().count();
+            self.user_input = previous_input;
+            self.words_completed -= 1;
+            if let Some(seconds) = self.word_seconds.pop() {
+                self.last_word_completed_at -= seconds;
+            }
+            // The previous word was finished, so it is not retried for mistakes made before.
+            self.word_mistyped = false;
+            self.word_retries = 0;
+            self.uncount_correct_chars(1);
+        }
+    }
+
+    /// Clears everything typed for the current word (Ctrl+Backspace), staying on the same word.
+    ///
+    /// Stats are adjusted the same way as for repeated single backspaces. Does nothing with
+    /// `GameConfig::no_backspace` on.
+    pub fn handle_delete_word(&mut self) {
+        if self.config.no_backspace {
+            trace!("No-backspace mode ignored delete word.");
+            return;
+        }
+        let deleted_correct = self.user_input.chars().count();
+        self.user_input.clear();
+        self.errors.clear();
+        self.current_char_index = 0;
+        self.uncount_correct_chars(deleted_correct);
+    }
+
+    /// Moves on from the current word when the separator is typed before its end, under
+    /// `SpacePolicy::Skip`, and returns how many characters were missed.
+    ///
+    /// The separator itself counts as the correct key, and every character of the word not yet
+    /// typed correctly is charged as a missed one: typed and wrong, in the totals and in
+    /// `char_stats`. Errors pending are dropped, having been counted already. A word with
+    /// anything missed counts as mistyped, so `retry_mistyped` starts it over instead.
+    fn skip_word(&mut self) -> usize {
+        let remaining: Vec<char> = self.words_to_type[self.current_word_index]
+            .chars()
+            .skip(self.current_char_index)
+            .collect();
+        for &c in &remaining {
+            let stats = self.char_stats.entry(c).or_insert((0, 0));
+            stats.0 += 1;
+            stats.1 += 1;
+        }
+        self.typed_chars_total += remaining.len();
+        self.wrong_keystrokes += remaining.len();
+        self.errors.clear();
+        if !remaining.is_empty() {
+            self.word_mistyped = true;
+        }
+        debug!("Skipped word {} with {} characters missed.", self.current_word_index, remaining.len());
+        if self.word_needs_retry() {
+            self.retry_word();
+        } else {
+            self.advance_word();
         }
+        remaining.len()
+    }
+
+    /// Starts the current word over for `retry_mistyped` (see `word_needs_retry`).
+    fn retry_word(&mut self) {
+        self.word_retries += 1;
+        debug!("Word {} was mistyped. Typing it again (retry {}).", self.current_word_index, self.word_retries);
+        self.word_mistyped = false;
+        self.current_char_index = 0;
+        self.user_input.clear();
+        self.errors.clear();
+        self.missed_char_indices.clear();
+    }
+
+    /// Completes the current word, recording how long it took, and moves on to the next one.
+    fn advance_word(&mut self) {
+        let completed_at = self.input_elapsed_secs();
+        self.word_seconds.push(completed_at - self.last_word_completed_at);
+        self.last_word_completed_at = completed_at;
+        self.current_word_index += 1;
+        self.current_char_index = 0;
+        self.typed_words.push(std::mem::take(&mut self.user_input));
+        self.missed_char_indices.clear();
+        self.words_completed += 1;
+        self.word_mistyped = false;
+        self.word_retries = 0;
+    }
+
+    /// Removes `count` deleted correct characters from both character totals.
+    ///
+    /// This lowers accuracy, so it can fail the run on the accuracy floor.
+    fn uncount_correct_chars(&mut self, count: usize) {
+        self.correct_chars_total = self.correct_chars_total.saturating_sub(count);
+        self.typed_chars_total = self.typed_chars_total.saturating_sub(count);
+        self.end_if_failed();
     }
 }
 
-/// Calculates Words Per Minute (WPM) and accuracy.
-pub fn calculate_wpm(correct_chars: usize, total_chars_typed: usize, time_seconds: f64) -> (f64, f64, f64) {
+/// Returns the next value of an exponential moving average at `previous`, given the new `raw`
+/// value with weight `factor` (from 0, ignoring it, to 1, taking it as it is).
+pub fn ema(previous: f64, raw: f64, factor: f64) -> f64 {
+    factor * raw + (1.0 - factor) * previous
+}
+
+/// Removes the last grapheme cluster from `text` and returns how many `char`s it was made of,
+/// or 0 if `text` is empty.
+fn pop_grapheme(text: &mut String) -> usize {
+    let Some((start, cluster)) = text.grapheme_indices(true).next_back() else {
+        return 0;
+    };
+    let chars = cluster.chars().count();
+    text.truncate(start);
+    chars
+}
+
+/// Typing speed and accuracy, as computed by `calculate_wpm`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WpmStats {
+    /// The headline WPM under the chosen `WpmScheme`; one of the figures below, or
+    /// gross WPM times accuracy for `WpmScheme::Simple`.
+    pub wpm: f64,
+    /// Gross words per minute: every typed character counts.
+    pub gross_wpm: f64,
+    /// Net words per minute: gross less the error penalty for each error per minute.
+    pub net_wpm: f64,
+    /// Accuracy as a percentage from 0 to 100.
+    pub accuracy: f64,
+}
+
+/// Calculates Words Per Minute (WPM) and accuracy, with the headline WPM scored by `scheme`.
+///
+/// `correct_chars` and `total_chars_typed` are character counts (Unicode scalar values),
+/// as tracked in `GameState`. Net WPM takes `error_penalty` WPM off gross WPM for each error
+/// per minute; `DEFAULT_ERROR_PENALTY` is the standard formula.
+pub fn calculate_wpm(
+    correct_chars: usize,
+    total_chars_typed: usize,
+    time_seconds: f64,
+    scheme: &WpmScheme,
+    error_penalty: f64,
+) -> WpmStats {
     if time_seconds < 0.01 || total_chars_typed == 0 { 
         let accuracy = if total_chars_typed == 0 { 100.0 } else { (correct_chars as f64 / total_chars_typed as f64) * 100.0 };
-        return (0.0, 0.0, accuracy);
+        return WpmStats { wpm: 0.0, gross_wpm: 0.0, net_wpm: 0.0, accuracy };
     }
     let time_in_minutes = time_seconds / 60.0;
     let gross_wpm = (total_chars_typed as f64 / STANDARD_WORD_LENGTH) / time_in_minutes;
     let errors_count = total_chars_typed.saturating_sub(correct_chars);
-    let error_penalty_wpm = errors_count as f64 / time_in_minutes;
+    let error_penalty_wpm = error_penalty * errors_count as f64 / time_in_minutes;
     let net_wpm = (gross_wpm - error_penalty_wpm).max(0.0); 
     let accuracy = (correct_chars as f64 / total_chars_typed as f64) * 100.0;
-    (gross_wpm, net_wpm, accuracy)
+    let wpm = match scheme {
+        WpmScheme::Net => net_wpm,
+        WpmScheme::Simple => gross_wpm * accuracy / 100.0,
+        WpmScheme::Raw => gross_wpm,
+    };
+    WpmStats { wpm, gross_wpm, net_wpm, accuracy }
+}
+
+/// Returns the keystrokes per minute (KPM): every character typed, right or wrong, without the
+/// word normalization of WPM. Under 0.01 seconds there is no rate to speak of, so it is zero.
+pub fn calculate_kpm(typed_chars: usize, time_seconds: f64) -> f64 {
+    if time_seconds < 0.01 {
+        return 0.0;
+    }
+    typed_chars as f64 / (time_seconds / 60.0)
+}
+
+/// Returns the number of whole words completed per minute, as scored in a time attack.
+pub fn words_per_minute(words_completed: usize, time_seconds: f64) -> f64 {
+    if time_seconds < 0.01 {
+        return 0.0;
+    }
+    words_completed as f64 / (time_seconds / 60.0)
+}
+
+/// Calculates typing consistency from a series of WPM samples, as a percentage from 0 to 100.
+///
+/// Based on the coefficient of variation (standard deviation / mean) of the samples, mapped
+/// onto 0–100% the same way MonkeyType does: `100 * (1 - tanh(cv + cv³/3 + cv⁵/5))`.
+/// A perfectly steady run scores 100%. With no samples, or only zero-WPM samples,
+/// there is no variation to measure and the result is also 100%.
+pub fn calculate_consistency(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 100.0;
+    }
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    if mean <= 0.0 {
+        return 100.0;
+    }
+    let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+    let cv = variance.sqrt() / mean;
+    100.0 * (1.0 - (cv + cv.powi(3) / 3.0 + cv.powi(5) / 5.0).tanh())
+}
+
+/// Picks a random quote whose length matches `config.quote_length` (see `Quote::has_length`),
+/// skipping blacklisted quotes and favoring favorites as given by `marks`. With
+/// `config.quote_number` set, that quote is used instead (see `select_quote`).
+///
+/// Falls back, with a warning, to blacklisted quotes when every quote is blacklisted, and to
+/// quotes of any length when none has the requested length.
+fn choose_quote_with_rng<'a, R: Rng + ?Sized>(
+    config: &GameConfig,
+    all_quotes: &'a [Quote],
+    marks: &QuoteMarks,
+    rng: &mut R,
+) -> Result<&'a Quote> {
+    if all_quotes.is_empty() {
+        return Err(anyhow!("No quotes available for Quote mode. Please check data/quotes.json."));
+    }
+    if let Some(number) = config.quote_number {
+        return select_quote(all_quotes, number);
+    }
+    let mut allowed: Vec<&Quote> = all_quotes.iter().filter(|quote| !marks.is_blacklisted(quote)).collect();
+    if allowed.is_empty() {
+        warn!("Every quote is blacklisted; choosing from all quotes instead.");
+        allowed = all_quotes.iter().collect();
+    }
+    let mut candidates: Vec<&Quote> = allowed.iter()
+        .copied()
+        .filter(|quote| quote.has_length(&config.quote_length))
+        .collect();
+    if candidates.is_empty() {
+        warn!("No quotes match length {:?}; choosing from all quotes instead.", config.quote_length);
+        candidates = allowed;
+    }
+    candidates.choose_weighted(rng, |quote| marks.weight(quote)).copied()
+        .map_err(|e| anyhow!("Failed to choose a quote, though list was not empty: {}", e))
+}
+
+/// Returns the quote with `id` equal to `number`, or if none has that id, the one at index
+/// `number` of `all_quotes`. Its length and marks do not matter, since it was asked for by name.
+///
+/// Fails with the valid range of indices, and of ids if the quotes have any, when neither matches.
+pub fn select_quote(all_quotes: &[Quote], number: u64) -> Result<&Quote> {
+    if let Some(quote) = all_quotes.iter().find(|quote| quote.id == Some(number)) {
+        return Ok(quote);
+    }
+    if let Some(quote) = usize::try_from(number).ok().and_then(|index| all_quotes.get(index)) {
+        return Ok(quote);
+    }
+    let ids = all_quotes.iter().filter_map(|quote| quote.id);
+    let id_range = ids.clone().min().zip(ids.max())
+        .map_or_else(String::new, |(min, max)| format!(", or an id from {} to {}", min, max));
+    Err(anyhow!("No quote {}: pick an index from 0 to {}{}.", number, all_quotes.len().saturating_sub(1), id_range))
+}
+
+/// Splits a quote's text into the words to type.
+fn quote_words(quote: &Quote) -> Vec<String> {
+    quote.text.split_whitespace().map(String::from).collect()
+}
+
+/// Splits the text of a Custom run into the words to type.
+///
+/// Words are split on any whitespace (spaces, tabs, newlines); everything else, including
+/// punctuation, capitalization and non-ASCII characters, is kept exactly as given.
+fn custom_words(text: &str) -> Vec<String> {
+    text.split_whitespace().map(String::from).collect()
+}
+
+/// Splits the text of a Code run into lines, each typed as one "word" and ended with Enter.
+///
+/// Indentation is kept, with tabs expanded to `CODE_TAB_WIDTH` spaces. Trailing whitespace is
+/// dropped, since it cannot be seen, as are blank lines before and after the code.
+fn code_lines(text: &str) -> Vec<String> {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.replace('\t', &" ".repeat(CODE_TAB_WIDTH)).trim_end().to_string())
+        .collect();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    match (first, last) {
+        (Some(first), Some(last)) => lines[first..=last].to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the RNG for a session: seeded with `seed` if given, so the session is
+/// reproducible, or from system entropy otherwise.
+pub fn session_rng(seed: Option<u64>) -> StdRng {
+    seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+}
+
+/// Selects the text for a new game: the words to type and, in Quote mode, the quotes they come
+/// from, each with the index of its first word.
+///
+/// A marathon picks `GameConfig::marathon` quotes one after another and joins their words, so
+/// they are typed as one run.
+fn choose_game_text<R: Rng + ?Sized>(
+    config: &GameConfig,
+    all_words: &[String],
+    all_quotes: &[Quote],
+    marks: &QuoteMarks,
+    rng: &mut R,
+) -> Result<(Vec<String>, QuoteStarts)> {
+    if config.game_type == GameType::Quote {
+        let mut words = Vec::new();
+        let mut quote_starts = Vec::new();
+        for _ in 0..config.marathon.unwrap_or(1).max(1) {
+            let quote = choose_quote_with_rng(config, all_quotes, marks, rng)?;
+            quote_starts.push((words.len(), quote.clone()));
+            words.extend(quote_words(quote));
+        }
+        return Ok((words, quote_starts));
+    }
+    Ok((get_words_for_game_with_rng(config, all_words, all_quotes, rng)?, Vec::new()))
+}
+
+/// Creates the state for the first game of a session, with its text chosen by the session's RNG
+/// and, in Quote mode, the player's `quote_marks`.
+fn start_session(config: GameConfig, all_words: Vec<String>, all_quotes: Vec<Quote>, quote_marks: QuoteMarks) -> Result<GameState> {
+    let mut rng = session_rng(config.seed);
+    let (words_for_game, quote_starts) = choose_game_text(&config, &all_words, &all_quotes, &quote_marks, &mut rng)
+        .with_context(|| format!("Failed to get words for game with config: {:?}", config))?;
+    // This check is now more robust as get_words_for_game returns Err if no words can be selected.
+    if words_for_game.is_empty() { // Should ideally be caught by error from get_words_for_game
+        warn!("get_words_for_game returned an empty list unexpectedly, though it should return Err.");
+        return Err(anyhow!("No words were selected for the game, words_for_game list is empty."));
+    }
+    let mut game_state = GameState::new(config, all_words, all_quotes, words_for_game);
+    game_state.set_quotes(quote_starts);
+    game_state.rng = rng;
+    game_state.quote_marks = quote_marks;
+    Ok(game_state)
 }
 
 /// Selects words or quote text for the game based on the `GameConfig`.
@@ -106,235 +1329,709 @@ pub fn get_words_for_game(
     all_words: &[String],
     all_quotes: &[Quote],
 ) -> Result<Vec<String>> {
-    let mut rng = rand::thread_rng(); 
+    get_words_for_game_with_rng(config, all_words, all_quotes, &mut rand::thread_rng())
+}
+
+/// Like `get_words_for_game`, but draws all randomness from `rng`.
+///
+/// Passing a seeded RNG (e.g. `StdRng::seed_from_u64`) makes the selection reproducible.
+pub fn get_words_for_game_with_rng<R: Rng + ?Sized>(
+    config: &GameConfig,
+    all_words: &[String],
+    all_quotes: &[Quote],
+    rng: &mut R,
+) -> Result<Vec<String>> {
     match config.game_type {
         GameType::Quote => {
-            if all_quotes.is_empty() {
-                return Err(anyhow!("No quotes available for Quote mode. Please check data/quotes.json."));
+            // No quote marks here: only a running game knows them (see `choose_game_text`).
+            let chosen_quote = choose_quote_with_rng(config, all_quotes, &QuoteMarks::default(), rng)?;
+            Ok(quote_words(chosen_quote))
+        }
+        GameType::Custom | GameType::Code => {
+            let text = config.custom_text.as_deref().unwrap_or_default();
+            let words = if config.game_type == GameType::Code { code_lines(text) } else { custom_words(text) };
+            if words.is_empty() {
+                return Err(anyhow!("No custom text to type. Pass it with --text or --text-file."));
             }
-            let chosen_quote = all_quotes.choose(&mut rng)
-                .ok_or_else(|| anyhow!("Failed to choose a quote, though list was not empty."))?;
-            Ok(chosen_quote.text.split_whitespace().map(String::from).collect())
+            Ok(words)
         }
-        GameType::Time | GameType::Words => {
+        GameType::Time | GameType::Words | GameType::Zen => {
             if all_words.is_empty() {
                 return Err(anyhow!("No words available for selected game mode. Please check data/allWords.json."));
             }
             let count = match config.game_type {
-                GameType::Time => 300, 
+                GameType::Time => time_mode_word_count(config.time_seconds),
+                GameType::Zen => WORD_BATCH_SIZE,
                 GameType::Words => config.word_count.unwrap_or(30) as usize,
                 _ => unreachable!(),
             };
             
-            let mut filtered_words: Vec<String> = match config.difficulty {
-                Difficulty::Easy => all_words.iter().filter(|w| w.len() <= 5).cloned().collect(),
-                Difficulty::Medium => all_words.iter().filter(|w| w.len() <= 8).cloned().collect(),
-                Difficulty::Hard => all_words.to_vec(),
-            };
+            // Lengths are visible characters, so accented words are not pushed into harder buckets.
+            let (min_chars, max_chars) = config.word_length_bounds();
+            let mut filtered_words: Vec<String> = all_words.iter()
+                .filter(|w| {
+                    let chars = w.chars().count();
+                    chars >= min_chars && max_chars.is_none_or(|max| chars <= max)
+                })
+                .cloned()
+                .collect();
 
             if filtered_words.is_empty() { 
                 // If filtering results in an empty list (e.g. no easy words), use all available words.
                 // Consider if this should be an error or a fallback. For now, fallback.
-                warn!("No words found for difficulty {:?}, falling back to all available words.", config.difficulty);
+                warn!("No words of {} to {:?} characters found (difficulty {:?}), falling back to all available words.",
+                    min_chars, max_chars, config.difficulty);
                 filtered_words = all_words.to_vec();
                 if filtered_words.is_empty() { // Double check if all_words itself was empty after fallback attempt
                      return Err(anyhow!("No words available after difficulty filtering and fallback. Check data/allWords.json."));
                 }
             }
             
+            if let Some(rows) = &config.key_rows {
+                let mut drill = drill_words(rows, &filtered_words, count, max_chars, rng);
+                separate_repeats(&mut drill, None);
+                return Ok(drill);
+            }
+
             let num_to_choose = if filtered_words.len() < count { filtered_words.len() } else { count };
             if num_to_choose == 0 { // If after all filtering and selection, we have no words to choose.
                  return Err(anyhow!("No words could be selected for the game with current criteria (count: {}, available: {}).", count, filtered_words.len()));
             }
             
-            Ok(filtered_words.choose_multiple(&mut rng, num_to_choose).cloned().collect())
+            // Without frequency weights or a weak-key drill, every word is equally likely.
+            let mut chosen: Vec<String> = if config.weak_chars.is_empty() && config.word_weights.is_empty() {
+                filtered_words.choose_multiple(rng, num_to_choose).cloned().collect()
+            } else {
+                let weight = |word: &String| config.word_weights.weight(word) * weak_key_weight(word, &config.weak_chars);
+                filtered_words.choose_multiple_weighted(rng, num_to_choose, weight)
+                    .map_err(|e| anyhow!("Failed to weigh words: {}", e))?
+                    .cloned()
+                    .collect()
+            };
+            if config.numbers {
+                chosen = insert_numbers(chosen, NUMBER_TOKEN_RATE, rng);
+            }
+            // A word list with duplicates, or a number token, can put the same word twice in a row.
+            separate_repeats(&mut chosen, None);
+            if config.punctuation {
+                Ok(punctuate_words(chosen, rng))
+            } else {
+                Ok(chosen)
+            }
         }
     }
 }
 
-/// Displays the main game interface (typing area, stats, timer).
-fn display_game_interface(stdout: &mut Stdout, game_state: &GameState, terminal_width: u16, terminal_height: u16) -> Result<()> {
-    execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
-    let elapsed_seconds = game_state.start_time.map_or(0.0, |st| st.elapsed().as_secs_f64());
-    let mut header_lines: Vec<String> = Vec::new();
-    let timer_display = if game_state.config.game_type == GameType::Time {
-        let total_duration = game_state.config.time_seconds.unwrap_or(0) as f64;
-        let remaining_time = (total_duration - elapsed_seconds).max(0.0);
-        format!("Time Left: {:02}:{:02}", (remaining_time / 60.0).floor() as u32, (remaining_time % 60.0).floor() as u32)
-    } else {
-        format!("Time Elapsed: {:02}:{:02}", (elapsed_seconds / 60.0).floor() as u32, (elapsed_seconds % 60.0).floor() as u32)
-    };
-    header_lines.push(timer_display);
-    if game_state.start_time.is_some() && elapsed_seconds > 0.01 {
-        let (gross_wpm, net_wpm, accuracy) = calculate_wpm(
-            game_state.correct_chars_total, game_state.typed_chars_total, elapsed_seconds);
-        header_lines.push(format!("Gross WPM: {:.0} | Net WPM: {:.0} | Accuracy: {:.2}%", gross_wpm, net_wpm, accuracy));
-    } else {
-        header_lines.push("Gross WPM: - | Net WPM: - | Accuracy: -%".to_string());
-    }
-    for (i, line) in header_lines.iter().enumerate() {
-        let padding = (terminal_width.saturating_sub(line.len() as u16)) / 2;
-        execute!(stdout, cursor::MoveTo(padding, i as u16), Print(line))?;
-    }
-    const MAX_WORDS_TO_DISPLAY: usize = 15; 
-    const APPROX_CHARS_WINDOW: usize = 60;  
-    let start_idx = game_state.current_word_index.saturating_sub(MAX_WORDS_TO_DISPLAY / 3);
-    let mut end_idx = start_idx;
-    let mut current_len_chars = 0;
-    for i in start_idx..game_state.words_to_type.len() {
-        if i >= game_state.words_to_type.len() { end_idx = game_state.words_to_type.len(); break; }
-        current_len_chars += game_state.words_to_type[i].len() + 1; 
-        if current_len_chars > APPROX_CHARS_WINDOW && i > game_state.current_word_index { end_idx = i; break; }
-        end_idx = i + 1;
-    }
-    if end_idx == start_idx && end_idx < game_state.words_to_type.len() { end_idx = start_idx + 1; }
-    let display_words_slice = if !game_state.words_to_type.is_empty() {
-        &game_state.words_to_type[start_idx..end_idx.min(game_state.words_to_type.len())]
-    } else { &[] };
-    let mut display_string_parts: Vec<String> = Vec::new();
-    for (i_slice, word) in display_words_slice.iter().enumerate() {
-        let actual_word_idx = start_idx + i_slice;
-        if actual_word_idx == game_state.current_word_index {
-            let target_word = &game_state.words_to_type[game_state.current_word_index];
-            if !game_state.user_input.is_empty() { display_string_parts.push(format!("{}", game_state.user_input.green())); }
-            if !game_state.errors.is_empty() { display_string_parts.push(format!("{}", game_state.errors.on_red())); }
-            if game_state.current_char_index < target_word.len() {
-                let current_char_str = target_word.chars().nth(game_state.current_char_index).unwrap().to_string();
-                if game_state.errors.is_empty() { display_string_parts.push(format!("{}", current_char_str.black().on_yellow())); }
-                else { display_string_parts.push(format!("{}", current_char_str.dimmed())); }
-                if game_state.current_char_index + 1 < target_word.len() {
-                    display_string_parts.push(format!("{}", (&target_word[(game_state.current_char_index + 1)..]).dimmed()));
-                }
-            }
-        } else { display_string_parts.push(format!("{}", word.dimmed())); }
-        display_string_parts.push(" ".to_string()); 
-    }
-    if !display_string_parts.is_empty() { display_string_parts.pop(); }
-    let full_display_line = display_string_parts.join("");
-    let wrap_width = (terminal_width.saturating_sub(4)).max(10) as usize;
-    let wrapped_text_lines = wrap(&full_display_line, wrap_width);
-    let header_height = header_lines.len() as u16;
-    let footer_height = 1u16; 
-    let available_height_for_text = terminal_height.saturating_sub(header_height).saturating_sub(footer_height);
-    let text_display_start_row = header_height + available_height_for_text.saturating_sub(wrapped_text_lines.len() as u16) / 2;
-    for (i, line) in wrapped_text_lines.iter().enumerate() {
-        let padding = (terminal_width.saturating_sub(line.len() as u16)) / 2;
-        execute!(stdout, cursor::MoveTo(padding, text_display_start_row + i as u16), Print(line))?;
-    }
-    let quit_msg = "Press Esc to quit";
-    let quit_msg_padding = (terminal_width.saturating_sub(quit_msg.len() as u16)) / 2;
-    execute!(stdout, cursor::MoveTo(quit_msg_padding, terminal_height - 1), Print(quit_msg))?;
-    stdout.flush()?; 
-    Ok(())
+/// Returns how likely `word` is to be picked in a weak-key drill: 1, plus `WEAK_KEY_WEIGHT` for
+/// each of `weak_chars` it contains.
+fn weak_key_weight(word: &str, weak_chars: &[char]) -> f64 {
+    1.0 + WEAK_KEY_WEIGHT * weak_chars.iter().filter(|&&c| word.contains(c)).count() as f64
 }
 
-/// Displays the game over screen with final statistics.
-fn display_game_over_screen(stdout: &mut Stdout, game_state: &GameState, terminal_width: u16, terminal_height: u16) -> Result<()> {
-    execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
-    let font = FIGfont::standard().unwrap_or_else(|_| FIGfont::from_content("Game Over!").expect("Figlet fallback font failed")); 
-    let game_over_banner = font.convert("Game Over!").unwrap_or_default().to_string();
-    let mut lines_to_display: Vec<String> = Vec::new();
-    for line in game_over_banner.lines() { lines_to_display.push(line.to_string()); }
-    lines_to_display.push("".to_string()); 
-    let final_time = game_state.final_elapsed_time_seconds.unwrap_or_else(|| 
-        game_state.start_time.map_or(0.0, |st| st.elapsed().as_secs_f64()));
-    let (gross_wpm, net_wpm, accuracy) = calculate_wpm(
-        game_state.correct_chars_total, game_state.typed_chars_total, final_time);
-    lines_to_display.push(format!("Gross WPM: {:.0}", gross_wpm));
-    lines_to_display.push(format!("Net WPM:   {:.0}", net_wpm));
-    lines_to_display.push(format!("Accuracy:  {:.2}%", accuracy));
-    lines_to_display.push(format!("Time Taken: {:02}:{:02}", (final_time / 60.0).floor() as u32, (final_time % 60.0).floor() as u32));
-    lines_to_display.push("".to_string()); 
-    lines_to_display.push("Press any key to return to main menu.".to_string());
-    let total_lines_height = lines_to_display.len() as u16;
-    let start_row = terminal_height.saturating_sub(total_lines_height) / 2;
-    for (i, line) in lines_to_display.iter().enumerate() {
-        let padding = (terminal_width.saturating_sub(line.len() as u16)) / 2;
-        execute!(stdout, cursor::MoveTo(padding, start_row + i as u16), Print(line))?;
-    }
-    stdout.flush()?;
-    Ok(())
+/// Returns `count` words made only of letters on the key `rows`, drawn from `words`.
+///
+/// When fewer than `MIN_DRILL_WORDS` of `words` qualify, made-up words of up to `max_chars`
+/// random row letters are added, with a warning. Every word is used once before any repeats.
+fn drill_words<R: Rng + ?Sized>(rows: &KeyRows, words: &[String], count: usize, max_chars: Option<usize>, rng: &mut R) -> Vec<String> {
+    let letters: Vec<char> = rows.letters().chars().collect();
+    let mut pool: Vec<String> = words.iter()
+        .filter(|word| word.chars().all(|c| letters.contains(&c)))
+        .cloned()
+        .collect();
+    if pool.len() < MIN_DRILL_WORDS {
+        warn!("Only {} words use just the {:?} keys; adding made-up words.", pool.len(), rows);
+        let max_chars = max_chars.unwrap_or(MAX_DRILL_WORD_CHARS).max(MIN_DRILL_WORD_CHARS);
+        while pool.len() < MIN_DRILL_WORDS {
+            let length = rng.gen_range(MIN_DRILL_WORD_CHARS..=max_chars);
+            pool.push((0..length).map(|_| letters[rng.gen_range(0..letters.len())]).collect());
+        }
+    }
+    pool.shuffle(rng);
+    pool.into_iter().cycle().take(count).collect()
 }
 
-/// Runs the main game loop, handling user input, game state updates, and rendering.
-pub fn run_game(config: GameConfig, all_words: Vec<String>, all_quotes: Vec<Quote>) -> Result<()> {
-    let mut stdout = stdout();
-    terminal::enable_raw_mode().context("Failed to enable raw mode")?;
-    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::Hide).context("Failed to clear screen or hide cursor")?;
+/// Returns how many words to generate for a Time mode run of `time_seconds`.
+///
+/// Enough for a typist at `MAX_EXPECTED_WPM` to never run out, without generating thousands of
+/// words for a short run. Faster typists are covered by `GameState::refill_words_if_needed`.
+fn time_mode_word_count(time_seconds: Option<u32>) -> usize {
+    let seconds = time_seconds.unwrap_or(0) as usize;
+    (seconds * MAX_EXPECTED_WPM as usize).div_ceil(60).max(MIN_TIME_MODE_WORDS)
+}
 
-    let words_for_game = get_words_for_game(&config, &all_words, &all_quotes)
-        .with_context(|| format!("Failed to get words for game with config: {:?}", config))?;
-    
-    // This check is now more robust as get_words_for_game returns Err if no words can be selected.
-    if words_for_game.is_empty() { // Should ideally be caught by error from get_words_for_game
-        warn!("get_words_for_game returned an empty list unexpectedly, though it should return Err.");
-        execute!(stdout, cursor::Show).ok(); // Attempt to cleanup
-        terminal::disable_raw_mode().ok();  // Attempt to cleanup
-        return Err(anyhow!("No words were selected for the game, words_for_game list is empty."));
+/// Reorders `words` so that no word directly follows the same word, where that is possible.
+///
+/// `previous` is the word shown just before `words`, if any, which stays where it is. Each repeat
+/// is swapped with the next word that differs from the one before it; when none is left ahead,
+/// with an earlier word that fits in both places. Words that cannot be separated (e.g. a list
+/// of one word) are left in order.
+pub fn separate_repeats(words: &mut [String], previous: Option<&str>) {
+    /// Returns the words on either side of position `i`.
+    fn neighbours<'a>(words: &'a [String], previous: Option<&'a str>, i: usize) -> [Option<&'a str>; 2] {
+        let before = if i == 0 { previous } else { Some(words[i - 1].as_str()) };
+        [before, words.get(i + 1).map(String::as_str)]
     }
-    
-    let mut game_state = GameState::new(config.clone(), all_words, all_quotes, words_for_game);
-    let (mut term_cols, mut term_rows) = terminal::size().context("Failed to get terminal size")?;
+    for i in 0..words.len() {
+        if neighbours(words, previous, i)[0] != Some(words[i].as_str()) {
+            continue;
+        }
+        if let Some(j) = (i + 1..words.len()).find(|&j| words[j] != words[i]) {
+            words.swap(i, j);
+            continue;
+        }
+        // Moving `words[j]` to `i` and the repeat to `j` must not make a new pair on either side.
+        let fits = |j: usize| {
+            words[j] != words[i]
+                && !neighbours(words, previous, j).contains(&Some(words[i].as_str()))
+                && !neighbours(words, previous, i).contains(&Some(words[j].as_str()))
+        };
+        if let Some(j) = (0..i).find(|&j| fits(j)) {
+            words.swap(i, j);
+        }
+    }
+}
+
+/// Replaces roughly `rate` of the words with random integer tokens of 1 to `MAX_NUMBER_DIGITS` digits.
+///
+/// Words are replaced rather than added so Words mode keeps its requested length.
+/// The tokens are ordinary words as far as matching is concerned.
+fn insert_numbers<R: Rng + ?Sized>(words: Vec<String>, rate: f64, rng: &mut R) -> Vec<String> {
+    words.into_iter().map(|word| {
+        if rng.gen_bool(rate) {
+            let digits = rng.gen_range(1..=MAX_NUMBER_DIGITS);
+            let low = if digits == 1 { 0 } else { 10u32.pow(digits - 1) };
+            rng.gen_range(low..10u32.pow(digits)).to_string()
+        } else {
+            word
+        }
+    }).collect()
+}
+
+/// Turns a list of plain words into prose-like text for punctuation mode.
+///
+/// Words that start a sentence (the first word, or one following `.`, `?` or `!`) are capitalized,
+/// plus an occasional random word. Some words then get trailing punctuation, and a few are
+/// wrapped in quotes or parentheses. See `TRAILING_PUNCTUATION` for the densities used.
+fn punctuate_words<R: Rng + ?Sized>(words: Vec<String>, rng: &mut R) -> Vec<String> {
+    let mut sentence_start = true;
+    words.into_iter().map(|word| {
+        let mut word = if sentence_start || rng.gen_bool(EXTRA_CAPITAL_CHANCE) {
+            capitalize(&word)
+        } else {
+            word
+        };
+        let roll: f64 = rng.gen();
+        if roll < QUOTE_WRAP_CHANCE {
+            word = format!("\"{}\"", word);
+        } else if roll < QUOTE_WRAP_CHANCE + PAREN_WRAP_CHANCE {
+            word = format!("({})", word);
+        }
+        let roll: f64 = rng.gen();
+        let mark = TRAILING_PUNCTUATION.iter().find(|(chance, _)| roll < *chance).map(|(_, mark)| *mark);
+        if let Some(mark) = mark {
+            word.push(mark);
+        }
+        sentence_start = matches!(mark, Some('.' | '?' | '!'));
+        word
+    }).collect()
+}
+
+/// Uppercases the first character of `word`, leaving the rest untouched.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Saves a finished run to the results history and remembers the previous personal best and average.
+/// With `GameConfig::csv_path` set, the run is appended to that CSV file too, and with
+/// `GameConfig::record_path`, its recording is saved there (see `replay`).
+///
+/// Runs where nothing was typed are not recorded, and neither are failed runs (see
+/// `RunFailure`), since this is never called for them. Failing to save is logged but does not
+/// interrupt the game.
+fn record_run(game_state: &mut GameState, elapsed_seconds: f64, history_path: Option<&Path>) {
+    if game_state.typed_chars_total == 0 {
+        return;
+    }
+    // The history always keeps net WPM with the default penalty, so personal bests compare
+    // across scoring schemes and penalty weights.
+    let stats = calculate_wpm(game_state.correct_chars_total, game_state.typed_chars_total, elapsed_seconds,
+        &WpmScheme::Net, DEFAULT_ERROR_PENALTY);
+    let mut result = RunResult::new(&game_state.config, stats.gross_wpm, stats.net_wpm, stats.accuracy, elapsed_seconds);
+    result.char_stats = game_state.char_stats.iter().map(|(&c, &counts)| (c, counts)).collect();
+    if let Some(csv_path) = &game_state.config.csv_path {
+        let source = game_state.quote_sources().join("; ");
+        if let Err(e) = results_csv::append_csv_row(csv_path, &result, &source) {
+            warn!("Failed to append run to CSV: {:?}", e);
+        }
+    }
+    if let Some(record_path) = &game_state.config.record_path {
+        if let Err(e) = replay::save_recording(record_path, &Recording::from_state(game_state, elapsed_seconds)) {
+            warn!("Failed to save recording: {:?}", e);
+        }
+    }
+    let Some(history_path) = history_path else {
+        warn!("Could not determine the config directory; the run is not saved to the history.");
+        return;
+    };
+    match stats::record_result(history_path, &result) {
+        Ok(past) => {
+            game_state.result_recorded = true;
+            game_state.previous_best_wpm = past.best_wpm;
+            game_state.previous_average_wpm = past.average_wpm;
+        }
+        Err(e) => warn!("Failed to save run to history: {:?}", e),
+    }
+}
+
+/// Returns whether `key_event` is Ctrl+C.
+///
+/// Raw mode turns Ctrl+C into an ordinary key press instead of a signal, so the game has to
+/// look for it itself to let the player leave.
+pub fn is_quit_key(key_event: &KeyEvent) -> bool {
+    matches!(key_event.code, KeyCode::Char('c' | 'C')) && key_event.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// What an event does while the "Press any key to start" prompt is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptEvent {
+    /// Ctrl+C was pressed: the program is left without starting.
+    Quit,
+    /// A key was pressed: the test starts.
+    Start,
+    /// The terminal was resized to the given columns and rows: the prompt is redrawn.
+    Resize(u16, u16),
+    /// Anything else, including key releases and repeats.
+    Ignore,
+}
 
+/// Returns what `event` does while the start prompt is shown.
+///
+/// Only a key press starts the test, so the release of that same key (reported on some
+/// platforms) cannot start it a second time after a quick restart.
+fn prompt_event(event: &Event) -> PromptEvent {
+    match event {
+        Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press && is_quit_key(key_event) => PromptEvent::Quit,
+        Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press => PromptEvent::Start,
+        Event::Resize(cols, rows) => PromptEvent::Resize(*cols, *rows),
+        _ => PromptEvent::Ignore,
+    }
+}
+
+/// Shows the "Press any key to start" prompt and waits for a key press, then starts the timer.
+///
+/// Waiting blocks on the next event, so the key press starts the test as soon as it arrives.
+/// That key only starts the test: it is consumed here and never typed. The prompt is redrawn
+/// centered whenever the terminal is resized while waiting.
+///
+/// Returns `false`, without starting the timer, if Ctrl+C is pressed instead.
+fn wait_for_start(stdout: &mut impl Write, events: &mut impl EventSource, game_state: &mut GameState, term_cols: &mut u16, term_rows: &mut u16) -> Result<bool> {
     let initial_prompt = "Press any key to start...";
-    let prompt_padding = (term_cols.saturating_sub(initial_prompt.len() as u16)) / 2;
-    let prompt_row = term_rows / 2;
-    execute!(stdout, cursor::MoveTo(prompt_padding, prompt_row), Print(initial_prompt))
+    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::Hide, cursor::MoveTo(ui::centered_column(initial_prompt, *term_cols), *term_rows / 2), Print(initial_prompt))
         .context("Failed to display initial prompt")?;
     stdout.flush().context("Failed to flush stdout for initial prompt")?;
     
     loop { 
-        if event::poll(Duration::from_millis(500)).context("Event polling failed")? { 
-            match event::read().context("Failed to read event")? {
-                Event::Key(_key_event) => { // Any key press
-                    game_state.start_time = Some(Instant::now());
-                    break; 
-                }
-                Event::Resize(new_cols, new_rows) => { // Handle resize during initial prompt
-                    term_cols = new_cols;
-                    term_rows = new_rows;
-                    // Re-display prompt
-                    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::MoveTo((term_cols.saturating_sub(initial_prompt.len() as u16)) / 2, term_rows / 2), Print(initial_prompt))
-                        .context("Failed to re-display initial prompt on resize")?;
-                    stdout.flush().context("Failed to flush stdout for prompt resize")?;
+        match prompt_event(&events.read().context("Failed to read event")?) {
+            PromptEvent::Start => {
+                if game_state.config.countdown {
+                    run_countdown(stdout, events, game_state, term_cols, term_rows)?;
+                } else {
+                    game_state.timer.start_at(events.now());
                 }
-                _ => {} // Ignore other events like mouse during prompt
+                game_state.note_input_at(events.now());
+                return Ok(true);
             }
+            PromptEvent::Quit => return Ok(false),
+            PromptEvent::Resize(new_cols, new_rows) => {
+                *term_cols = new_cols;
+                *term_rows = new_rows;
+                execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(ui::centered_column(initial_prompt, *term_cols), *term_rows / 2), Print(initial_prompt))
+                    .context("Failed to re-display initial prompt on resize")?;
+                stdout.flush().context("Failed to flush stdout for prompt resize")?;
+            }
+            PromptEvent::Ignore => {}
         }
     }
+}
+
+/// Shows a centered 3-2-1 countdown, then starts the timer.
+///
+/// Key presses during the countdown are ignored; resizes re-center the number.
+fn run_countdown(stdout: &mut impl Write, events: &mut impl EventSource, game_state: &mut GameState, term_cols: &mut u16, term_rows: &mut u16) -> Result<()> {
+    let countdown_started = events.now();
+    let mut shown: Option<u64> = None;
+    while let Some(remaining) = game_state.tick_countdown_at(countdown_started, events.now()) {
+        if shown != Some(remaining) {
+            let text = remaining.to_string();
+            execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(ui::centered_column(&text, *term_cols), *term_rows / 2), Print(&text))
+                .context("Failed to display countdown")?;
+            stdout.flush().context("Failed to flush stdout for countdown")?;
+            shown = Some(remaining);
+        }
+        if events.poll(Duration::from_millis(50)).context("Event polling failed during countdown")? {
+            if let Event::Resize(new_cols, new_rows) = events.read().context("Failed to read event during countdown")? {
+                *term_cols = new_cols;
+                *term_rows = new_rows;
+                shown = None; // Redraw at the new center.
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the control sequence that starts the configured error feedback, if any.
+///
+/// A flash only inverts the screen mode, never text attributes, so switching it off with
+/// `SCREEN_INVERT_OFF` restores the screen exactly.
+fn error_feedback_sequence(feedback: &ErrorFeedback) -> Option<&'static str> {
+    match feedback {
+        ErrorFeedback::Off => None,
+        ErrorFeedback::Bell => Some("\x07"),
+        ErrorFeedback::Flash => Some(SCREEN_INVERT_ON),
+    }
+}
+
+/// Decides when the screen needs redrawing, so an idle game does not repaint (and flicker) constantly.
+///
+/// A redraw is due after anything marked the screen dirty (input, resizes, the game ending)
+/// and whenever the timer reaches a new whole second, which keeps the timer display current.
+#[derive(Debug)]
+struct RedrawTracker {
+    /// Whether something changed since the last redraw.
+    dirty: bool,
+    /// The whole elapsed second shown by the last redraw.
+    drawn_second: Option<u64>,
+}
+
+impl RedrawTracker {
+    /// Creates a tracker whose first check always asks for a redraw.
+    fn new() -> Self {
+        RedrawTracker { dirty: true, drawn_second: None }
+    }
+
+    /// Records that the screen is out of date.
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns whether to redraw now, at `elapsed_seconds` into the game, and if so assumes it is done.
+    fn take_redraw(&mut self, elapsed_seconds: f64) -> bool {
+        let second = elapsed_seconds as u64;
+        let due = self.dirty || self.drawn_second != Some(second);
+        if due {
+            self.dirty = false;
+            self.drawn_second = Some(second);
+        }
+        due
+    }
+}
+
+/// Collects `first` and every further event `next_ready` returns without blocking.
+///
+/// `next_ready` yields `Ok(None)` once no more events are pending.
+fn drain_events(first: Event, mut next_ready: impl FnMut() -> std::io::Result<Option<Event>>) -> std::io::Result<Vec<Event>> {
+    let mut events = vec![first];
+    while let Some(event) = next_ready()? {
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// A change to the typed text, made by a key press or a paste.
+///
+/// Runs are recorded as the edits made in them (see `GameState::edit_log`), so a replay goes
+/// through exactly the same steps as the original run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Edit {
+    /// A character typed.
+    Char(char),
+    /// Backspace.
+    Backspace,
+    /// Clearing the current word (Ctrl+Backspace, Ctrl+W or Ctrl+H).
+    DeleteWord,
+    /// Enter: a line break in Code mode, elsewhere maybe the finish key.
+    Enter,
+    /// Text pasted in one go.
+    Paste(String),
+}
+
+/// An edit as logged in `GameState::edit_log`, and saved one per line by `--record`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggedEdit {
+    /// Elapsed seconds into the run at which the edit was made.
+    pub at: f64,
+    /// The edit itself.
+    pub edit: Edit,
+    /// The character the text expected when the edit was made (see `GameState::expected_char`).
+    pub expected: Option<char>,
+}
+
+impl Edit {
+    /// Returns the edit `key_event` makes, or `None` for a key that does not edit the text.
+    pub fn from_key(key_event: &KeyEvent) -> Option<Self> {
+        match key_event.code {
+            // Terminals report Ctrl+Backspace in different ways (many Unix ones send Ctrl+H); Ctrl+W is the shell shortcut.
+            KeyCode::Backspace if key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => Some(Edit::DeleteWord),
+            KeyCode::Char('w') | KeyCode::Char('h') if key_event.modifiers.contains(KeyModifiers::CONTROL) => Some(Edit::DeleteWord),
+            KeyCode::Backspace => Some(Edit::Backspace),
+            KeyCode::Char(c) => Some(Edit::Char(c)),
+            KeyCode::Enter => Some(Edit::Enter),
+            _ => None,
+        }
+    }
+}
+
+/// Applies a key press that edits the typed text: characters, Backspace, Enter and the
+/// delete-word shortcuts (see `Edit::from_key`).
+///
+/// This is everything the game loop does to `game_state` for such a key, with no terminal
+/// involved. Keys that control the game itself (Esc, Tab, Ctrl+P, Ctrl+C) are handled by the
+/// game loop, and any other key is ignored.
+pub fn apply_key(game_state: &mut GameState, key_event: KeyEvent) {
+    if let Some(edit) = Edit::from_key(&key_event) {
+        apply_edit(game_state, edit);
+    }
+}
+
+/// Applies `edit` to `game_state` and adds it to the run's `edit_log`, timed as of the last input.
+pub fn apply_edit(game_state: &mut GameState, edit: Edit) {
+    game_state.edit_log.push(LoggedEdit {
+        at: game_state.input_elapsed_secs(),
+        edit: edit.clone(),
+        expected: game_state.expected_char(),
+    });
+    match edit {
+        Edit::DeleteWord => {
+            trace!("Delete word pressed. Errors: '{}', Input: '{}'", game_state.errors, game_state.user_input);
+            game_state.handle_delete_word();
+        }
+        Edit::Backspace => {
+            trace!("Backspace pressed. Errors: '{}', Input: '{}'", game_state.errors, game_state.user_input);
+            game_state.handle_backspace();
+        }
+        Edit::Char(c) => {
+            trace!("Char '{}' pressed.", c);
+            game_state.handle_char(c);
+        }
+        // Enter only ends a line in Code mode; elsewhere it is not part of the text, but may finish the run.
+        Edit::Enter if game_state.word_separator() == '\n' => {
+            trace!("Enter pressed.");
+            game_state.handle_char('\n');
+        }
+        Edit::Enter => {
+            trace!("Enter pressed.");
+            game_state.finish_with_enter();
+        }
+        Edit::Paste(text) => {
+            trace!("Pasted {} chars.", text.chars().count());
+            game_state.handle_paste(&text);
+        }
+    }
+}
+
+/// Restarts the game in `game_state` with the same configuration and freshly chosen words or quote.
+fn restart_with_new_text(game_state: &mut GameState) -> Result<()> {
+    let (words_for_game, quote_starts) = choose_game_text(
+        &game_state.config, &game_state.all_loaded_words, &game_state.all_loaded_quotes,
+        &game_state.quote_marks, &mut game_state.rng)
+        .context("Failed to get words for restarted game")?;
+    game_state.restart(words_for_game);
+    game_state.set_quotes(quote_starts);
+    Ok(())
+}
+
+/// Toggles the quote just typed as a favorite (`favorite`) or as blacklisted, and saves the
+/// quote marks. Does nothing outside Quote mode. A failed save is logged; the mark still
+/// applies for the rest of the session.
+fn mark_quote(game_state: &mut GameState, favorite: bool) {
+    let Some(quote) = &game_state.quote else { return };
+    if favorite {
+        game_state.quote_marks.toggle_favorite(quote);
+    } else {
+        game_state.quote_marks.toggle_blacklisted(quote);
+    }
+    debug!("Quote {} marks changed: favorite {}, blacklisted {}.", quote_marks::quote_key(quote),
+        game_state.quote_marks.is_favorite(quote), game_state.quote_marks.is_blacklisted(quote));
+    let Some(path) = quote_marks::quote_marks_file_path() else {
+        warn!("Could not determine the config directory; quote marks are not saved.");
+        return;
+    };
+    if let Err(e) = quote_marks::save_quote_marks(&path, &game_state.quote_marks) {
+        warn!("Failed to save quote marks: {:#}", e);
+    }
+}
+
+/// Runs the main game loop, handling user input, game state updates, and rendering.
+///
+/// From the game-over screen, Enter repeats the test with the same words, R starts a new test with
+/// the same configuration, F and B mark the quote as a favorite or blacklist it, and Esc leaves. Ctrl+C leaves at any point and asks the caller to exit
+/// (see `GameResult::exit_requested`); pressed during a run, it ends the run first, and the result is marked
+/// `interrupted` so the caller can report the partial statistics. Returns the statistics of the last run once the user leaves. The terminal
+/// is restored to its normal mode afterwards, also when the game fails or panics.
+pub fn run_game(config: GameConfig, all_words: Vec<String>, all_quotes: Vec<Quote>) -> Result<GameResult> {
+    let guard = TerminalGuard::enable()?;
+    let history_path = stats::history_file_path();
+    let result = play_game(&mut stdout(), &mut TerminalEvents, history_path.as_deref(), config, all_words, all_quotes)?;
+    guard.restore()?;
+    Ok(result)
+}
+
+/// The body of `run_game`, run while the terminal is in raw mode.
+///
+/// The screen is drawn to `stdout`, and input, the terminal size and the time all come from
+/// `events`. Finished runs are saved to the results history at `history_path`, and personal
+/// bests and weak keys are read from it; with no path, nothing is saved.
+fn play_game(
+    stdout: &mut impl Write,
+    events: &mut impl EventSource,
+    history_path: Option<&Path>,
+    mut config: GameConfig,
+    all_words: Vec<String>,
+    all_quotes: Vec<Quote>,
+) -> Result<GameResult> {
+    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::Hide).context("Failed to clear screen or hide cursor")?;
+
+    if config.weak_keys {
+        config.weak_chars = history_path
+            .map(|path| stats::weak_chars(&stats::load_history(path)))
+            .unwrap_or_default();
+        debug!("Weak-key drill favoring {:?}.", config.weak_chars);
+    }
+
+    let quote_marks = quote_marks::quote_marks_file_path()
+        .map(|path| quote_marks::load_quote_marks(&path))
+        .unwrap_or_default();
+    let mut game_state = start_session(config, all_words, all_quotes, quote_marks)?;
+    let (mut term_cols, mut term_rows) = events.size().context("Failed to get terminal size")?;
+
+    if !wait_for_start(stdout, events, &mut game_state, &mut term_cols, &mut term_rows)? {
+        debug!("Ctrl+C pressed at the start prompt. Exiting.");
+        return Ok(GameResult { exit_requested: true, ..GameResult::from_state(&game_state) });
+    }
+
+    // Whether the timer was stopped because the terminal got too small, so it is resumed afterwards.
+    let mut paused_for_resize = false;
+    // Whether the last drawn footer suggested Backspace (see `GameState::backspace_hint_due`).
+    let mut backspace_hint_shown = false;
+    // Whether the user asked to leave the program (Ctrl+C) rather than return to the menu.
+    let mut exit_requested = false;
+    // Whether that Ctrl+C ended a run in progress.
+    let mut interrupted = false;
+    let mut redraw = RedrawTracker::new();
+    // When the current error flash should end, if the screen is inverted.
+    let mut flash_until: Option<Instant> = None;
 
     'game_loop: loop {
-        let elapsed_seconds = game_state.start_time.map_or(0.0, |st| st.elapsed().as_secs_f64());
+        if ui::terminal_is_too_small(term_cols, term_rows) {
+            if game_state.timer.is_running() {
+                game_state.timer.stop_at(events.now());
+                paused_for_resize = true;
+            }
+            redraw.mark_dirty();
+            ui::display_terminal_too_small(stdout, term_cols, term_rows)
+                .context("Failed to display terminal size notice")?;
+            // Key presses other than Ctrl+C are ignored until the terminal is large enough again.
+            if events.poll(Duration::from_millis(100)).context("Event polling failed while terminal too small")? {
+                match events.read().context("Failed to read event while terminal too small")? {
+                    Event::Resize(new_cols, new_rows) => {
+                        term_cols = new_cols; term_rows = new_rows;
+                    }
+                    Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press && is_quit_key(&key_event) => {
+                        debug!("Ctrl+C pressed while the terminal was too small. Exiting.");
+                        interrupted = !game_state.game_over;
+                        game_state.end_game_at(events.now());
+                        game_state.quit_early |= interrupted;
+                        exit_requested = true;
+                        break 'game_loop;
+                    }
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if paused_for_resize {
+            game_state.timer.start_at(events.now());
+            paused_for_resize = false;
+        }
+        if flash_until.is_some_and(|until| events.now() >= until) {
+            execute!(stdout, Print(SCREEN_INVERT_OFF)).context("Failed to end error flash")?;
+            flash_until = None;
+        }
+
+        if game_state.pause_if_idle_at(events.now()) {
+            debug!("No input for {:?}s. Paused at {:.2}s.", game_state.config.idle_timeout, game_state.timer.elapsed_secs_at(events.now()));
+            redraw.mark_dirty();
+        }
+        let elapsed_seconds = game_state.timer.elapsed_secs_at(events.now());
 
         if !game_state.game_over {
+            game_state.record_wpm_sample(elapsed_seconds);
             let mut game_should_end = false;
             match game_state.config.game_type {
                 GameType::Time => {
                     if elapsed_seconds >= game_state.config.time_seconds.unwrap_or(0) as f64 { game_should_end = true; }
+                    game_state.refill_words_if_needed().context("Failed to refill Time word list")?;
                 }
-                GameType::Words => {
-                    if game_state.current_word_index >= game_state.config.word_count.unwrap_or(0) as usize 
-                       && !game_state.words_to_type.is_empty() { game_should_end = true; }
+                GameType::Words | GameType::Quote | GameType::Custom | GameType::Code => {
+                    game_should_end = game_state.is_text_complete() || game_state.finished_with_enter;
                 }
-                GameType::Quote => {
-                    if game_state.current_word_index >= game_state.words_to_type.len() 
-                       && !game_state.words_to_type.is_empty() { game_should_end = true; }
+                GameType::Zen => {
+                    // Zen mode only ends on Esc; keep the word stream topped up instead.
+                    game_state.refill_words_if_needed().context("Failed to refill Zen word list")?;
                 }
             }
+            if !game_should_end && game_state.reached_max_duration(elapsed_seconds) {
+                debug!("Run reached the {:?}-minute cap at {:.2}s.", game_state.config.max_minutes, elapsed_seconds);
+                game_should_end = true;
+            }
             if game_should_end {
                 debug!("Game over condition met. Type: {:?}, Elapsed: {:.2}s, Word Index: {}/{}", 
                     game_state.config.game_type, elapsed_seconds, game_state.current_word_index, game_state.words_to_type.len());
-                game_state.game_over = true;
-                game_state.final_elapsed_time_seconds = Some(elapsed_seconds);
+                let final_seconds = game_state.end_game_at(events.now());
+                record_run(&mut game_state, final_seconds, history_path);
+                redraw.mark_dirty();
             }
         }
 
         if game_state.game_over {
-            display_game_over_screen(&mut stdout, &game_state, term_cols, term_rows)
-                .context("Failed to display game over screen")?;
-            if event::poll(Duration::from_millis(100)).context("Event polling failed on game over screen")? {
-                 match event::read().context("Failed to read event on game over screen")? {
-                    Event::Key(_) => break 'game_loop,
+            if redraw.take_redraw(elapsed_seconds) {
+                ui::display_game_over_screen(stdout, &game_state, term_cols, term_rows)
+                    .context("Failed to display game over screen")?;
+            }
+            if events.poll(Duration::from_millis(100)).context("Event polling failed on game over screen")? {
+                 redraw.mark_dirty();
+                 match events.read().context("Failed to read event on game over screen")? {
+                    Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press => match key_event.code {
+                        KeyCode::Esc => break 'game_loop,
+                        _ if is_quit_key(&key_event) => {
+                            exit_requested = true;
+                            break 'game_loop;
+                        }
+                        KeyCode::Enter => {
+                            debug!("Enter pressed on game over screen. Repeating the same words.");
+                            game_state.restart_same_text();
+                            if !wait_for_start(stdout, events, &mut game_state, &mut term_cols, &mut term_rows)? {
+                                exit_requested = true;
+                                break 'game_loop;
+                            }
+                        }
+                        KeyCode::Char('r') | KeyCode::Char('R') => {
+                            debug!("R pressed on game over screen. Restarting with a fresh word selection.");
+                            restart_with_new_text(&mut game_state)?;
+                            if !wait_for_start(stdout, events, &mut game_state, &mut term_cols, &mut term_rows)? {
+                                exit_requested = true;
+                                break 'game_loop;
+                            }
+                        }
+                        KeyCode::Char(key @ ('f' | 'F' | 'b' | 'B')) => {
+                            mark_quote(&mut game_state, key.eq_ignore_ascii_case(&'f'));
+                        }
+                        _ => {}
+                    },
                     Event::Resize(new_cols, new_rows) => {
                         term_cols = new_cols; term_rows = new_rows;
                     }
@@ -342,72 +2039,1773 @@ pub fn run_game(config: GameConfig, all_words: Vec<String>, all_quotes: Vec<Quot
                  }
             }
         } else {
-            if event::poll(Duration::from_millis(100)).context("Event polling failed in active game")? { 
-                match event::read().context("Failed to read event in active game")? {
-                    Event::Key(key_event) => {
-                        if key_event.kind == event::KeyEventKind::Press {
+            if events.poll(Duration::from_millis(100)).context("Event polling failed in active game")? { 
+                // Apply everything already buffered (e.g. a burst of pasted keys) before redrawing once.
+                let first_event = events.read().context("Failed to read event in active game")?;
+                let pending_events = drain_events(first_event, || {
+                    if events.poll(Duration::ZERO)? { Ok(Some(events.read()?)) } else { Ok(None) }
+                }).context("Failed to read buffered events in active game")?;
+                redraw.mark_dirty();
+                let wrong_keystrokes_before = game_state.wrong_keystrokes;
+                if pending_events.iter().any(|event| matches!(event, Event::Key(_) | Event::Paste(_))) {
+                    game_state.note_input_at(events.now());
+                }
+                for event in pending_events {
+                    // Keys buffered past the end of the text must not count against the finished run.
+                    if game_state.game_over || game_state.is_text_complete() || game_state.finished_with_enter {
+                        break;
+                    }
+                    match event {
+                        Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press => {
                             match key_event.code {
                                 KeyCode::Esc => { 
                                     debug!("Escape key pressed. Ending game.");
-                                    game_state.game_over = true; 
-                                    game_state.final_elapsed_time_seconds = Some(elapsed_seconds); 
-                                },
-                                KeyCode::Backspace => {
-                                    trace!("Backspace pressed. Errors: '{}', Input: '{}'", game_state.errors, game_state.user_input);
-                                    if !game_state.errors.is_empty() { game_state.errors.pop(); } 
-                                    else if !game_state.user_input.is_empty() {
-                                        game_state.user_input.pop();
-                                        game_state.current_char_index = game_state.current_char_index.saturating_sub(1);
+                                    let final_seconds = game_state.end_game_at(events.now());
+                                    // Esc is the normal way to finish Zen mode, so that run still counts.
+                                    if game_state.config.game_type == GameType::Zen {
+                                        record_run(&mut game_state, final_seconds, history_path);
+                                    } else {
+                                        game_state.quit_early = true;
                                     }
+                                },
+                                // Ctrl+C ends the run where it stands and leaves the program.
+                                _ if is_quit_key(&key_event) => {
+                                    debug!("Ctrl+C pressed. Ending game and exiting.");
+                                    game_state.end_game_at(events.now());
+                                    game_state.quit_early = true;
+                                    exit_requested = true;
+                                    interrupted = true;
+                                    break 'game_loop;
                                 }
-                                KeyCode::Char(c) => {
-                                    trace!("Char '{}' pressed.", c);
-                                    game_state.typed_chars_total += 1; 
-                                    if game_state.current_word_index >= game_state.words_to_type.len() { 
-                                        warn!("Character typed after all words completed. Current index: {}, Total words: {}", 
-                                            game_state.current_word_index, game_state.words_to_type.len());
-                                        continue; 
-                                    }
-                                    // Ensure target_word is valid before indexing
-                                    let target_word = &game_state.words_to_type[game_state.current_word_index];
-                                    if game_state.current_char_index < target_word.len() {
-                                        if c == target_word.chars().nth(game_state.current_char_index).unwrap_or_default() && game_state.errors.is_empty() {
-                                            game_state.user_input.push(c);
-                                            game_state.current_char_index += 1;
-                                            game_state.correct_chars_total += 1;
-                                        } else { game_state.errors.push(c); }
-                                    } else { 
-                                        if c == ' ' && game_state.errors.is_empty() {
-                                            game_state.current_word_index += 1;
-                                            game_state.current_char_index = 0;
-                                            game_state.user_input.clear();
-                                            game_state.correct_chars_total += 1; 
-                                        } else { game_state.errors.push(c); }
+                                KeyCode::Char('p') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                                    game_state.toggle_pause_at(events.now());
+                                    debug!("Pause toggled. Paused: {}, Elapsed: {:.2}s", game_state.is_paused(), game_state.timer.elapsed_secs_at(events.now()));
+                                }
+                                // While paused, only Esc and Ctrl+P do anything.
+                                _ if game_state.is_paused() => {}
+                                KeyCode::Tab => {
+                                    debug!("Tab pressed. Restarting with a fresh word selection.");
+                                    restart_with_new_text(&mut game_state)?;
+                                    if !wait_for_start(stdout, events, &mut game_state, &mut term_cols, &mut term_rows)? {
+                                        exit_requested = true;
+                                        break 'game_loop;
                                     }
+                                    // Anything still buffered was typed for the old text.
+                                    break;
                                 }
-                                _ => {} 
+                                _ => apply_key(&mut game_state, key_event),
                             }
                         }
+                        Event::Paste(text) if !game_state.is_paused() => apply_edit(&mut game_state, Edit::Paste(text)),
+                        Event::Resize(new_cols, new_rows) => { 
+                            term_cols = new_cols; term_rows = new_rows;
+                        }
+                        _ => {} 
                     }
-                    Event::Resize(new_cols, new_rows) => { 
-                        term_cols = new_cols; term_rows = new_rows;
+                    if game_state.restarted_on_error {
+                        game_state.restarted_on_error = false;
+                        if !wait_for_start(stdout, events, &mut game_state, &mut term_cols, &mut term_rows)? {
+                            exit_requested = true;
+                            break 'game_loop;
+                        }
+                        // Anything still buffered was typed for the failed attempt.
+                        break;
+                    }
+                }
+                if game_state.wrong_keystrokes > wrong_keystrokes_before {
+                    if let Some(sequence) = error_feedback_sequence(&game_state.config.error_feedback) {
+                        execute!(stdout, Print(sequence)).context("Failed to give error feedback")?;
+                        if game_state.config.error_feedback == ErrorFeedback::Flash {
+                            flash_until = Some(events.now() + ERROR_FLASH_DURATION);
+                        }
                     }
-                    _ => {} 
                 }
             }
-            display_game_interface(&mut stdout, &game_state, term_cols, term_rows)
-                .context("Failed to display game interface")?;
+            // The hint comes and goes with time as well as keys, so it needs a redraw of its own.
+            if game_state.backspace_hint_due(elapsed_seconds) != backspace_hint_shown {
+                backspace_hint_shown = !backspace_hint_shown;
+                redraw.mark_dirty();
+            }
+            if redraw.take_redraw(elapsed_seconds) {
+                if elapsed_seconds > 0.01 {
+                    game_state.update_live_wpm(elapsed_seconds);
+                }
+                ui::display_game_interface(stdout, &game_state, elapsed_seconds, term_cols, term_rows)
+                    .context("Failed to display game interface")?;
+            }
         }
         
-        let (current_cols, current_rows) = terminal::size().context("Failed to get terminal size during loop")?;
+        let (current_cols, current_rows) = events.size().context("Failed to get terminal size during loop")?;
         if current_cols != term_cols || current_rows != term_rows {
              term_cols = current_cols;
              term_rows = current_rows;
+             redraw.mark_dirty();
              // Screen will be redrawn at the start of the next iteration or by specific display calls.
         }
     } 
 
-    execute!(stdout, cursor::Show).context("Failed to show cursor")?;
-    terminal::disable_raw_mode().context("Failed to disable raw mode")?;
-    Ok(())
+    game_state.add_attempt_to_session();
+    let session = game_state.config.cumulative
+        .then(|| game_state.session_totals.result(game_state.config.error_penalty))
+        .filter(|session| session.attempts > 0);
+    if let Some(session) = session.as_ref().filter(|_| !exit_requested) {
+        show_session_summary(stdout, events, session, term_cols, term_rows)?;
+    }
+    Ok(GameResult { exit_requested, interrupted, session, ..GameResult::from_state(&game_state) })
+}
+
+/// Shows the session summary until a key is pressed.
+fn show_session_summary(
+    stdout: &mut impl Write,
+    events: &mut impl EventSource,
+    session: &SessionResult,
+    mut term_cols: u16,
+    mut term_rows: u16,
+) -> Result<()> {
+    loop {
+        ui::display_session_summary(stdout, session, term_cols, term_rows).context("Failed to display session summary")?;
+        match events.read().context("Failed to read event on session summary")? {
+            Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press => return Ok(()),
+            Event::Resize(new_cols, new_rows) => (term_cols, term_rows) = (new_cols, new_rows),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::QuoteLength;
+    use crate::data_loader::WordWeights;
+    use crate::ui::{goal_description, most_missed_chars, most_missed_line};
+
+    /// Words picked from the `seeded_selection_is_reproducible` list with seed 42.
+    const SEEDED_SELECTION: [&str; 3] = ["banana", "date", "grape"];
+
+    /// Asserts that `actual` is within a small tolerance of `expected`.
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn wpm_counts_characters_over_a_fixed_duration() {
+        // "héllo wörld" is 11 characters but 13 bytes. Typed with one mistake, over 12 seconds.
+        let correct = "héllo wörld".chars().count();
+        let stats = calculate_wpm(correct, correct + 1, 12.0, &WpmScheme::Net, DEFAULT_ERROR_PENALTY);
+        assert_close(stats.gross_wpm, 12.0);
+        assert_close(stats.net_wpm, 7.0);
+        assert_close(stats.accuracy, 100.0 * 11.0 / 12.0);
+    }
+
+    #[test]
+    fn wpm_schemes_score_the_same_run_differently() {
+        // 60 characters in a minute, 6 of them wrong: 12 gross WPM at 90% accuracy.
+        let score = |scheme| calculate_wpm(54, 60, 60.0, &scheme, DEFAULT_ERROR_PENALTY);
+        assert_close(score(WpmScheme::Net).wpm, 6.0);
+        assert_close(score(WpmScheme::Simple).wpm, 10.8);
+        assert_close(score(WpmScheme::Raw).wpm, 12.0);
+        // Only the headline figure depends on the scheme.
+        for scheme in [WpmScheme::Simple, WpmScheme::Raw] {
+            let stats = score(scheme);
+            assert_eq!((stats.gross_wpm, stats.net_wpm, stats.accuracy), (12.0, 6.0, 90.0));
+        }
+        // Without errors every scheme agrees.
+        let clean = |scheme| calculate_wpm(60, 60, 60.0, &scheme, DEFAULT_ERROR_PENALTY).wpm;
+        assert_eq!([clean(WpmScheme::Net), clean(WpmScheme::Simple)], [clean(WpmScheme::Raw); 2]);
+    }
+
+    #[test]
+    fn error_penalty_weighs_the_errors_in_net_wpm() {
+        // 60 characters in a minute, 6 of them wrong: 12 gross WPM, less 6 errors per minute.
+        let net = |error_penalty| calculate_wpm(54, 60, 60.0, &WpmScheme::Net, error_penalty).net_wpm;
+        assert_eq!(net(DEFAULT_ERROR_PENALTY), 12.0 - 6.0);
+        assert_eq!(net(0.5), 12.0 - 3.0);
+        // A large penalty never takes net WPM below zero.
+        assert_eq!(net(5.0), 0.0);
+        // Without a penalty, net WPM is gross WPM, and so is the headline figure.
+        let stats = calculate_wpm(54, 60, 60.0, &WpmScheme::Net, 0.0);
+        assert_eq!((stats.net_wpm, stats.wpm), (stats.gross_wpm, stats.gross_wpm));
+        // The default reproduces the unweighted formula exactly, whatever the numbers.
+        for (correct, typed, seconds) in [(7, 8, 6.0), (113, 131, 37.3), (0, 4, 2.5)] {
+            let minutes = seconds / 60.0;
+            let gross = (typed as f64 / STANDARD_WORD_LENGTH) / minutes;
+            let unweighted = (gross - (typed - correct) as f64 / minutes).max(0.0);
+            assert_eq!(calculate_wpm(correct, typed, seconds, &WpmScheme::Net, DEFAULT_ERROR_PENALTY).net_wpm, unweighted);
+        }
+    }
+
+    #[test]
+    fn consistency_of_empty_samples_is_perfect() {
+        assert_close(calculate_consistency(&[]), 100.0);
+        assert_close(calculate_consistency(&[0.0, 0.0]), 100.0);
+    }
+
+    #[test]
+    fn consistency_of_steady_run_is_perfect() {
+        assert_close(calculate_consistency(&[72.0; 30]), 100.0);
+    }
+
+    #[test]
+    fn consistency_drops_for_variable_runs() {
+        let slightly_uneven = calculate_consistency(&[60.0, 64.0, 58.0, 62.0]);
+        let erratic = calculate_consistency(&[10.0, 120.0, 0.0, 90.0, 5.0]);
+        assert!(slightly_uneven > 90.0, "got {slightly_uneven}");
+        assert!(erratic < 40.0, "got {erratic}");
+        assert!((0.0..=100.0).contains(&erratic));
+    }
+
+    #[test]
+    fn wpm_samples_cover_each_interval() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["hello".to_string()]);
+        "hello".chars().for_each(|c| state.handle_char(c));
+        state.record_wpm_sample(0.5);
+        assert!(state.wpm_samples.is_empty());
+        state.record_wpm_sample(1.0);
+        state.record_wpm_sample(2.0);
+        assert_eq!(state.wpm_samples.len(), 2);
+        assert_close(state.wpm_samples[0].1, 60.0);
+        // Nothing was typed during the second interval.
+        assert_close(state.wpm_samples[1].1, 0.0);
+    }
+
+    #[test]
+    fn rolling_wpm_covers_the_last_few_seconds() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["hello".to_string()]);
+        assert_eq!(state.rolling_wpm(5.0), Some(0.0));
+        // Cold start: no figure in the first second, then the run so far is the window.
+        state.record_keystroke(0.2);
+        state.record_keystroke(0.4);
+        assert_eq!(state.rolling_wpm(0.5), None);
+        (1..=8).for_each(|i| state.record_keystroke(0.4 + 0.2 * i as f64));
+        assert_close(state.rolling_wpm(2.0).unwrap(), 10.0 / 5.0 / (2.0 / 60.0));
+        // Ten keys a second for three seconds is 120 WPM, whatever came before.
+        (1..=30).for_each(|i| state.record_keystroke(5.0 + 0.1 * i as f64));
+        assert_close(state.rolling_wpm(8.0).unwrap(), 120.0);
+        assert_eq!(state.recent_keystrokes.len(), 30, "older keys are forgotten");
+        // A pause in typing brings it down, and then to zero.
+        assert_close(state.rolling_wpm(9.5).unwrap(), 60.0);
+        assert_eq!(state.rolling_wpm(11.5), Some(0.0));
+    }
+
+    #[test]
+    fn timer_accumulates_only_running_time() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut timer = GameTimer::new();
+        assert!(!timer.has_started());
+        assert_close(timer.elapsed_secs_at(at(5)), 0.0);
+
+        timer.start_at(at(0));
+        timer.start_at(at(1)); // Starting twice keeps the original start.
+        assert_close(timer.elapsed_secs_at(at(2)), 2.0);
+        timer.stop_at(at(2));
+        timer.stop_at(at(4)); // Stopping twice adds nothing.
+        assert!(timer.has_started() && !timer.is_running());
+        assert_close(timer.elapsed_secs_at(at(10)), 2.0);
+        timer.start_at(at(10));
+        timer.stop_at(at(13));
+        assert_close(timer.elapsed_secs_at(at(30)), 5.0);
+    }
+
+    #[test]
+    fn idle_runs_pause_without_counting_the_idle_time() {
+        let base = Instant::now();
+        let at = |secs: u64| base + Duration::from_secs(secs);
+        let config = GameConfig { idle_timeout: Some(10), ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        assert!(!state.pause_if_idle_at(at(60)), "nothing to pause before the start");
+        state.timer.start_at(at(0));
+        state.note_input_at(at(0));
+        state.note_input_at(at(4));
+        assert!(!state.pause_if_idle_at(at(13)));
+        assert!(state.pause_if_idle_at(at(14)));
+        assert!(state.is_paused() && state.idle_paused);
+        assert_eq!(state.timer.elapsed_secs_at(at(30)), 4.0);
+        assert!(!state.pause_if_idle_at(at(40)), "already paused");
+        state.toggle_pause_at(at(40));
+        assert!(!state.idle_paused);
+        assert_eq!(state.timer.elapsed_secs_at(at(41)), 5.0);
+
+        state.config.idle_timeout = None;
+        assert!(!state.pause_if_idle_at(at(1000)));
+    }
+
+    #[test]
+    fn paused_time_is_not_counted() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["word".to_string()]);
+        state.toggle_pause_at(at(0));
+        assert!(!state.is_paused(), "pausing before the game starts should do nothing");
+
+        state.timer.start_at(start);
+        state.toggle_pause_at(at(2));
+        assert!(state.is_paused());
+        assert_close(state.timer.elapsed_secs_at(at(10)), 2.0);
+        state.toggle_pause_at(at(10));
+        assert_close(state.timer.elapsed_secs_at(at(13)), 5.0);
+        state.toggle_pause_at(at(13));
+        state.toggle_pause_at(at(20));
+        assert!(!state.is_paused());
+        assert_close(state.timer.elapsed_secs_at(at(21)), 6.0);
+
+        state.end_game();
+        assert!(!state.is_paused() && !state.timer.is_running());
+    }
+
+    #[test]
+    fn restart_yields_a_pristine_state() {
+        let all_words = vec!["alpha".to_string(), "beta".to_string()];
+        let mut state = GameState::new(GameConfig::new(), all_words.clone(), Vec::new(), vec!["alpha".to_string()]);
+        state.timer.start();
+        "alpha bx".chars().for_each(|c| state.handle_char(c));
+        state.record_wpm_sample(1.5);
+        state.end_game();
+
+        state.restart(vec!["beta".to_string()]);
+        assert_eq!(state.words_to_type, ["beta"]);
+        assert_eq!((state.current_word_index, state.current_char_index), (0, 0));
+        assert!(state.user_input.is_empty() && state.errors.is_empty() && state.typed_words.is_empty());
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (0, 0));
+        assert!(!state.timer.has_started());
+        assert!(!state.game_over);
+        assert!(state.final_elapsed_time_seconds.is_none());
+        assert!(state.wpm_samples.is_empty());
+        // The next sample only covers what is typed after the restart.
+        "be".chars().for_each(|c| state.handle_char(c));
+        state.record_wpm_sample(1.0);
+        assert_close(state.wpm_samples[0].1, 24.0);
+        assert_eq!(state.all_loaded_words, all_words);
+    }
+
+    #[test]
+    fn backspace_crosses_into_previous_word_and_back() {
+        let words = vec!["hi".to_string(), "there".to_string()];
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+        state.handle_backspace();
+        assert_eq!((state.current_word_index, state.current_char_index), (0, 0));
+
+        "hi t".chars().for_each(|c| state.handle_char(c));
+        state.handle_backspace();
+        state.handle_backspace();
+        assert_eq!(state.current_word_index, 0);
+        assert_eq!(state.current_char_index, 2);
+        assert_eq!(state.user_input, "hi");
+        assert!(state.typed_words.is_empty());
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (2, 2));
+
+        state.handle_backspace();
+        assert_eq!(state.user_input, "h");
+        "i there ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(state.current_word_index, 2);
+        assert_eq!(state.typed_words, ["hi", "there"]);
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (9, 9));
+    }
+
+    #[test]
+    fn delete_word_clears_current_word_and_keeps_stats_consistent() {
+        let words = vec!["hi".to_string(), "there".to_string()];
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+        "hi ".chars().for_each(|c| state.handle_char(c));
+        "thx".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (5, 6));
+
+        state.handle_delete_word();
+        assert_eq!(state.current_word_index, 1);
+        assert_eq!(state.current_char_index, 0);
+        assert!(state.user_input.is_empty() && state.errors.is_empty());
+        // The deleted "th" no longer counts; the mistyped "x" still does.
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (3, 4));
+
+        "there".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (8, 9));
+
+        // On an untouched word it is a no-op and does not go back to the previous word.
+        state.handle_char(' ');
+        state.handle_delete_word();
+        assert_eq!(state.current_word_index, 2);
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (9, 10));
+    }
+
+    #[test]
+    fn strict_mode_rejects_wrong_keys() {
+        let words = vec!["ab".to_string(), "cd".to_string()];
+        let config = GameConfig { strict: true, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        state.handle_char('a');
+        state.handle_char('x');
+        assert!(state.errors.is_empty());
+        assert_eq!((state.current_char_index, state.typed_chars_total, state.correct_chars_total), (1, 2, 1));
+        // A space cannot skip the rest of the word either.
+        state.handle_char(' ');
+        assert_eq!(state.current_word_index, 0);
+        "b c".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.current_word_index, state.current_char_index), (1, 1));
+        assert_eq!((state.typed_chars_total, state.correct_chars_total), (6, 4));
+    }
+
+    #[test]
+    fn a_mistake_restarts_the_run_only_with_restart_on_error() {
+        let words = vec!["ab".to_string(), "cd".to_string()];
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words.clone());
+        "ab cx".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(progress(&state), (1, 1, 5, 4));
+        assert!(!state.restarted_on_error);
+
+        let config = GameConfig { restart_on_error: true, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words.clone());
+        state.timer.start();
+        state.quote = Some(Quote { text: "ab cd".to_string(), ..Quote::default() });
+        "ab c".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(progress(&state), (1, 1, 4, 4));
+        state.handle_paste("xd");
+        assert!(state.restarted_on_error);
+        // Back at the start of the same text, with nothing counted and the timer waiting for the prompt.
+        assert_eq!(progress(&state), (0, 0, 0, 0));
+        assert_eq!(state.words_to_type, words);
+        assert!(state.quote.is_some() && state.char_stats.is_empty() && !state.timer.has_started());
+    }
+
+    #[test]
+    fn mistyped_words_are_typed_again_with_retry_mistyped() {
+        let words = vec!["ab".to_string(), "cd".to_string()];
+        let config = GameConfig { retry_mistyped: true, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words.clone());
+        // A clean word moves on as usual.
+        "ab ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.current_word_index, state.words_completed), (1, 1));
+        // A corrected mistake still sends the word round again, with the keys all counted.
+        state.handle_char('x');
+        state.handle_backspace();
+        "cd".chars().for_each(|c| state.handle_char(c));
+        assert!(!state.is_text_complete(), "a mistyped last word needs its space");
+        state.handle_char(' ');
+        assert_eq!(progress(&state), (1, 0, 7, 6));
+        assert_eq!((state.word_retries, state.user_input.as_str(), state.words_to_type.clone()), (1, "", words.clone()));
+        "cd".chars().for_each(|c| state.handle_char(c));
+        assert!(state.is_text_complete());
+        assert_eq!(state.words_completed, 1);
+
+        // Without the flag, the same keys finish the text.
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words);
+        "ab x".chars().for_each(|c| state.handle_char(c));
+        state.handle_backspace();
+        "cd".chars().for_each(|c| state.handle_char(c));
+        assert!(state.is_text_complete());
+    }
+
+    #[test]
+    fn space_in_a_partial_word_is_a_mistake_by_default() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["abc".to_string(), "de".to_string()]);
+        "a ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(progress(&state), (0, 1, 2, 1));
+        assert_eq!(state.errors, " ");
+        assert_eq!(state.char_stats[&'b'], (1, 1));
+    }
+
+    #[test]
+    fn space_skips_a_partial_word_with_the_skip_policy() {
+        let config = GameConfig { space_policy: SpacePolicy::Skip, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["abc".to_string(), "de".to_string()]);
+        "a ".chars().for_each(|c| state.handle_char(c));
+        // The space is correct, and 'b' and 'c' are typed and wrong.
+        assert_eq!(progress(&state), (1, 0, 4, 2));
+        assert_eq!((state.char_stats[&'b'], state.char_stats[&'c'], state.char_stats[&' ']), ((1, 1), (1, 1), (1, 0)));
+        assert_eq!((state.words_completed, state.wrong_keystrokes, state.typed_words.clone()), (1, 2, vec!["a".to_string()]));
+        // An unstarted word is not skipped.
+        state.handle_char(' ');
+        assert_eq!((state.current_word_index, state.errors.as_str()), (1, " "));
+        // Backspacing into the skipped word takes back only the space; the misses stay counted.
+        state.handle_backspace();
+        state.handle_backspace();
+        assert_eq!(progress(&state), (0, 1, 4, 1));
+        assert_eq!(state.user_input, "a");
+
+        // A skipped word with anything missed is mistyped, so it is retried with `retry_mistyped`.
+        let config = GameConfig { space_policy: SpacePolicy::Skip, retry_mistyped: true, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["abc".to_string(), "de".to_string()]);
+        "ab ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.current_word_index, state.word_retries), (0, 1));
+        assert!(state.user_input.is_empty());
+    }
+
+    #[test]
+    fn word_retries_are_capped() {
+        let config = GameConfig { retry_mistyped: true, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        let mistype_and_finish = |state: &mut GameState| {
+            state.handle_char('x');
+            state.handle_backspace();
+            "ab ".chars().for_each(|c| state.handle_char(c));
+        };
+        for retry in 1..=MAX_WORD_RETRIES {
+            mistype_and_finish(&mut state);
+            assert_eq!((state.current_word_index, state.word_retries), (0, retry));
+        }
+        mistype_and_finish(&mut state);
+        assert_eq!((state.current_word_index, state.word_retries), (1, 0), "the last retry moves on regardless");
+        assert!(!state.word_mistyped);
+    }
+
+    #[test]
+    fn a_marathon_chains_quotes_and_records_where_each_starts() {
+        let quotes: Vec<Quote> = ["One two.", "Three four five.", "Six."].iter()
+            .map(|text| Quote { text: text.to_string(), source: format!("Source of {}", text), ..Quote::default() })
+            .collect();
+        let config = GameConfig { game_type: GameType::Quote, word_count: None, marathon: Some(3), ..words_config(0, false) };
+        let (words, quote_starts) = choose_game_text(
+            &config, &[], &quotes, &QuoteMarks::default(), &mut StdRng::seed_from_u64(8)).unwrap();
+        assert_eq!(quote_starts.len(), 3);
+        let mut start = 0;
+        for (quote_start, quote) in &quote_starts {
+            assert_eq!(*quote_start, start);
+            let quote_length = quote_words(quote).len();
+            assert_eq!(words[start..start + quote_length], quote_words(quote)[..]);
+            start += quote_length;
+        }
+        assert_eq!(start, words.len());
+
+        let mut state = GameState::new(config, Vec::new(), quotes, words.clone());
+        state.set_quotes(quote_starts.clone());
+        assert_eq!(state.quote.as_ref().map(|quote| &quote.text), Some(&quote_starts[2].1.text));
+        assert_eq!(state.quote_progress(), Some((1, 3)));
+        state.current_word_index = quote_starts[1].0;
+        assert_eq!(state.quote_progress(), Some((2, 3)));
+        state.restart_same_text();
+        assert_eq!((state.words_to_type.clone(), state.quote_starts.len()), (words, 3));
+
+        // Without a marathon, Quote mode still types a single quote.
+        let single = GameConfig { marathon: None, ..state.config.clone() };
+        let (_, quote_starts) = choose_game_text(
+            &single, &[], &state.all_loaded_quotes, &QuoteMarks::default(), &mut StdRng::seed_from_u64(8)).unwrap();
+        assert_eq!(quote_starts.len(), 1);
+    }
+
+    #[test]
+    fn mistakes_are_permanent_without_backspace() {
+        let words = vec!["ab".to_string(), "cd".to_string()];
+        let config = GameConfig { no_backspace: true, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        // The wrong key uses up the 'a' instead of blocking.
+        state.handle_char('x');
+        assert!(state.errors.is_empty());
+        assert_eq!((state.current_char_index, state.user_input.as_str()), (1, "a"));
+        assert_eq!(state.missed_char_indices, vec![0]);
+        state.handle_backspace();
+        state.handle_delete_word();
+        assert_eq!(progress(&state), (0, 1, 1, 0), "corrections are ignored");
+        // In place of the space a wrong key is counted but nothing moves.
+        "bz".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(progress(&state), (0, 2, 3, 1));
+        state.handle_char(' ');
+        assert!(state.missed_char_indices.is_empty(), "the next word starts clean");
+        apply_key(&mut state, press(KeyCode::Backspace));
+        assert_eq!(state.current_word_index, 1, "backspace cannot go back into the previous word");
+        "cd".chars().for_each(|c| state.handle_char(c));
+        assert!(state.is_text_complete());
+        assert_eq!((state.typed_chars_total, state.correct_chars_total, state.wrong_keystrokes), (6, 4, 2));
+    }
+
+    #[test]
+    fn words_completed_counts_each_correct_space() {
+        let words: Vec<String> = ["one", "two", "three", "four"].iter().map(|w| w.to_string()).collect();
+        let config = GameConfig { time_attack: true, ..words_config(4, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        // A space typed mid-word is a mistake, not a completed word.
+        "one tw ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(state.words_completed, 1);
+        state.handle_backspace();
+        "o three ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(state.words_completed, 3);
+        // Backspacing into a completed word takes it back; completing it again counts it once.
+        state.handle_backspace();
+        assert_eq!(state.words_completed, 2);
+        state.handle_char(' ');
+        assert_eq!(state.words_completed, 3);
+        assert_eq!(GameResult::from_state(&state).words_completed, 3);
+        assert_eq!(words_per_minute(state.words_completed, 30.0), 6.0);
+        assert_eq!(words_per_minute(3, 0.0), 0.0);
+    }
+
+    #[test]
+    fn kpm_counts_every_keystroke() {
+        assert_close(calculate_kpm(300, 60.0), 300.0);
+        assert_close(calculate_kpm(50, 12.0), 250.0);
+        // Gross WPM is KPM in five-character words.
+        assert_close(calculate_kpm(50, 12.0) / STANDARD_WORD_LENGTH,
+            calculate_wpm(40, 50, 12.0, &WpmScheme::Raw, DEFAULT_ERROR_PENALTY).gross_wpm);
+        // Too short a time has no rate, the same guard as `calculate_wpm`.
+        assert_eq!(calculate_kpm(5, 0.009), 0.0);
+        assert_eq!(calculate_kpm(0, 0.0), 0.0);
+        assert_close(calculate_kpm(1, 0.01), 6000.0);
+    }
+
+    #[test]
+    fn wrong_keystrokes_are_counted_for_error_feedback() {
+        for strict in [false, true] {
+            let config = GameConfig { strict, ..words_config(1, false) };
+            let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["ab".to_string()]);
+            "axb".chars().for_each(|c| state.handle_char(c));
+            assert_eq!(state.wrong_keystrokes, if strict { 1 } else { 2 }, "strict: {strict}");
+            state.handle_backspace();
+            assert_eq!(state.wrong_keystrokes, if strict { 1 } else { 2 }, "backspace is not a mistake");
+        }
+        assert_eq!(error_feedback_sequence(&ErrorFeedback::Off), None);
+        assert_eq!(error_feedback_sequence(&ErrorFeedback::Bell), Some("\x07"));
+        assert_eq!(error_feedback_sequence(&ErrorFeedback::Flash), Some(SCREEN_INVERT_ON));
+    }
+
+    #[test]
+    fn char_stats_accumulate_per_expected_character() {
+        let words = vec!["tea".to_string(), "set".to_string()];
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words);
+        // Mistype 'e' once (then fix it), and the space once.
+        "tx".chars().for_each(|c| state.handle_char(c));
+        state.handle_backspace();
+        "ea".chars().for_each(|c| state.handle_char(c));
+        state.handle_char('s');
+        state.handle_backspace();
+        " set".chars().for_each(|c| state.handle_char(c));
+
+        assert_eq!(state.char_stats[&'t'], (2, 0));
+        assert_eq!(state.char_stats[&'e'], (3, 1));
+        assert_eq!(state.char_stats[&'a'], (1, 0));
+        assert_eq!(state.char_stats[&' '], (2, 1));
+        assert_eq!(state.char_stats[&'s'], (1, 0));
+        assert_eq!(most_missed_chars(&state.char_stats, 3), vec![(' ', 2, 1), ('e', 3, 1)]);
+        assert_eq!(most_missed_line(&state.char_stats), "Most missed: space 50% (1/2), e 33% (1/3)");
+    }
+
+    #[test]
+    fn typing_multibyte_word_advances_per_char() {
+        // "é" is 2 bytes and "🦀" is 4 bytes; each should take exactly one keystroke.
+        let words = vec!["é🦀x".to_string(), "ok".to_string()];
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+
+        state.handle_char('é');
+        assert_eq!(state.current_char_index, 1);
+        state.handle_char('🦀');
+        assert_eq!(state.current_char_index, 2);
+        assert_eq!(state.user_input, "é🦀");
+
+        // A wrong key is recorded as an error and can be erased again.
+        state.handle_char('y');
+        assert_eq!(state.errors, "y");
+        state.handle_backspace();
+        assert!(state.errors.is_empty());
+
+        state.handle_char('x');
+        state.handle_char(' ');
+        assert_eq!(state.current_word_index, 1);
+        assert_eq!(state.current_char_index, 0);
+        assert!(state.user_input.is_empty());
+        assert_eq!(state.correct_chars_total, 4);
+        assert_eq!(state.typed_chars_total, 5);
+
+        // Backspace at the start of a word goes back into the previous one.
+        state.handle_backspace();
+        assert_eq!(state.current_word_index, 0);
+        assert_eq!(state.current_char_index, 3);
+        assert_eq!(state.user_input, "é🦀x");
+    }
+
+    /// Builds a Words-mode config for `count` words with the given punctuation setting.
+    fn words_config(count: u32, punctuation: bool) -> GameConfig {
+        GameConfig {
+            game_type: GameType::Words,
+            word_count: Some(count),
+            time_seconds: None,
+            difficulty: Difficulty::Hard,
+            punctuation,
+            ..GameConfig::new()
+        }
+    }
+
+    /// Builds a quote of `word_count` words.
+    fn quote_of(word_count: usize) -> Quote {
+        Quote { text: vec!["word"; word_count].join(" "), source: format!("{} words", word_count), ..Quote::default() }
+    }
+
+    #[test]
+    fn custom_text_round_trips_into_words() {
+        let passage = "  Ünïcode — naïve café!\n\tfn main() { println!(\"Hi\"); }  \r\n日本語 ";
+        let config = GameConfig {
+            game_type: GameType::Custom,
+            custom_text: Some(passage.to_string()),
+            ..words_config(0, false)
+        };
+        let words = get_words_for_game(&config, &[], &[]).unwrap();
+        let expected = ["Ünïcode", "—", "naïve", "café!", "fn", "main()", "{", "println!(\"Hi\");", "}", "日本語"];
+        assert_eq!(words, expected);
+
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        state.handle_paste(&expected.join(" "));
+        assert!(state.is_text_complete());
+        assert_eq!(state.typed_chars_total, state.correct_chars_total);
+
+        let blank = GameConfig { game_type: GameType::Custom, custom_text: Some(" \n".to_string()), ..words_config(0, false) };
+        assert!(get_words_for_game(&blank, &[], &[]).is_err());
+    }
+
+    /// Builds a Code-mode config for `text`.
+    fn code_config(text: &str) -> GameConfig {
+        GameConfig { game_type: GameType::Code, custom_text: Some(text.to_string()), ..words_config(0, false) }
+    }
+
+    #[test]
+    fn code_text_keeps_its_lines_and_indentation() {
+        let text = "\n\nfn main() {\r\n\tlet x = 1;   \n\n    println!(\"{x}\");\n}\n\n";
+        let lines = get_words_for_game(&code_config(text), &[], &[]).unwrap();
+        assert_eq!(lines, ["fn main() {", "    let x = 1;", "", "    println!(\"{x}\");", "}"]);
+        assert!(get_words_for_game(&code_config("\n \t\n"), &[], &[]).is_err());
+    }
+
+    #[test]
+    fn code_mode_expects_enter_at_line_ends() {
+        let lines = vec!["if x {".to_string(), "  y".to_string()];
+        let mut state = GameState::new(code_config("if x {\n  y"), Vec::new(), Vec::new(), lines);
+        // Spaces inside a line are ordinary characters.
+        "if x {".chars().for_each(|c| apply_key(&mut state, press(KeyCode::Char(c))));
+        assert_eq!(progress(&state), (0, 6, 6, 6));
+        // A space cannot end the line...
+        apply_key(&mut state, press(KeyCode::Char(' ')));
+        assert_eq!((state.current_word_index, state.errors.as_str()), (0, " "));
+        apply_key(&mut state, press(KeyCode::Backspace));
+        // ...only Enter can, and it counts as one correct keystroke.
+        apply_key(&mut state, press(KeyCode::Enter));
+        assert_eq!(progress(&state), (1, 0, 8, 7));
+        // Enter before the end of a line is a mistake, recorded against the expected character.
+        apply_key(&mut state, press(KeyCode::Enter));
+        assert_eq!((state.current_word_index, state.errors.as_str()), (1, "\n"));
+        assert_eq!(state.char_stats[&' '], (3, 1));
+        apply_key(&mut state, press(KeyCode::Backspace));
+        // Backspace at the start of a line returns to the end of the previous one.
+        apply_key(&mut state, press(KeyCode::Backspace));
+        assert_eq!((state.current_word_index, state.current_char_index), (0, 6));
+        state.handle_paste("\r\n  y");
+        assert!(state.is_text_complete());
+        // The space typed in place of the first Enter counts against it.
+        assert_eq!(state.char_stats[&'\n'], (3, 1));
+    }
+
+    #[test]
+    fn enter_is_ignored_outside_code_mode() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        "ab".chars().for_each(|c| apply_key(&mut state, press(KeyCode::Char(c))));
+        apply_key(&mut state, press(KeyCode::Enter));
+        assert_eq!(progress(&state), (0, 2, 2, 2));
+        assert_eq!(state.word_separator(), ' ');
+    }
+
+    #[test]
+    fn quote_mode_picks_from_the_requested_length() {
+        let quotes = vec![quote_of(20), quote_of(21), quote_of(60), quote_of(61)];
+        for (length, expected) in [
+            (QuoteLength::Short, vec![20]),
+            (QuoteLength::Medium, vec![21, 60]),
+            (QuoteLength::Long, vec![61]),
+        ] {
+            let config = GameConfig { game_type: GameType::Quote, quote_length: length.clone(), ..words_config(0, false) };
+            for seed in 0..20 {
+                let chosen = get_words_for_game_with_rng(&config, &[], &quotes, &mut StdRng::seed_from_u64(seed)).unwrap();
+                assert!(expected.contains(&chosen.len()), "{length:?} picked a {}-word quote", chosen.len());
+            }
+        }
+    }
+
+    #[test]
+    fn quote_mode_falls_back_to_all_quotes_when_no_length_matches() {
+        let quotes = vec![quote_of(5)];
+        let config = GameConfig { game_type: GameType::Quote, quote_length: QuoteLength::Long, ..words_config(0, false) };
+        let chosen = get_words_for_game_with_rng(&config, &[], &quotes, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(chosen.len(), 5);
+    }
+
+    #[test]
+    fn a_chosen_quote_is_found_by_id_or_index() {
+        let quotes = vec![quote_of(3), Quote { id: Some(7), ..quote_of(5) }, quote_of(4)];
+        let config = GameConfig { game_type: GameType::Quote, quote_number: Some(7), ..words_config(0, false) };
+        for seed in 0..5 {
+            let chosen = get_words_for_game_with_rng(&config, &[], &quotes, &mut StdRng::seed_from_u64(seed)).unwrap();
+            assert_eq!(chosen, quote_words(&quotes[1]));
+        }
+        assert_eq!(select_quote(&quotes, 2).unwrap().source, "4 words");
+        let error = select_quote(&quotes, 3).unwrap_err().to_string();
+        assert_eq!(error, "No quote 3: pick an index from 0 to 2, or an id from 7 to 7.");
+        assert_eq!(select_quote(&quotes[..1], 1).unwrap_err().to_string(), "No quote 1: pick an index from 0 to 0.");
+    }
+
+    #[test]
+    fn quote_mode_skips_blacklisted_quotes_and_favors_favorites() {
+        let quotes = vec![quote_of(5), quote_of(6), quote_of(7)];
+        let config = GameConfig { game_type: GameType::Quote, ..words_config(0, false) };
+        let mut marks = QuoteMarks::default();
+        marks.toggle_blacklisted(&quotes[0]);
+        marks.toggle_favorite(&quotes[1]);
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut picks = HashMap::new();
+        for _ in 0..500 {
+            let chosen = choose_quote_with_rng(&config, &quotes, &marks, &mut rng).unwrap();
+            *picks.entry(chosen.source.as_str()).or_insert(0) += 1;
+        }
+        assert!(!picks.contains_key("5 words"));
+        // Favorites are picked FAVORITE_WEIGHT (4) times as often, so about 400 of 500 times.
+        assert!((350..450).contains(&picks["6 words"]), "favorite picked {} times", picks["6 words"]);
+
+        // When every quote is blacklisted, any of them may still be picked.
+        marks.toggle_blacklisted(&quotes[1]);
+        marks.toggle_blacklisted(&quotes[2]);
+        let chosen = choose_quote_with_rng(&config, &quotes, &marks, &mut rng).unwrap();
+        assert!(marks.is_blacklisted(chosen));
+    }
+
+    #[test]
+    fn only_a_key_press_leaves_the_start_prompt() {
+        use crossterm::event::{KeyEvent, KeyEventKind, KeyEventState};
+        let key = |kind| Event::Key(KeyEvent { code: KeyCode::Char('a'), modifiers: KeyModifiers::NONE, kind, state: KeyEventState::NONE });
+        assert_eq!(prompt_event(&key(KeyEventKind::Press)), PromptEvent::Start);
+        assert_eq!(prompt_event(&key(KeyEventKind::Release)), PromptEvent::Ignore);
+        assert_eq!(prompt_event(&key(KeyEventKind::Repeat)), PromptEvent::Ignore);
+        assert_eq!(prompt_event(&Event::Resize(80, 24)), PromptEvent::Resize(80, 24));
+        assert_eq!(prompt_event(&Event::FocusLost), PromptEvent::Ignore);
+        let ctrl_c = Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert_eq!(prompt_event(&ctrl_c), PromptEvent::Quit);
+    }
+
+    #[test]
+    fn only_ctrl_c_is_the_quit_key() {
+        let key = |code, modifiers| is_quit_key(&KeyEvent::new(code, modifiers));
+        assert!(key(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        // With Caps Lock or Shift held, some terminals report an uppercase letter.
+        assert!(key(KeyCode::Char('C'), KeyModifiers::CONTROL | KeyModifiers::SHIFT));
+        assert!(!key(KeyCode::Char('c'), KeyModifiers::NONE));
+        assert!(!key(KeyCode::Char('c'), KeyModifiers::ALT));
+        assert!(!key(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        assert!(!key(KeyCode::Esc, KeyModifiers::CONTROL));
+    }
+
+    #[test]
+    fn countdown_starts_the_timer_only_once_it_elapses() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["word".to_string()]);
+        let started = Instant::now();
+        assert_eq!(state.tick_countdown_at(started, started), Some(3));
+        assert_eq!(state.tick_countdown_at(started, started + Duration::from_millis(1500)), Some(2));
+        assert_eq!(state.tick_countdown_at(started, started + Duration::from_millis(2999)), Some(1));
+        assert!(!state.timer.has_started());
+
+        let end = started + Duration::from_secs(COUNTDOWN_SECONDS);
+        assert_eq!(state.tick_countdown_at(started, end + Duration::from_millis(200)), None);
+        assert!(state.timer.is_running());
+        assert_close(state.timer.elapsed_secs_at(end + Duration::from_secs(1)), 1.0);
+    }
+
+    #[test]
+    fn words_mode_completes_on_the_last_character_of_the_last_word() {
+        let words: Vec<String> = ["one", "two", "three", "four"].iter().map(|w| w.to_string()).collect();
+        let mut state = GameState::new(words_config(3, false), Vec::new(), Vec::new(), words);
+        "one two thre".chars().for_each(|c| state.handle_char(c));
+        assert!(!state.is_text_complete());
+        state.handle_char('x');
+        assert!(!state.is_text_complete(), "a pending error on the last word is not completion");
+        state.handle_backspace();
+        state.handle_char('e');
+        assert!(state.is_text_complete());
+        assert_eq!(state.correct_chars_total, "one two three".chars().count());
+    }
+
+    #[test]
+    fn words_mode_trailing_space_is_optional() {
+        let words: Vec<String> = ["one", "two", "three"].iter().map(|w| w.to_string()).collect();
+        let mut state = GameState::new(words_config(3, false), Vec::new(), Vec::new(), words);
+        "one two three ".chars().for_each(|c| state.handle_char(c));
+        assert!(state.is_text_complete());
+        assert_eq!(state.current_word_index, 3);
+    }
+
+    #[test]
+    fn words_mode_does_not_complete_early() {
+        let words: Vec<String> = ["a", "bb", "a"].iter().map(|w| w.to_string()).collect();
+        let mut state = GameState::new(words_config(3, false), Vec::new(), Vec::new(), words);
+        // Typing the first word (identical to the last) must not look like the end.
+        state.handle_char('a');
+        assert!(!state.is_text_complete());
+        "a bb ".chars().skip(1).for_each(|c| state.handle_char(c));
+        assert!(!state.is_text_complete());
+        state.handle_char('a');
+        assert!(state.is_text_complete());
+    }
+
+    #[test]
+    fn game_result_serializes_to_the_documented_json() {
+        let result = GameResult {
+            mode: GameType::Words,
+            difficulty: Difficulty::Easy,
+            gross_wpm: 60.0,
+            net_wpm: 54.5,
+            accuracy: 97.25,
+            duration: 12.5,
+            correct_chars: 62,
+            typed_chars: 64,
+            words_completed: 12,
+            word_seconds: vec![1.5, 0.75],
+            completed: true,
+            finished_with_enter: false,
+            failed: false,
+            session: None,
+            exit_requested: true,
+            interrupted: true,
+        };
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"mode":"Words","difficulty":"Easy","gross_wpm":60.0,"net_wpm":54.5,"accuracy":97.25,"duration":12.5,"correct_chars":62,"typed_chars":64,"words_completed":12,"word_seconds":[1.5,0.75],"completed":true,"finished_with_enter":false,"failed":false}"#
+        );
+    }
+
+    #[test]
+    fn finished_state_converts_to_a_game_result() {
+        let words: Vec<String> = ["one", "two"].iter().map(|w| w.to_string()).collect();
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words);
+        "one twx".chars().for_each(|c| state.handle_char(c));
+        state.handle_backspace();
+        state.handle_char('o');
+        state.game_over = true;
+        state.final_elapsed_time_seconds = Some(6.0);
+
+        let result = GameResult::from_state(&state);
+        assert_eq!(result.mode, GameType::Words);
+        assert_eq!(result.difficulty, Difficulty::Hard);
+        assert_eq!((result.correct_chars, result.typed_chars), (7, 8));
+        assert_close(result.duration, 6.0);
+        let stats = calculate_wpm(7, 8, 6.0, &WpmScheme::Net, DEFAULT_ERROR_PENALTY);
+        assert_close(result.gross_wpm, stats.gross_wpm);
+        assert_close(result.net_wpm, stats.net_wpm);
+        assert_close(result.accuracy, stats.accuracy);
+        assert!(result.completed);
+
+        state.quit_early = true;
+        assert!(!GameResult::from_state(&state).completed);
+
+        state.quit_early = false;
+        state.failure = Some(RunFailure::ConsecutiveErrors);
+        let failed = GameResult::from_state(&state);
+        assert!(failed.failed && !failed.completed);
+    }
+
+    static GUARD_RESTORES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    fn count_restore() -> Result<()> {
+        GUARD_RESTORES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(())
+    }
+
+    #[test]
+    fn terminal_guard_restores_exactly_once() {
+        use std::sync::atomic::Ordering;
+        let restores = || GUARD_RESTORES.load(Ordering::SeqCst);
+        let before = restores();
+
+        // Dropped while unwinding from a panic.
+        let outcome = std::panic::catch_unwind(|| {
+            let _guard = TerminalGuard::with_restore(count_restore);
+            panic!("game crashed");
+        });
+        assert!(outcome.is_err());
+        assert_eq!(restores(), before + 1);
+
+        // Dropped early through an error return.
+        let fails = || -> Result<()> {
+            let _guard = TerminalGuard::with_restore(count_restore);
+            Err(anyhow!("failed mid-game"))
+        };
+        assert!(fails().is_err());
+        assert_eq!(restores(), before + 2);
+
+        // Restored explicitly: dropping afterwards must not restore again.
+        TerminalGuard::with_restore(count_restore).restore().unwrap();
+        assert_eq!(restores(), before + 3);
+    }
+
+    /// Turns `text` into the key events a terminal would send for it.
+    fn key_events(text: &str) -> Vec<Event> {
+        text.chars().map(|c| Event::Key(press(KeyCode::Char(c)))).collect()
+    }
+
+    #[test]
+    fn accuracy_counts_each_keystroke_once() {
+        let words = vec!["ab".to_string(), "cd".to_string()];
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words.clone());
+        "ab cd".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.typed_chars_total, state.correct_chars_total), (5, 5));
+        assert_close(calculate_wpm(state.correct_chars_total, state.typed_chars_total, 60.0, &WpmScheme::Net, DEFAULT_ERROR_PENALTY).accuracy, 100.0);
+
+        // a, x (wrong), Backspace, b, space, c, d: six keystrokes, five of them correct.
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words.clone());
+        "ax".chars().for_each(|c| state.handle_char(c));
+        state.handle_backspace();
+        "b cd".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.typed_chars_total, state.correct_chars_total), (6, 5));
+        assert_close(calculate_wpm(state.correct_chars_total, state.typed_chars_total, 60.0, &WpmScheme::Net, DEFAULT_ERROR_PENALTY).accuracy, 500.0 / 6.0);
+
+        // A space typed mid-word, or over pending errors, is a mistake rather than a completing space.
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words);
+        "a ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.current_word_index, state.errors.as_str()), (0, " "));
+        state.handle_backspace();
+        "bx ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(state.current_word_index, 0);
+        assert_eq!((state.typed_chars_total, state.correct_chars_total), (5, 2));
+        assert_eq!(state.char_stats[&' '], (2, 2));
+    }
+
+    #[test]
+    fn keys_after_the_last_word_are_not_counted() {
+        let mut state = GameState::new(words_config(1, false), Vec::new(), Vec::new(), vec!["a".to_string()]);
+        "a z".chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.typed_chars_total, state.correct_chars_total), (2, 2));
+    }
+
+    /// A key press without modifiers.
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    /// Returns `(word, char, typed, correct)`: the indices and character totals of `state`.
+    fn progress(state: &GameState) -> (usize, usize, usize, usize) {
+        (state.current_word_index, state.current_char_index, state.typed_chars_total, state.correct_chars_total)
+    }
+
+    #[test]
+    fn apply_key_advances_on_a_correct_char() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        apply_key(&mut state, press(KeyCode::Char('a')));
+        assert_eq!(progress(&state), (0, 1, 1, 1));
+        assert_eq!((state.user_input.as_str(), state.errors.as_str()), ("a", ""));
+    }
+
+    #[test]
+    fn apply_key_records_a_wrong_char_without_advancing() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        apply_key(&mut state, press(KeyCode::Char('x')));
+        assert_eq!(progress(&state), (0, 0, 1, 0));
+        assert_eq!((state.user_input.as_str(), state.errors.as_str()), ("", "x"));
+        // Once there is an error, even the expected character is another error.
+        apply_key(&mut state, press(KeyCode::Char('a')));
+        assert_eq!(progress(&state), (0, 0, 2, 0));
+        assert_eq!(state.errors, "xa");
+        assert_eq!(state.wrong_keystrokes, 2);
+    }
+
+    #[test]
+    fn apply_key_backspace_clears_errors_first_and_keeps_them_counted() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        for code in [KeyCode::Char('a'), KeyCode::Char('x'), KeyCode::Backspace] {
+            apply_key(&mut state, press(code));
+        }
+        assert_eq!(progress(&state), (0, 1, 2, 1));
+        assert_eq!((state.user_input.as_str(), state.errors.as_str()), ("a", ""));
+        apply_key(&mut state, press(KeyCode::Char('b')));
+        assert_eq!(progress(&state), (0, 2, 3, 2));
+    }
+
+    #[test]
+    fn apply_key_backspace_over_input_takes_back_the_char() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        for code in [KeyCode::Char('a'), KeyCode::Char('b'), KeyCode::Backspace] {
+            apply_key(&mut state, press(code));
+        }
+        assert_eq!(progress(&state), (0, 1, 1, 1));
+        assert_eq!(state.user_input, "a");
+        apply_key(&mut state, press(KeyCode::Backspace));
+        assert_eq!(progress(&state), (0, 0, 0, 0));
+        // Nothing left to delete: backspace is a no-op.
+        apply_key(&mut state, press(KeyCode::Backspace));
+        assert_eq!(progress(&state), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn apply_key_space_advances_to_the_next_word() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        "ab ".chars().for_each(|c| apply_key(&mut state, press(KeyCode::Char(c))));
+        assert_eq!(progress(&state), (1, 0, 3, 3));
+        assert_eq!(state.typed_words, vec!["ab".to_string()]);
+        assert!(state.user_input.is_empty());
+        // Keys that are not typing keys leave the state alone.
+        apply_key(&mut state, press(KeyCode::Left));
+        assert_eq!(progress(&state), (1, 0, 3, 3));
+    }
+
+    #[test]
+    fn buffered_events_are_drained_and_applied_together() {
+        let mut buffered: std::collections::VecDeque<Event> = key_events("hello wp").into();
+        buffered.push_back(Event::Key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE)));
+        buffered.extend(key_events("orld"));
+        let first = buffered.pop_front().unwrap();
+        let events = drain_events(first, || Ok(buffered.pop_front())).unwrap();
+        assert_eq!(events.len(), 13);
+        assert!(buffered.is_empty());
+
+        let words = vec!["hello".to_string(), "world".to_string()];
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words);
+        for event in events {
+            if let Event::Key(key_event) = event {
+                apply_key(&mut state, key_event);
+            }
+        }
+        assert_eq!(state.current_word_index, 1);
+        assert_eq!(state.user_input, "world");
+        assert!(state.errors.is_empty());
+        assert!(state.is_text_complete());
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (11, 12));
+    }
+
+    #[test]
+    fn paste_types_each_character_and_stops_at_the_end() {
+        let words = vec!["hello".to_string(), "world".to_string()];
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words);
+        state.handle_paste("hello\nworld and more");
+        assert!(state.is_text_complete());
+        assert_eq!(state.typed_chars_total, "hello world".chars().count());
+        assert_eq!(state.correct_chars_total, "hello world".chars().count());
+    }
+
+    #[test]
+    fn redraws_only_when_dirty_or_on_a_new_second() {
+        let mut redraw = RedrawTracker::new();
+        assert!(redraw.take_redraw(0.0));
+        assert!(!redraw.take_redraw(0.3));
+        assert!(!redraw.take_redraw(0.9));
+        redraw.mark_dirty();
+        assert!(redraw.take_redraw(0.95));
+        assert!(!redraw.take_redraw(0.99));
+        assert!(redraw.take_redraw(1.0), "the timer display changes every second");
+        assert!(!redraw.take_redraw(1.5));
+    }
+
+    #[test]
+    fn accuracy_floor_applies_only_after_enough_characters() {
+        let words = vec!["a".repeat(40)];
+        let config = GameConfig { min_accuracy: Some(90.0), ..words_config(1, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        state.handle_char('x');
+        assert!(!state.accuracy_below_floor(), "one early mistake must not end the run");
+        state.handle_backspace();
+        "a".repeat(18).chars().for_each(|c| state.handle_char(c));
+        // 18 of 19 correct (94.7%) is above the floor...
+        assert!(!state.accuracy_below_floor());
+        state.handle_char('x');
+        // ...but 18 of 20 (90%) is exactly on it, and 18 of 21 below it.
+        assert!(!state.accuracy_below_floor());
+        assert!(!state.game_over);
+        state.handle_char('x');
+        assert!(state.accuracy_below_floor());
+        assert!(state.game_over);
+        assert_eq!(state.failure, Some(RunFailure::AccuracyFloor));
+        assert!(state.final_elapsed_time_seconds.is_some());
+        assert_eq!(goal_description(&state.config), "90% accuracy");
+    }
+
+    #[test]
+    fn run_fails_on_exactly_the_maximum_mistakes_in_a_row() {
+        let config = GameConfig { max_consecutive_errors: Some(3), ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["abc".to_string(), "def".to_string()]);
+        "xx".chars().for_each(|c| state.handle_char(c));
+        // Backspace fixes the word but does not forgive the streak; a correct key does.
+        state.handle_backspace();
+        state.handle_backspace();
+        state.handle_char('a');
+        assert_eq!(state.consecutive_errors, 0);
+        "xx".chars().for_each(|c| state.handle_char(c));
+        assert!(!state.game_over, "two in a row is below the limit");
+        assert_eq!(state.failure, None);
+        state.handle_char('x');
+        assert!(state.game_over);
+        assert_eq!(state.failure, Some(RunFailure::ConsecutiveErrors));
+        let final_seconds = state.final_elapsed_time_seconds;
+        assert!(final_seconds.is_some());
+
+        // Nothing typed after the failure changes the finished run.
+        state.handle_paste("bc def");
+        assert_eq!(state.typed_chars_total, 6);
+        assert_eq!(state.final_elapsed_time_seconds, final_seconds);
+        assert!(!GameResult::from_state(&state).completed);
+    }
+
+    #[test]
+    fn seeded_selection_is_reproducible() {
+        let all_words: Vec<String> = ["apple", "banana", "cherry", "date", "elderberry", "fig", "grape"]
+            .iter().map(|w| w.to_string()).collect();
+        let config = GameConfig { difficulty: Difficulty::Hard, ..words_config(3, false) };
+        let chosen = get_words_for_game_with_rng(&config, &all_words, &[], &mut StdRng::seed_from_u64(42)).unwrap();
+        assert_eq!(chosen, SEEDED_SELECTION);
+    }
+
+    #[test]
+    fn sessions_with_the_same_seed_get_the_same_tests() {
+        let all_words: Vec<String> = (0..200).map(|i| format!("word{i}")).collect();
+        let config = GameConfig {
+            game_type: GameType::Time,
+            time_seconds: Some(30),
+            word_count: None,
+            numbers: true,
+            seed: Some(2024),
+            ..words_config(0, true)
+        };
+        let play = |config: &GameConfig| {
+            let mut state = start_session(config.clone(), all_words.clone(), Vec::new(), QuoteMarks::default()).unwrap();
+            let first_test = state.words_to_type.clone();
+            // Typing towards the end of the list refills it; R starts the next test.
+            state.current_word_index = state.words_to_type.len();
+            state.refill_words_if_needed().unwrap();
+            let refilled = state.words_to_type.clone();
+            restart_with_new_text(&mut state).unwrap();
+            (first_test, refilled, state.words_to_type)
+        };
+        let (first_test, refilled, second_test) = play(&config);
+        assert!(refilled.len() > first_test.len());
+        assert_ne!(first_test, second_test, "the next test is new, not a repeat");
+        assert_eq!(play(&config), (first_test.clone(), refilled, second_test));
+        let (other_seed, _, _) = play(&GameConfig { seed: Some(2025), ..config });
+        assert_ne!(other_seed, first_test);
+    }
+
+    fn no_adjacent_repeats(previous: Option<&str>, words: &[String]) -> bool {
+        previous.into_iter().chain(words.iter().map(String::as_str)).collect::<Vec<_>>().windows(2).all(|pair| pair[0] != pair[1])
+    }
+
+    #[test]
+    fn repeats_are_separated_where_possible() {
+        let words = |list: &[&str]| list.iter().map(|w| w.to_string()).collect::<Vec<String>>();
+        let mut sorted_input = words(&["go", "go", "go", "up", "up", "at"]);
+        separate_repeats(&mut sorted_input, None);
+        assert!(no_adjacent_repeats(None, &sorted_input), "{sorted_input:?}");
+        // The repeat at the end can only go back to the front.
+        let mut tail = words(&["at", "go", "up", "up"]);
+        separate_repeats(&mut tail, None);
+        assert_eq!(tail, words(&["up", "go", "up", "at"]));
+        let mut batch = words(&["up", "go"]);
+        separate_repeats(&mut batch, Some("up"));
+        assert_eq!(batch, words(&["go", "up"]), "the word before the batch counts");
+        let mut stuck = words(&["go", "go"]);
+        separate_repeats(&mut stuck, None);
+        assert_eq!(stuck, words(&["go", "go"]));
+    }
+
+    #[test]
+    fn selected_words_never_repeat_back_to_back() {
+        // With two of each word, a plain shuffle would put some next to each other.
+        let all_words: Vec<String> = ["go", "go", "up", "up", "at", "at"].map(str::to_string).to_vec();
+        for seed in 0..50 {
+            let config = GameConfig { difficulty: Difficulty::Hard, ..words_config(6, false) };
+            let chosen = get_words_for_game_with_rng(&config, &all_words, &[], &mut StdRng::seed_from_u64(seed)).unwrap();
+            assert!(no_adjacent_repeats(None, &chosen), "seed {seed}: {chosen:?}");
+        }
+        let config = GameConfig { game_type: GameType::Time, time_seconds: Some(15), ..words_config(0, false) };
+        let mut state = GameState::new(config, all_words.clone(), Vec::new(), vec!["at".to_string()]);
+        for _ in 0..20 {
+            state.current_word_index = state.words_to_type.len();
+            state.refill_words_if_needed().unwrap();
+        }
+        assert!(no_adjacent_repeats(None, &state.words_to_type), "no repeat where one batch meets the next");
+    }
+
+    #[test]
+    fn easy_difficulty_only_returns_short_words() {
+        let all_words: Vec<String> = ["cat", "house", "banana", "keyboard", "dog", "elephant", "sun"]
+            .iter().map(|w| w.to_string()).collect();
+        let config = GameConfig { difficulty: Difficulty::Easy, ..words_config(10, false) };
+        let chosen = get_words_for_game_with_rng(&config, &all_words, &[], &mut StdRng::seed_from_u64(1)).unwrap();
+        let mut sorted = chosen.clone();
+        sorted.sort();
+        assert_eq!(sorted, ["cat", "dog", "house", "sun"]);
+        assert!(chosen.iter().all(|w| w.chars().count() <= 5));
+    }
+
+    #[test]
+    fn difficulty_buckets_count_characters_not_bytes() {
+        // "naïve" is 6 bytes but 5 chars, so it belongs in Easy.
+        let all_words: Vec<String> = ["naïve", "café", "résumé", "piñatas", "fiancée", "déjàvuüs", "smörgåsbord", "cat"]
+            .iter().map(|w| w.to_string()).collect();
+        let select = |difficulty: Difficulty| {
+            let config = GameConfig { difficulty, ..words_config(20, false) };
+            let mut chosen = get_words_for_game_with_rng(&config, &all_words, &[], &mut StdRng::seed_from_u64(3)).unwrap();
+            chosen.sort();
+            chosen
+        };
+        assert_eq!(select(Difficulty::Easy), ["café", "cat", "naïve"]);
+        assert_eq!(select(Difficulty::Medium), ["café", "cat", "déjàvuüs", "fiancée", "naïve", "piñatas", "résumé"]);
+        assert_eq!(select(Difficulty::Hard).len(), all_words.len());
+    }
+
+    #[test]
+    fn word_length_bounds_filter_the_words() {
+        let all_words: Vec<String> = ["a", "cat", "naïve", "banana", "keyboard", "smörgåsbord"]
+            .iter().map(|w| w.to_string()).collect();
+        let select = |difficulty, min_word_length, max_word_length| {
+            let config = GameConfig { difficulty, min_word_length, max_word_length, ..words_config(20, false) };
+            let mut chosen = get_words_for_game_with_rng(&config, &all_words, &[], &mut StdRng::seed_from_u64(5)).unwrap();
+            chosen.sort();
+            chosen
+        };
+        assert_eq!(select(Difficulty::Hard, Some(3), Some(6)), ["banana", "cat", "naïve"]);
+        // Explicit bounds win: a long minimum lifts Easy's limit, and a maximum replaces it.
+        assert_eq!(select(Difficulty::Easy, Some(6), None), ["banana", "keyboard", "smörgåsbord"]);
+        assert_eq!(select(Difficulty::Easy, None, Some(8)), ["a", "banana", "cat", "keyboard", "naïve"]);
+        // No word is long enough, so every word is used rather than none.
+        assert_eq!(select(Difficulty::Hard, Some(20), None).len(), all_words.len());
+    }
+
+    #[test]
+    fn zen_refill_appends_without_disturbing_progress() {
+        let all_words = vec!["zen".to_string(); 10];
+        let config = GameConfig { game_type: GameType::Zen, word_count: None, ..words_config(0, false) };
+        let words = get_words_for_game(&config, &all_words, &[]).unwrap();
+        let mut state = GameState::new(config, all_words, Vec::new(), words);
+        let initial_len = state.words_to_type.len();
+
+        state.refill_words_if_needed().unwrap();
+        "zen ".chars().for_each(|c| state.handle_char(c));
+        "z".chars().for_each(|c| state.handle_char(c));
+        assert!(state.words_to_type.len() > initial_len);
+        assert_eq!(state.current_word_index, 1);
+        assert_eq!(state.current_char_index, 1);
+        assert_eq!((state.correct_chars_total, state.typed_chars_total), (5, 5));
+
+        let len_after_refill = state.words_to_type.len();
+        state.refill_words_if_needed().unwrap();
+        assert_eq!(state.words_to_type.len(), len_after_refill + initial_len);
+        assert_eq!(state.current_word_index, 1);
+        assert_eq!(state.user_input, "z");
+    }
+
+    #[test]
+    fn time_mode_buffer_scales_with_duration() {
+        assert_eq!(time_mode_word_count(Some(15)), 63);
+        assert_eq!(time_mode_word_count(Some(120)), 500);
+        assert_eq!(time_mode_word_count(Some(1)), MIN_TIME_MODE_WORDS);
+        assert_eq!(time_mode_word_count(None), MIN_TIME_MODE_WORDS);
+    }
+
+    #[test]
+    fn fast_typist_never_exhausts_a_long_time_run() {
+        let all_words: Vec<String> = (0..1000).map(|i| format!("w{}", i)).collect();
+        let config = GameConfig { game_type: GameType::Time, time_seconds: Some(120), word_count: None, ..words_config(0, false) };
+        let words = get_words_for_game(&config, &all_words, &[]).unwrap();
+        assert_eq!(words.len(), 500);
+        let mut state = GameState::new(config, all_words, Vec::new(), words);
+        // Twice the planned maximum speed: 1000 words in the 120 seconds.
+        for _ in 0..1000 {
+            state.refill_words_if_needed().unwrap();
+            let word = state.words_to_type[state.current_word_index].clone();
+            format!("{} ", word).chars().for_each(|c| state.handle_char(c));
+            assert!(state.current_word_index < state.words_to_type.len());
+        }
+        assert_eq!(state.current_word_index, 1000);
+        assert_eq!(state.correct_chars_total, state.typed_chars_total);
+    }
+
+    #[test]
+    fn accented_language_words_survive_filtering_and_typing() {
+        let german = crate::data_loader::load_words_for_language_in("german", None, None).unwrap().words;
+        let config = GameConfig { difficulty: Difficulty::Easy, language: "german".to_string(), ..words_config(500, false) };
+        let easy = get_words_for_game_with_rng(&config, &german, &[], &mut StdRng::seed_from_u64(9)).unwrap();
+        // "größe" and "füße" are longer than 5 bytes but only 5 and 4 characters.
+        assert!(easy.contains(&"größe".to_string()));
+        assert!(easy.contains(&"füße".to_string()));
+
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["süß".to_string()]);
+        "süß ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(state.current_word_index, 1);
+        assert!(state.errors.is_empty());
+    }
+
+    #[test]
+    fn key_row_drills_only_use_the_drilled_letters() {
+        let english = crate::data_loader::load_words_for_language_in("english", None, None).unwrap().words;
+        for rows in <KeyRows as clap::ValueEnum>::value_variants() {
+            let config = GameConfig { key_rows: Some(rows.clone()), difficulty: Difficulty::Medium, ..words_config(100, false) };
+            let words = get_words_for_game_with_rng(&config, &english, &[], &mut StdRng::seed_from_u64(4)).unwrap();
+            assert_eq!(words.len(), 100);
+            for word in &words {
+                assert!(word.chars().all(|c| rows.letters().contains(c)), "{word:?} is not on the {rows:?} keys");
+                assert!(word.chars().count() <= 8, "{word:?} is too long for Medium");
+            }
+        }
+        // The home row spells plenty of real words; the bottom row needs made-up ones.
+        let home = drill_words(&KeyRows::Home, &english, 10, None, &mut StdRng::seed_from_u64(1));
+        assert!(home.iter().all(|word| english.contains(word)));
+        let bottom = drill_words(&KeyRows::Bottom, &[], 30, Some(5), &mut StdRng::seed_from_u64(1));
+        assert_eq!(bottom.len(), 30);
+        assert!(bottom.iter().all(|word| word.len() <= 5 && word.chars().all(|c| "zxcvbnm".contains(c))));
+    }
+
+    #[test]
+    fn weak_key_drills_favor_words_with_weak_keys() {
+        let english = crate::data_loader::load_words_for_language_in("english", None, None).unwrap().words;
+        let share_with_z = |weak_chars: Vec<char>| {
+            let config = GameConfig { weak_chars, ..words_config(2000, false) };
+            let words = get_words_for_game_with_rng(&config, &english, &[], &mut StdRng::seed_from_u64(8)).unwrap();
+            words.iter().filter(|word| word.contains('z')).count() as f64 / words.len() as f64
+        };
+        let baseline = share_with_z(Vec::new());
+        let drilled = share_with_z(vec!['z']);
+        assert!(drilled > 2.0 * baseline, "{drilled} of words had a z, against {baseline} without the drill");
+    }
+
+    #[test]
+    fn frequent_words_are_picked_more_often() {
+        // Ten common words, each fifty times as frequent as the ninety rare ones.
+        let all_words: Vec<String> = (0..100).map(|i| format!("w{i}")).collect();
+        let weights: Vec<f64> = (0..100).map(|i| if i < 10 { 50.0 } else { 1.0 }).collect();
+        let share_common = |word_weights: WordWeights| {
+            let config = GameConfig { word_weights, ..words_config(10, false) };
+            let mut rng = StdRng::seed_from_u64(11);
+            let picks: Vec<String> = (0..200)
+                .flat_map(|_| get_words_for_game_with_rng(&config, &all_words, &[], &mut rng).unwrap())
+                .collect();
+            assert_eq!(picks.len(), 2000);
+            picks.iter().filter(|word| all_words[..10].contains(word)).count() as f64 / picks.len() as f64
+        };
+        let uniform = share_common(WordWeights::default());
+        let weighted = share_common(WordWeights::new(&all_words, &weights));
+        assert!((0.07..0.13).contains(&uniform), "common words were {uniform} of uniform picks");
+        assert!(weighted > 0.6, "common words were only {weighted} of weighted picks");
+    }
+
+    #[test]
+    fn number_tokens_are_inserted_and_matchable() {
+        let words = vec!["lorem".to_string(); 100];
+        let mixed = insert_numbers(words.clone(), 0.5, &mut StdRng::seed_from_u64(7));
+        let numbers: Vec<&String> = mixed.iter().filter(|w| w.parse::<u32>().is_ok()).collect();
+        assert_eq!(mixed.len(), 100);
+        assert!(!numbers.is_empty());
+        assert!(numbers.iter().all(|n| (1..=MAX_NUMBER_DIGITS as usize).contains(&n.len())));
+        assert_eq!(insert_numbers(words.clone(), 0.0, &mut StdRng::seed_from_u64(7)), words);
+
+        // A number token is typed like any other word.
+        let token = numbers[0].clone();
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec![token.clone()]);
+        token.chars().for_each(|c| state.handle_char(c));
+        state.handle_char(' ');
+        assert_eq!(state.current_word_index, 1);
+        assert!(state.errors.is_empty());
+    }
+
+    #[test]
+    fn punctuation_only_appears_when_enabled() {
+        let all_words = vec!["lorem".to_string(); 300];
+        let is_punctuation = |c: char| ",.;!?\"()".contains(c);
+
+        let plain = get_words_for_game(&words_config(300, false), &all_words, &[]).unwrap();
+        assert!(plain.iter().all(|w| w == "lorem"));
+
+        let punctuated = get_words_for_game(&words_config(300, true), &all_words, &[]).unwrap();
+        assert_eq!(punctuated.len(), 300);
+        assert!(punctuated[0].trim_start_matches(['"', '(']).starts_with('L'));
+        assert!(punctuated.iter().any(|w| w.chars().any(is_punctuation)));
+        assert!(punctuated.iter().all(|w| w.to_lowercase().contains("lorem")));
+    }
+
+    #[test]
+    fn scripted_run_types_the_text_to_completion() {
+        use crate::events::ScriptedEvents;
+        let key = |c: char| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        let step = Duration::from_millis(200);
+        let mut script = vec![(Duration::ZERO, Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)))];
+        script.extend("ab cd".chars().map(|c| (step, key(c))));
+        script.push((Duration::from_millis(500), Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))));
+        let mut events = ScriptedEvents::new((80, 24), script);
+        let config = GameConfig {
+            game_type: GameType::Custom,
+            custom_text: Some("ab cd".to_string()),
+            ..words_config(0, false)
+        };
+        let mut screen = Vec::new();
+        let result = play_game(&mut screen, &mut events, None, config, Vec::new(), Vec::new()).unwrap();
+        assert!(result.completed && !result.exit_requested);
+        // Only the first word ends with a space; typing the last one's final letter ends the run.
+        assert_eq!((result.words_completed, result.correct_chars, result.typed_chars), (1, 5, 5));
+        // Five keys, 200ms apart on the fake clock, finish the text one second after the start key.
+        assert!((result.duration - 1.0).abs() < 1e-9, "{}", result.duration);
+        assert!((result.gross_wpm - 60.0).abs() < 1e-6, "{}", result.gross_wpm);
+        assert_eq!(result.accuracy, 100.0);
+        assert!(!screen.is_empty());
+    }
+
+    #[test]
+    fn scripted_run_records_every_key_with_what_was_expected() {
+        use crate::events::ScriptedEvents;
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let step = Duration::from_millis(250);
+        let codes = [KeyCode::Char('a'), KeyCode::Char('x'), KeyCode::Backspace, KeyCode::Char('b'),
+            KeyCode::Char(' '), KeyCode::Char('c'), KeyCode::Char('d')];
+        let mut script = vec![(Duration::ZERO, key(KeyCode::Enter))];
+        script.extend(codes.map(|code| (step, key(code))));
+        script.push((step, key(KeyCode::Esc)));
+        let path = std::env::temp_dir().join(format!("monk_minal_rust_test_{}_record.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = GameConfig {
+            game_type: GameType::Custom,
+            custom_text: Some("ab cd".to_string()),
+            record_path: Some(path.clone()),
+            ..words_config(0, false)
+        };
+        play_game(&mut Vec::new(), &mut ScriptedEvents::new((80, 24), script), None, config, Vec::new(), Vec::new()).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert!(lines[0].contains(r#""words":["ab","cd"]"#), "{}", lines[0]);
+        let logged: Vec<LoggedEdit> = lines[1..].iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let entry = |quarters: u32, edit, expected| LoggedEdit { at: quarters as f64 * 0.25, edit, expected: Some(expected) };
+        assert_eq!(logged, vec![
+            entry(1, Edit::Char('a'), 'a'),
+            entry(2, Edit::Char('x'), 'b'),
+            entry(3, Edit::Backspace, 'b'),
+            entry(4, Edit::Char('b'), 'b'),
+            entry(5, Edit::Char(' '), ' '),
+            entry(6, Edit::Char('c'), 'c'),
+            entry(7, Edit::Char('d'), 'd'),
+        ]);
+        assert_eq!(lines[1], r#"{"at":0.25,"edit":{"Char":"a"},"expected":"a"}"#);
+    }
+
+    #[test]
+    fn ema_weights_each_new_value_by_the_factor() {
+        let smooth = |raw: &[f64], factor| raw[1..].iter().fold(vec![raw[0]], |mut values, &value| {
+            values.push(ema(*values.last().unwrap(), value, factor));
+            values
+        });
+        assert_eq!(smooth(&[100.0, 0.0, 0.0, 50.0], 0.5), vec![100.0, 50.0, 25.0, 37.5]);
+        assert_eq!(smooth(&[100.0, 20.0, 60.0], 1.0), vec![100.0, 20.0, 60.0], "a factor of 1 does not smooth");
+        let calm = smooth(&[120.0, 40.0, 80.0, 60.0], 0.25);
+        assert_eq!(calm, vec![120.0, 100.0, 95.0, 86.25]);
+    }
+
+    #[test]
+    fn live_wpm_is_smoothed_but_the_results_are_not() {
+        let config = GameConfig { wpm_smoothing: 0.5, wpm_scheme: WpmScheme::Raw, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["abcde".to_string(), "fghij".to_string()]);
+        "abcde".chars().for_each(|c| state.handle_char(c));
+        // Five characters are one word: 60 WPM after a second, 30 after two.
+        state.update_live_wpm(1.0);
+        assert_eq!(state.live_wpm, Some((60.0, 60.0)));
+        state.update_live_wpm(2.0);
+        assert_eq!(state.live_wpm, Some((45.0, 45.0)));
+        state.final_elapsed_time_seconds = Some(2.0);
+        assert_eq!(GameResult::from_state(&state).gross_wpm, 30.0);
+    }
+
+    #[test]
+    fn backspace_hint_follows_blocking_errors() {
+        let base = Instant::now();
+        let at = |millis: u64| base + Duration::from_millis(millis);
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        state.timer.start_at(at(0));
+        state.note_input_at(at(200));
+        state.handle_char('a');
+        assert!(!state.backspace_hint_due(5.0), "no errors, no hint");
+        state.note_input_at(at(500));
+        state.handle_char('x');
+        state.note_input_at(at(900));
+        state.handle_char('y');
+        // The hint counts from the first wrong key, not the latest.
+        assert!(!state.backspace_hint_due(1.4));
+        assert!(state.backspace_hint_due(1.5));
+        state.handle_backspace();
+        assert!(state.backspace_hint_due(1.5), "one wrong key is still in the way");
+        state.handle_backspace();
+        assert!(!state.backspace_hint_due(1.5));
+        // A fresh mistake starts the wait over.
+        state.note_input_at(at(3000));
+        state.handle_char('z');
+        assert!(!state.backspace_hint_due(3.5));
+        assert!(state.backspace_hint_due(4.0));
+    }
+
+    #[test]
+    fn pending_errors_are_capped_but_still_counted() {
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        (0..MAX_PENDING_ERRORS + 5).for_each(|_| state.handle_char('x'));
+        assert_eq!(state.errors.chars().count(), MAX_PENDING_ERRORS);
+        assert_eq!((state.wrong_keystrokes, state.typed_chars_total), (MAX_PENDING_ERRORS + 5, MAX_PENDING_ERRORS + 5));
+        (0..MAX_PENDING_ERRORS).for_each(|_| state.handle_backspace());
+        assert!(state.errors.is_empty());
+        state.handle_char('a');
+        assert_eq!(state.user_input, "a");
+    }
+
+    #[test]
+    fn enter_finishes_a_words_run_where_it_stands() {
+        use crate::events::ScriptedEvents;
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let step = Duration::from_millis(250);
+        let mut script = vec![(Duration::ZERO, key(KeyCode::Enter))];
+        script.extend("ab a".chars().map(|c| (step, key(KeyCode::Char(c)))));
+        // Anything typed after Enter is not counted.
+        script.extend([(step, key(KeyCode::Enter)), (Duration::ZERO, key(KeyCode::Char('x')))]);
+        script.push((Duration::from_millis(500), key(KeyCode::Esc)));
+        let mut events = ScriptedEvents::new((80, 24), script);
+        let config = GameConfig { enter_finishes: true, ..words_config(5, false) };
+        let all_words = vec!["ab".to_string(); 5];
+        let result = play_game(&mut Vec::new(), &mut events, None, config, all_words, Vec::new()).unwrap();
+        assert!(result.completed && result.finished_with_enter && !result.exit_requested);
+        assert_eq!((result.words_completed, result.correct_chars, result.typed_chars), (1, 4, 4));
+        assert!((result.duration - 1.25).abs() < 1e-9, "{}", result.duration);
+    }
+
+    #[test]
+    fn enter_only_finishes_when_turned_on_and_typing_started() {
+        let words = vec!["ab".to_string(), "cd".to_string()];
+        let mut state = GameState::new(GameConfig { enter_finishes: true, ..words_config(2, false) }, Vec::new(), Vec::new(), words.clone());
+        apply_key(&mut state, press(KeyCode::Enter));
+        assert!(!state.finished_with_enter, "nothing typed yet");
+        apply_key(&mut state, press(KeyCode::Char('a')));
+        apply_key(&mut state, press(KeyCode::Enter));
+        assert!(state.finished_with_enter);
+
+        let mut state = GameState::new(words_config(2, false), Vec::new(), Vec::new(), words);
+        apply_key(&mut state, press(KeyCode::Char('a')));
+        apply_key(&mut state, press(KeyCode::Enter));
+        assert!(!state.finished_with_enter);
+        assert_eq!(progress(&state), (0, 1, 1, 1));
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_combining_sequence() {
+        // "né" with the accent as a combining character: three keystrokes, two visible letters.
+        let word = "ne\u{301}";
+        let mut state = GameState::new(words_config(1, false), Vec::new(), Vec::new(), vec![word.to_string(), "x".to_string()]);
+        word.chars().for_each(|c| state.handle_char(c));
+        assert_eq!((state.current_char_index, state.correct_chars_total), (3, 3));
+        state.handle_backspace();
+        assert_eq!(state.user_input, "n");
+        assert_eq!((state.current_char_index, state.correct_chars_total, state.typed_chars_total), (1, 1, 1));
+
+        // Wrong keys are taken back the same way.
+        "o\u{308}".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(state.errors, "o\u{308}");
+        state.handle_backspace();
+        assert!(state.errors.is_empty());
+        assert_eq!(state.typed_chars_total, 3, "erased errors still count as typed");
+        assert_eq!(pop_grapheme(&mut String::new()), 0);
+    }
+
+    #[test]
+    fn cumulative_sessions_add_up_every_attempt() {
+        let base = Instant::now();
+        let at = |millis: u64| base + Duration::from_millis(millis);
+        let config = GameConfig { cumulative: true, ..words_config(2, false) };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        // An attempt restarted before anything was typed is not counted.
+        state.restart_same_text();
+        assert_eq!(state.session_totals, SessionTotals::default());
+
+        // The first attempt: one slip, corrected, then abandoned two seconds in.
+        state.timer.start_at(at(0));
+        "ax".chars().for_each(|c| state.handle_char(c));
+        state.handle_backspace();
+        "b ".chars().for_each(|c| state.handle_char(c));
+        state.note_input_at(at(2000));
+        state.restart_same_text();
+        assert_eq!(state.session_totals.attempts, 1);
+        assert_eq!(state.typed_chars_total, 0, "the attempt itself starts from a clean slate");
+
+        // The second attempt is typed cleanly in a second.
+        state.timer.start_at(at(3000));
+        "ab cd".chars().for_each(|c| state.handle_char(c));
+        state.end_game_at(at(4000));
+        state.add_attempt_to_session();
+        assert_eq!(state.session_totals, SessionTotals {
+            attempts: 2, correct_chars: 8, typed_chars: 9, wrong_keystrokes: 1, words_completed: 2, seconds: 3.0,
+        });
+        let session = state.session_totals.result(DEFAULT_ERROR_PENALTY);
+        let stats = calculate_wpm(8, 9, 3.0, &WpmScheme::Net, DEFAULT_ERROR_PENALTY);
+        assert_eq!((session.gross_wpm, session.net_wpm, session.accuracy), (stats.gross_wpm, stats.net_wpm, stats.accuracy));
+        assert_eq!(session.gross_wpm, 36.0);
+        assert_eq!(session.summary(), format!(
+            "Session of 2 attempts over 3.0s: {:.1} WPM (36.0 gross), 88.9% accuracy, 2 words typed.", stats.net_wpm));
+    }
+
+    #[test]
+    fn scripted_run_times_each_completed_word() {
+        use crate::events::ScriptedEvents;
+        let key = |c: char| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        let at = |millis| Duration::from_millis(millis);
+        let mut script = vec![(Duration::ZERO, Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)))];
+        // "ab " takes 0.9s, "cd " 0.4s after a slip that is backspaced, and "ef" ends the run.
+        script.extend([(at(300), key('a')), (at(300), key('b')), (at(300), key(' '))]);
+        script.extend([(at(100), key('x'))]);
+        script.push((at(100), Event::Key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE))));
+        script.extend([(at(100), key('c')), (at(50), key('d')), (at(50), key(' '))]);
+        script.extend([(at(200), key('e')), (at(200), key('f'))]);
+        script.push((at(500), Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))));
+        let mut events = ScriptedEvents::new((80, 24), script);
+        let config = GameConfig {
+            game_type: GameType::Custom,
+            custom_text: Some("ab cd ef".to_string()),
+            ..words_config(0, false)
+        };
+        let result = play_game(&mut Vec::new(), &mut events, None, config, Vec::new(), Vec::new()).unwrap();
+        assert!(result.completed);
+        assert_eq!(result.word_seconds.len(), 2, "the last word ends the run without a separator");
+        assert!((result.word_seconds[0] - 0.9).abs() < 1e-9, "{:?}", result.word_seconds);
+        assert!((result.word_seconds[1] - 0.4).abs() < 1e-9, "{:?}", result.word_seconds);
+    }
+
+    #[test]
+    fn backspacing_into_a_word_takes_back_its_time() {
+        let base = Instant::now();
+        let at = |millis: u64| base + Duration::from_millis(millis);
+        let mut state = GameState::new(words_config(3, false), Vec::new(), Vec::new(),
+            vec!["ab".to_string(), "cd".to_string(), "ef".to_string()]);
+        state.timer.start_at(at(0));
+        let type_at = |state: &mut GameState, c, millis| {
+            state.note_input_at(at(millis));
+            state.handle_char(c);
+        };
+        type_at(&mut state, 'a', 250);
+        type_at(&mut state, 'b', 500);
+        type_at(&mut state, ' ', 750);
+        type_at(&mut state, 'c', 1000);
+        type_at(&mut state, 'd', 1250);
+        type_at(&mut state, ' ', 1750);
+        assert_eq!(state.word_seconds, vec![0.75, 1.0]);
+        state.handle_backspace();
+        state.handle_backspace();
+        assert_eq!(state.word_seconds, vec![0.75]);
+        type_at(&mut state, 'd', 2000);
+        type_at(&mut state, ' ', 2250);
+        // The word took from the first word's completion until its retyped separator.
+        assert_eq!(state.word_seconds, vec![0.75, 1.5]);
+    }
+
+    #[test]
+    fn max_minutes_end_the_run_without_counting_pauses() {
+        use crate::events::ScriptedEvents;
+        let key = |code, modifiers| Event::Key(KeyEvent::new(code, modifiers));
+        let ctrl_p = key(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        let script = vec![
+            (Duration::ZERO, key(KeyCode::Enter, KeyModifiers::NONE)),
+            (Duration::from_secs(10), key(KeyCode::Char('a'), KeyModifiers::NONE)),
+            (Duration::from_secs(10), ctrl_p.clone()),
+            // Paused for a whole minute and a half, which must not count towards the cap.
+            (Duration::from_secs(90), ctrl_p),
+            (Duration::from_secs(120), key(KeyCode::Esc, KeyModifiers::NONE)),
+        ];
+        let mut events = ScriptedEvents::new((80, 24), script);
+        let config = GameConfig {
+            game_type: GameType::Custom,
+            custom_text: Some("ab cd".to_string()),
+            max_minutes: Some(1),
+            ..words_config(0, false)
+        };
+        let mut screen = Vec::new();
+        let result = play_game(&mut screen, &mut events, None, config, Vec::new(), Vec::new()).unwrap();
+        assert!(!result.exit_requested);
+        // 20s before the pause and 40s after it make the minute; the 100ms poll bounds the overshoot.
+        assert!((60.0..60.2).contains(&result.duration), "{}", result.duration);
+        assert_eq!(result.typed_chars, 1);
+
+        let state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["ab".to_string()]);
+        assert!(!state.reached_max_duration(1e6), "no cap by default");
+    }
 }
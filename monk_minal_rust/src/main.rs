@@ -4,18 +4,27 @@
 //! It provides various game modes to help users improve their typing speed and accuracy.
 //! This is the main entry point of the application.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use colored::*;
 use figlet_rs::FIGfont;
+use std::io::IsTerminal;
 // log crate for logging errors
-use log::{error, info, warn, debug, trace};
-
+use log::{error, warn};
 
 // Modules defining different parts of the application
-pub mod config; 
+pub mod config;
 pub mod data_loader;
+pub mod events;
 pub mod game;
+pub mod quote_marks;
+pub mod replay;
+pub mod results_csv;
+pub mod settings_screen;
+pub mod spinner;
+pub mod stats;
+pub mod theme;
+pub mod ui;
 
 /// Command Line Interface arguments for MonkMinal Rust.
 ///
@@ -23,36 +32,339 @@ pub mod game;
 #[derive(Parser, Debug)]
 #[clap(author = "shikhar13012001", version = "0.1.0", about = "A terminal-based typing tutor written in Rust.", long_about = None)]
 struct CliArgs {
-    // No explicit arguments are defined here for now, as clap handles --version and --help.
-    // Future arguments like specific game modes or configurations could be added.
+    /// Game mode to play. Skips the game type prompt.
+    #[clap(long, value_enum)]
+    mode: Option<config::GameType>,
+
+    /// Duration in seconds for Time mode. Implies `--mode time`.
+    #[clap(long, value_name = "SECONDS")]
+    time: Option<u32>,
+
+    /// Number of words for Words mode. Implies `--mode words`.
+    #[clap(long, value_name = "COUNT")]
+    words: Option<u32>,
+
+    /// Difficulty level. Skips the difficulty prompt.
+    #[clap(long, value_enum)]
+    difficulty: Option<config::Difficulty>,
+
+    /// Mix capitalized words and punctuation into Time, Words and Zen modes.
+    #[clap(long)]
+    punctuation: bool,
+
+    /// Mix numbers into Time, Words and Zen modes.
+    #[clap(long)]
+    numbers: bool,
+
+    /// Load words from this file instead of the built-in list.
+    /// Accepts `{ "words": [...] }` JSON or plain text with one word per line.
+    #[clap(long, value_name = "PATH")]
+    words_file: Option<std::path::PathBuf>,
+
+    /// Directory holding replacement data files: an `allWords.json` or `quotes.json` found there is
+    /// used instead of the built-in copy. Defaults to the platform data dir, e.g. `~/.local/share/monk_minal_rust`.
+    #[clap(long, value_name = "DIR")]
+    data_dir: Option<std::path::PathBuf>,
+
+    /// Language pack to draw words from, e.g. `english`, `german` or `spanish`.
+    #[clap(long, value_name = "NAME")]
+    language: Option<String>,
+
+    /// How to mark the character to type next.
+    #[clap(long = "caret", value_enum, value_name = "STYLE")]
+    caret_style: Option<config::CaretStyle>,
+
+    /// Color theme for the typing area.
+    #[clap(long, value_enum)]
+    theme: Option<config::ThemeName>,
+
+    /// Count down 3-2-1 after the first key press before the timer starts.
+    #[clap(long)]
+    countdown: bool,
+
+    /// WPM to aim for, scored by --wpm-scheme; shown live and judged on the results screen.
+    #[clap(long, value_name = "WPM")]
+    target_wpm: Option<u32>,
+
+    /// Accuracy floor in percent; the run fails and ends as soon as accuracy drops below it.
+    #[clap(long, value_name = "PERCENT")]
+    min_accuracy: Option<f64>,
+
+    /// Fail and end the run as soon as this many mistakes are made in a row.
+    #[clap(long, value_name = "COUNT")]
+    max_consecutive_errors: Option<u32>,
+
+    /// Reject wrong keys, so every character has to be typed correctly before moving on.
+    #[clap(long)]
+    strict: bool,
+
+    /// Show mistakes like correct input while typing; they only appear in the results.
+    #[clap(long)]
+    blind: bool,
+
+    /// Score Time mode by the number of words completed rather than by characters typed.
+    #[clap(long)]
+    time_attack: bool,
+
+    /// Ignore Backspace, so mistakes are permanent. A wrong key uses up the character it was
+    /// meant for instead of blocking, so you push on through the text.
+    #[clap(long)]
+    no_backspace: bool,
+
+    /// Start the test over, on the same text, as soon as you make a mistake.
+    #[clap(long)]
+    restart_on_error: bool,
+
+    /// Type each word again, up to three times, until you type it without a mistake.
+    #[clap(long)]
+    retry_mistyped: bool,
+
+    /// Keep adding up every attempt when restarting, and show the totals for the whole session
+    /// when you leave.
+    #[clap(long)]
+    cumulative: bool,
+
+    /// Drill a few keyboard rows (QWERTY): words use only letters from these rows, topped up with
+    /// made-up words when the word list has too few.
+    #[clap(long, value_enum, value_name = "ROWS")]
+    key_rows: Option<config::KeyRows>,
+
+    /// Favor words with the characters you miss most, judged from the results history.
+    /// Until enough runs are saved, words are picked as usual.
+    #[clap(long)]
+    weak_keys: bool,
+
+    /// Chain this many quotes back to back in Quote mode, scored as one run.
+    #[clap(long, value_name = "COUNT")]
+    marathon: Option<u32>,
+
+    /// Type this quote instead of a random one: the quote with this id, or else the one at this
+    /// index (from 0) in the quotes file. Implies `--mode quote`.
+    #[clap(long, value_name = "ID")]
+    quote: Option<u64>,
+
+    /// Extra feedback on each mistyped character. Remembered for later sessions.
+    #[clap(long, value_enum, value_name = "KIND")]
+    error_feedback: Option<config::ErrorFeedback>,
+
+    /// How the headline WPM is scored: gross minus an error penalty (net), gross times
+    /// accuracy (simple) or gross alone (raw). Remembered for later sessions.
+    #[clap(long, value_enum, value_name = "SCHEME")]
+    wpm_scheme: Option<config::WpmScheme>,
+
+    /// Take this many WPM off net WPM for each error per minute (default 1; 0 makes net WPM
+    /// equal gross WPM). Remembered for later sessions.
+    #[clap(long, value_name = "WEIGHT")]
+    error_penalty: Option<f64>,
+
+    /// Smooth the live WPM in the header, giving each new figure this weight (above 0, up to 1
+    /// for no smoothing). Lower is calmer. The results are not smoothed. Remembered for later sessions.
+    #[clap(long, value_name = "FACTOR")]
+    wpm_smoothing: Option<f64>,
+
+    /// In Words and Quote mode, highlight the word count in the header once fewer than this many
+    /// words are left. Remembered for later sessions; 0 turns it off.
+    #[clap(long, value_name = "WORDS")]
+    finish_warning: Option<u32>,
+
+    /// Show at most this many words at a time instead of filling the terminal width.
+    /// Remembered for later sessions; 0 goes back to fitting the terminal.
+    #[clap(long, value_name = "COUNT")]
+    window_words: Option<u32>,
+
+    /// Show a few lines around the current word (window), or the whole passage in Words, Quote
+    /// and Custom modes (full). Remembered for later sessions.
+    #[clap(long, value_enum, value_name = "VIEW")]
+    text_view: Option<config::TextView>,
+
+    /// Flow the words through up to this many columns (1 to 3) on wide terminals, to see more of
+    /// the text ahead at a comfortable line length. Remembered for later sessions.
+    #[clap(long, value_name = "COUNT")]
+    columns: Option<u32>,
+
+    /// What space does in the middle of a word: only complete a fully typed word (complete), or
+    /// move on to the next word and count the rest as missed (skip). Remembered for later sessions.
+    #[clap(long, value_enum, value_name = "POLICY")]
+    space_policy: Option<config::SpacePolicy>,
+
+    /// Also put the terminal's own cursor on the character to type next (true or false), for
+    /// terminals that blink it. Remembered for later sessions.
+    #[clap(long, value_name = "BOOL")]
+    show_cursor: Option<bool>,
+
+    /// Let Enter finish a Words, Quote or Custom test where it stands (true or false), scored
+    /// like a completed test rather than quit like Esc. Remembered for later sessions.
+    #[clap(long, value_name = "BOOL")]
+    enter_finishes: Option<bool>,
+
+    /// Pause the test after this many seconds without a key press; the idle time is not counted.
+    /// Remembered for later sessions; 0 turns it off again.
+    #[clap(long, value_name = "SECONDS")]
+    idle_timeout: Option<u32>,
+
+    /// End any run after this many minutes of typing, whatever the mode, as a safety cap for
+    /// long Words, Quote or Zen runs. Paused time is not counted. Remembered for later sessions;
+    /// 0 removes the cap.
+    #[clap(long, value_name = "MINUTES")]
+    max_minutes: Option<u32>,
+
+    /// Only use words with at least this many characters in Time, Words and Zen modes, whatever
+    /// the difficulty. Remembered for later sessions; 0 removes the minimum.
+    #[clap(long, value_name = "CHARS")]
+    min_word_length: Option<u32>,
+
+    /// Only use words with at most this many characters, instead of the difficulty's limit.
+    /// Remembered for later sessions; 0 goes back to the difficulty's limit.
+    #[clap(long, value_name = "CHARS")]
+    max_word_length: Option<u32>,
+
+    /// Seed word and quote selection, so the same seed and settings always give the same tests.
+    /// Without it every session is random.
+    #[clap(long, value_name = "SEED")]
+    seed: Option<u64>,
+
+    /// Append the results of every finished run to this CSV file, writing a header first if the
+    /// file is new.
+    #[clap(long, value_name = "PATH")]
+    csv: Option<std::path::PathBuf>,
+
+    /// Save a recording of every finished run to this file, replacing the previous one: each key
+    /// with its time and the character expected, as JSON lines. Play it back with `--replay`.
+    #[clap(long, value_name = "PATH")]
+    record: Option<std::path::PathBuf>,
+
+    /// Play back a run saved with `--record` at its original pace, then exit. Esc skips to the results.
+    #[clap(long, value_name = "PATH")]
+    replay: Option<std::path::PathBuf>,
+
+    /// Type this text instead of generated words or a quote. Implies `--mode custom`,
+    /// or use `--mode code` to keep its line breaks and indentation.
+    /// Punctuation and capitalization are kept exactly as given.
+    #[clap(long, value_name = "TEXT", conflicts_with = "text_file")]
+    text: Option<String>,
+
+    /// Type the contents of this file instead of generated words or a quote. Implies `--mode custom`.
+    #[clap(long, value_name = "PATH")]
+    text_file: Option<std::path::PathBuf>,
+
+    /// Print how many words were loaded (from `--words-file`, or the `--language` pack) with a sample, then exit.
+    #[clap(long)]
+    list_words: bool,
+
+    /// Print how many quotes were loaded with a sample, then exit.
+    #[clap(long)]
+    list_quotes: bool,
+
+    /// Print a sample line in every color theme, then exit. With NO_COLOR set, only the theme names are listed.
+    #[clap(long)]
+    theme_preview: bool,
+
+    /// Check the settings and the data without playing: build the game config (using defaults for
+    /// anything not given), load the words and quotes and select a test's words, then print
+    /// "OK: N words selected". Exits with an error if any step fails.
+    #[clap(long)]
+    check: bool,
+
+    /// Skip the welcome banner and go straight to the settings or the game. To skip it every time,
+    /// set "show_banner" to false in the saved config file.
+    #[clap(long)]
+    no_banner: bool,
+
+    /// How to report the results once the game ends.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
 }
 
-/// Main entry point for the MonkMinal Rust application.
-///
-/// This function performs the following steps:
-/// 1. Parses command line arguments (currently only handles `--version` and `--help` via `clap`).
-/// 2. Displays a welcome banner.
-/// 3. Prompts the user for game configuration using `dialoguer`.
-/// 4. Loads necessary game data (words, quotes) from JSON files.
-/// 5. Starts and runs the main game loop.
-/// 6. Handles errors that occur during gameplay and ensures the terminal is reset.
-fn main() -> Result<()> {
-    // For this simplified logging, we are not using an external logger facade like env_logger.
-    // Log messages will go to stderr by default if not captured by a more sophisticated logger.
-    // To actually see log::info etc. you would typically need a logger initialized.
-    // However, log::error! will print to stderr regardless of RUST_LOG.
-    // For now, we focus on log::error! for critical failures.
+/// Number of entries shown from each end of a list by `--list-words` and `--list-quotes`.
+const LIST_SAMPLE_SIZE: usize = 5;
+/// Longest quote text, in characters, shown by `--list-quotes` before it is cut off.
+const LIST_QUOTE_MAX_CHARS: usize = 60;
 
-    // Parse command-line arguments. Clap handles --version and --help automatically.
-    let _args = CliArgs::parse(); 
+/// Ways of reporting the results of a run.
+#[derive(Debug, Clone, PartialEq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Only the game-over screen.
+    Text,
+    /// The game-over screen, then the results as a JSON object on stdout.
+    Json,
+}
+
+impl CliArgs {
+    /// Returns the directory searched for replacement data files: `--data-dir`, or else the default one.
+    fn data_dir(&self) -> Option<std::path::PathBuf> {
+        self.data_dir.clone().or_else(data_loader::default_data_dir)
+    }
+
+    /// Collects the game settings given on the command line.
+    ///
+    /// Fails if the `--text-file` cannot be read.
+    fn config_overrides(&self) -> Result<config::ConfigOverrides> {
+        Ok(config::ConfigOverrides {
+            game_type: self.mode.clone(),
+            time_seconds: self.time,
+            word_count: self.words,
+            difficulty: self.difficulty.clone(),
+            punctuation: self.punctuation.then_some(true),
+            numbers: self.numbers.then_some(true),
+            language: self.language.clone(),
+            caret_style: self.caret_style.clone(),
+            theme: self.theme.clone(),
+            countdown: self.countdown.then_some(true),
+            target_wpm: self.target_wpm,
+            min_accuracy: self.min_accuracy,
+            max_consecutive_errors: self.max_consecutive_errors,
+            strict: self.strict.then_some(true),
+            blind: self.blind.then_some(true),
+            time_attack: self.time_attack.then_some(true),
+            no_backspace: self.no_backspace.then_some(true),
+            restart_on_error: self.restart_on_error.then_some(true),
+            retry_mistyped: self.retry_mistyped.then_some(true),
+            cumulative: self.cumulative.then_some(true),
+            key_rows: self.key_rows.clone(),
+            weak_keys: self.weak_keys.then_some(true),
+            marathon: self.marathon,
+            quote_number: self.quote,
+            error_feedback: self.error_feedback.clone(),
+            wpm_scheme: self.wpm_scheme.clone(),
+            error_penalty: self.error_penalty,
+            wpm_smoothing: self.wpm_smoothing,
+            finish_warning_words: self.finish_warning,
+            window_words: self.window_words,
+            text_view: self.text_view.clone(),
+            text_columns: self.columns,
+            space_policy: self.space_policy.clone(),
+            show_cursor: self.show_cursor,
+            enter_finishes: self.enter_finishes,
+            idle_timeout: self.idle_timeout,
+            max_minutes: self.max_minutes,
+            min_word_length: self.min_word_length,
+            max_word_length: self.max_word_length,
+            custom_text: self.custom_text()?,
+            seed: self.seed,
+            csv_path: self.csv.clone(),
+            record_path: self.record.clone(),
+        })
+    }
+
+    /// Returns the custom text from `--text`, or read from `--text-file`.
+    fn custom_text(&self) -> Result<Option<String>> {
+        match &self.text_file {
+            Some(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read text file {}", path.display()))
+                .map(Some),
+            None => Ok(self.text.clone()),
+        }
+    }
+}
 
-    // Display the application welcome banner using Figlet.
-    let standard_font = FIGfont::standard().unwrap_or_else(|_| FIGfont::from_content("MonkMinal").unwrap_or_default());
-    let figure = standard_font.convert("MonkMinal");
-    println!("{}", figure.unwrap_or_default().to_string().cyan());
+/// Prints the Figlet welcome banner, followed by the application title, version, author and description.
+fn print_banner() {
+    let figure = FIGfont::standard()
+        .ok()
+        .and_then(|font| font.convert("MonkMinal").map(|figure| figure.to_string()));
+    println!("{}", figure.unwrap_or_default().cyan());
     println!(); 
 
-    // Print application title, version, author, and description.
     println!(
         "{} {}",
         "monk-minal".green().bold(),
@@ -61,31 +373,69 @@ fn main() -> Result<()> {
     println!("{}{}", "by ".dimmed(), env!("CARGO_PKG_AUTHORS").italic());
     println!("{}", env!("CARGO_PKG_DESCRIPTION").italic().dimmed());
     println!(); 
+}
 
-    // Get game configuration from the user.
-    let game_config = match config::get_game_config() {
-        Ok(cfg) => cfg,
-        Err(e) => {
-            error!("Failed to get game configuration: {}", e);
-            // Attempt to reset terminal if dialoguer left it in a weird state (though it usually handles this)
-            use crossterm::{execute, terminal, cursor};
-            let mut stderr_temp = std::io::stderr(); 
-            execute!(stderr_temp, cursor::Show).ok(); 
-            terminal::disable_raw_mode().ok();
-            return Err(e.context("Configuration failed")); // Propagate error
+/// Main entry point for the MonkMinal Rust application.
+///
+/// This function performs the following steps:
+/// 1. Parses command line arguments (game settings, plus `--version` and `--help` via `clap`).
+/// 2. Loads the quotes, and displays a welcome banner unless `--no-banner` is given or the saved
+///    config turns it off.
+/// 3. Builds the game configuration from the flags, prompting with `dialoguer` for anything missing.
+/// 4. Loads the words from the chosen language pack, falling back to English if it is missing,
+///    or from `--words-file`.
+/// 5. Starts and runs the main game loop, then prints the results as JSON if `--output json` is given.
+/// 6. Offers to play again, change the settings or quit, repeating from step 4 until the user quits.
+/// 7. Handles errors that occur during gameplay and ensures the terminal is reset.
+fn main() -> Result<()> {
+    // Parse command-line arguments. Clap handles --version and --help automatically.
+    let args = CliArgs::parse();
+
+    // Honor NO_COLOR (https://no-color.org) and skip ANSI styling when stdout is not a terminal.
+    let color = std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::stdout().is_terminal();
+    if !color {
+        colored::control::set_override(false);
+    }
+    // Put the terminal back to normal before a panic message is printed, in case it hit mid-game.
+    let default_panic_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_panic_hook(info);
+    }));
+
+    let overrides = args.config_overrides()?;
+    overrides.validate().context("Invalid command-line arguments")?;
+
+    if let Some(path) = &args.replay {
+        return replay::replay(path).context("Replay failed");
+    }
+
+    if args.theme_preview {
+        for line in theme::preview_lines(color) {
+            println!("{}", line);
         }
-    };
-    println!(); // Add spacing after dialoguer prompts.
+        return Ok(());
+    }
 
-    // Load game data (words and quotes).
-    let all_words = match data_loader::load_all_words() {
-        Ok(words) => words,
-        Err(e) => {
-            error!("Failed to load words data: {}", e);
-            return Err(e.context("Loading words failed"));
+    if args.check {
+        let selected = run_check(&args, &overrides).context("Check failed")?;
+        println!("OK: {} words selected", selected);
+        return Ok(());
+    }
+
+    // Diagnostics for custom data: show what was loaded and exit without playing.
+    if args.list_words || args.list_quotes {
+        if args.list_words {
+            list_words(&args).context("Loading words failed")?;
         }
-    };
-    let all_quotes = match data_loader::load_quotes() {
+        if args.list_quotes {
+            list_quotes(&args).context("Loading quotes failed")?;
+        }
+        return Ok(());
+    }
+
+    // Load the quotes before anything is asked, so a broken quotes file is reported straight away.
+    let all_quotes = match spinner::with_spinner("Loading quotes...", || data_loader::load_quotes_from(args.data_dir().as_deref())) {
         Ok(quotes) => quotes,
         Err(e) => {
             error!("Failed to load quotes data: {}", e);
@@ -93,20 +443,234 @@ fn main() -> Result<()> {
         }
     };
 
-    // Run the game with the chosen configuration and loaded data.
-    if let Err(e) = game::run_game(game_config, all_words, all_quotes) {
-        // Log the error using the log crate.
-        // The error `e` from run_game should be an anyhow::Error, which includes context.
-        error!("Game error: {:?}", e); // {:?} for full context from anyhow
-        
-        // `run_game` should ideally handle its own terminal teardown on error.
-        // This is a fallback.
-        use crossterm::{execute, terminal, cursor};
-        let mut stderr_temp = std::io::stderr(); 
-        execute!(stderr_temp, cursor::Show).ok(); 
-        terminal::disable_raw_mode().ok(); 
-        std::process::exit(1); // Exit with an error code
-    }
-    
+    // Skipped with --no-banner, or when the saved config turns it off.
+    if !args.no_banner && config::load_saved_config().is_none_or(|saved| saved.show_banner) {
+        print_banner();
+    }
+
+    // Get game configuration from the flags, or from the user for anything the flags leave out.
+    // Either way it is completed like a prompted one, so sticky settings are remembered and restored.
+    let from_flags = overrides.to_complete_config()
+        .map(|cfg| config::finish_game_config(cfg, &overrides, &config::load_saved_config().unwrap_or_default()));
+    let mut game_config = match from_flags.map_or_else(|| ask_game_config(&overrides), |cfg| Ok(Some(cfg))) {
+        Ok(Some(cfg)) => cfg,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            error!("Failed to get game configuration: {}", e);
+            // Attempt to reset terminal if dialoguer left it in a weird state (e.g. after Ctrl+C)
+            restore_terminal();
+            return Err(e.context("Configuration failed")); // Propagate error
+        }
+    };
+
+    loop {
+        println!(); // Add spacing after dialoguer prompts.
+        let word_list = match spinner::with_spinner("Loading words...", || load_words(&args, &game_config)) {
+            Ok(words) => words,
+            Err(e) => {
+                error!("Failed to load words data: {}", e);
+                return Err(e.context("Loading words failed"));
+            }
+        };
+        let mut session_config = game_config.clone();
+        session_config.word_weights = word_list.weights;
+
+        // Run the game with the chosen configuration and loaded data.
+        let result = match game::run_game(session_config, word_list.words, all_quotes.clone()) {
+            Ok(result) => result,
+            Err(e) => {
+                // Log the error using the log crate.
+                // The error `e` from run_game should be an anyhow::Error, which includes context.
+                error!("Game error: {:?}", e); // {:?} for full context from anyhow
+
+                // `run_game` restores the terminal itself, even on error.
+                // This is a fallback in case that restoring failed too.
+                restore_terminal();
+                std::process::exit(1); // Exit with an error code
+            }
+        };
+
+        if args.output == OutputFormat::Json {
+            println!("{}", serde_json::to_string(&result).context("Failed to serialize results")?);
+        } else if result.interrupted {
+            // The game-over screen was skipped, so show what the run came to.
+            println!("{}", result.interrupted_summary());
+        }
+        if let Some(session) = result.session.as_ref().filter(|_| result.exit_requested && args.output == OutputFormat::Text) {
+            // Ctrl+C skipped the session summary screen too.
+            println!("{}", session.summary());
+        }
+        if result.exit_requested {
+            break;
+        }
+
+        // Back at the menu: play again, pick new settings, or leave. Ctrl+C in a menu also quits.
+        match config::prompt_next_action() {
+            Ok(config::NextAction::NewGame) => {}
+            Ok(config::NextAction::ChangeSettings) => match ask_game_config(&overrides.for_new_settings()) {
+                Ok(Some(cfg)) => game_config = cfg,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Settings prompt ended without a new configuration: {}", e);
+                    restore_terminal();
+                    break;
+                }
+            },
+            Ok(config::NextAction::Quit) => break,
+            Err(e) => {
+                warn!("Menu prompt ended: {}", e);
+                restore_terminal();
+                break;
+            }
+        }
+    }
+
     Ok(())
 }
+
+/// Asks for the settings `overrides` leaves out: on the settings screen when stdout is a terminal,
+/// and with the `dialoguer` prompts otherwise or if the screen cannot be shown.
+///
+/// Returns `None` if the player left the settings screen without starting a game.
+fn ask_game_config(overrides: &config::ConfigOverrides) -> Result<Option<config::GameConfig>> {
+    if std::io::stdout().is_terminal() {
+        match settings_screen::choose_game_config(overrides) {
+            Ok(chosen) => return Ok(chosen),
+            Err(e) => warn!("Settings screen failed, asking with prompts instead: {:?}", e),
+        }
+    }
+    config::get_game_config(overrides).map(Some)
+}
+
+/// Loads the word list for `game_config`: the `--words-file` if given, else the configured
+/// language pack, falling back to English if that pack is missing or broken.
+fn load_words(args: &CliArgs, game_config: &config::GameConfig) -> Result<data_loader::WordList> {
+    match &args.words_file {
+        Some(path) => data_loader::load_words_from_path(path),
+        None => data_loader::load_words_for_language(&game_config.language, args.data_dir().as_deref()).or_else(|e| {
+            // A missing or broken language pack should not stop the game; use English instead.
+            warn!("Falling back to English words: {:?}", e);
+            println!("{}", format!("{:#}. Falling back to English.", e).yellow());
+            data_loader::load_all_words_from(args.data_dir().as_deref())
+        }),
+    }
+}
+
+/// Runs the startup pipeline for `--check` without a terminal: builds the game config from
+/// `overrides` and the defaults, loads the quotes and words and selects the words of a test.
+///
+/// Returns how many words were selected.
+fn run_check(args: &CliArgs, overrides: &config::ConfigOverrides) -> Result<usize> {
+    let mut game_config = overrides
+        .with_defaults()
+        .to_complete_config()
+        .context("Custom and Code modes need the text to type, given with --text or --text-file")?;
+    let all_quotes = data_loader::load_quotes_from(args.data_dir().as_deref()).context("Loading quotes failed")?;
+    let word_list = load_words(args, &game_config).context("Loading words failed")?;
+    game_config.word_weights = word_list.weights;
+    let words = game::get_words_for_game(&game_config, &word_list.words, &all_quotes)
+        .with_context(|| format!("Failed to select words with config: {:?}", game_config))?;
+    Ok(words.len())
+}
+
+/// Prints the word list a game would use (`--list-words`): its size, a sample and any warnings.
+///
+/// Unlike a game, a missing language pack is an error here rather than falling back to English.
+fn list_words(args: &CliArgs) -> Result<()> {
+    let (origin, word_list) = match &args.words_file {
+        Some(path) => (path.display().to_string(), data_loader::load_words_from_path(path)?),
+        None => {
+            let language = args.language.as_deref().unwrap_or(data_loader::DEFAULT_LANGUAGE);
+            let data_dir = args.data_dir();
+            (format!("language pack '{}'", language), data_loader::load_words_for_language(language, data_dir.as_deref())?)
+        }
+    };
+    let weighting = if word_list.weights.is_empty() { "" } else { ", weighted by frequency" };
+    println!("{} words loaded from {}{}", word_list.words.len(), origin, weighting);
+    print_sample(&word_list.words);
+    print_warnings(&data_loader::word_list_warnings(&word_list.words));
+    Ok(())
+}
+
+/// Prints the quotes Quote mode draws from (`--list-quotes`): their number, a sample and any warnings.
+fn list_quotes(args: &CliArgs) -> Result<()> {
+    let quotes = data_loader::load_quotes_from(args.data_dir().as_deref())?;
+    println!("{} quotes loaded", quotes.len());
+    let entries: Vec<String> = quotes
+        .iter()
+        .map(|quote| {
+            let mut text: String = quote.text.chars().take(LIST_QUOTE_MAX_CHARS).collect();
+            if quote.text.chars().count() > LIST_QUOTE_MAX_CHARS {
+                text.push_str("...");
+            }
+            format!("{} \u{2014} {}", text, quote.source)
+        })
+        .collect();
+    print_sample(&entries);
+    print_warnings(&data_loader::quote_warnings(&quotes));
+    Ok(())
+}
+
+/// Prints the first and last `LIST_SAMPLE_SIZE` of `entries`, or all of them if there are few.
+fn print_sample(entries: &[String]) {
+    if entries.len() <= 2 * LIST_SAMPLE_SIZE {
+        entries.iter().for_each(|entry| println!("  {}", entry));
+        return;
+    }
+    entries[..LIST_SAMPLE_SIZE].iter().for_each(|entry| println!("  {}", entry));
+    println!("  {}", "...".dimmed());
+    entries[entries.len() - LIST_SAMPLE_SIZE..].iter().for_each(|entry| println!("  {}", entry));
+}
+
+/// Prints each warning about loaded data in yellow.
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        println!("{}", format!("warning: {}", warning).yellow());
+    }
+}
+
+/// Shows the cursor and leaves raw mode, in case a prompt or the game left the terminal altered.
+fn restore_terminal() {
+    game::restore_terminal().ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `flags` as if given on the command line, and runs `--check` with them.
+    ///
+    /// The data dir is an empty temp dir, so only the embedded data is used, whatever the
+    /// developer keeps in their own data dir.
+    fn check(flags: &[&str]) -> Result<usize> {
+        let data_dir = std::env::temp_dir().join(format!("monk_minal_rust_test_{}_empty_data", std::process::id()));
+        std::fs::create_dir_all(&data_dir)?;
+        let data_dir = data_dir.to_string_lossy().into_owned();
+        let args = CliArgs::parse_from(["monk_minal_rust", "--check", "--data-dir", &data_dir].iter().chain(flags));
+        let overrides = args.config_overrides()?;
+        overrides.validate()?;
+        run_check(&args, &overrides)
+    }
+
+    #[test]
+    fn check_selects_words_with_the_real_data() {
+        assert_eq!(check(&["--words", "15", "--difficulty", "easy"]).unwrap(), 15);
+        assert!(check(&[]).unwrap() > 0);
+        assert!(check(&["--mode", "quote"]).unwrap() > 0);
+        assert_eq!(check(&["--text", "one two three"]).unwrap(), 3);
+    }
+
+    #[test]
+    fn check_reports_broken_settings_and_data() {
+        assert!(check(&["--words-file", "/nonexistent/words.txt"]).is_err());
+        assert!(check(&["--mode", "code"]).is_err());
+        assert!(check(&["--quote", "100000000"]).is_err());
+        assert!(check(&["--quote", "2", "--mode", "words"]).is_err());
+    }
+
+    #[test]
+    fn check_types_the_chosen_quote() {
+        // Quote 2 of the bundled quotes is "They don't know that we know they know we know."
+        assert_eq!(check(&["--quote", "2"]).unwrap(), 10);
+    }
+}
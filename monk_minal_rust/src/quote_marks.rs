@@ -0,0 +1,163 @@
+//! # Quote Marks Module
+//!
+//! This module lets players mark quotes: favorites are picked more often in Quote mode, and
+//! blacklisted quotes are not picked at all. The marks are stored in `quote_marks.json`, next
+//! to the saved configuration in the platform config directory. Quotes are identified by their
+//! `id` when the quotes file gives one, or else by a hash of their text (see `quote_key`).
+
+use crate::config::app_config_dir;
+use crate::data_loader::Quote;
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the quote marks, inside the app config directory.
+const QUOTE_MARKS_FILE_NAME: &str = "quote_marks.json";
+/// How many times more likely a favorite quote is to be picked than an unmarked one.
+pub const FAVORITE_WEIGHT: u32 = 4;
+
+/// The quotes the player has marked, by `quote_key`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuoteMarks {
+    /// Quotes picked `FAVORITE_WEIGHT` times as often as the others.
+    pub favorites: BTreeSet<String>,
+    /// Quotes never picked, unless every candidate is blacklisted.
+    pub blacklist: BTreeSet<String>,
+}
+
+impl QuoteMarks {
+    /// Returns whether `quote` is a favorite.
+    pub fn is_favorite(&self, quote: &Quote) -> bool {
+        self.favorites.contains(&quote_key(quote))
+    }
+
+    /// Returns whether `quote` is blacklisted.
+    pub fn is_blacklisted(&self, quote: &Quote) -> bool {
+        self.blacklist.contains(&quote_key(quote))
+    }
+
+    /// Returns how likely `quote` is to be picked, relative to an unmarked quote.
+    pub fn weight(&self, quote: &Quote) -> u32 {
+        if self.is_favorite(quote) { FAVORITE_WEIGHT } else { 1 }
+    }
+
+    /// Marks `quote` as a favorite, or unmarks it if it already is one. Returns whether it is
+    /// a favorite now. A quote cannot be both, so favoriting takes it off the blacklist.
+    pub fn toggle_favorite(&mut self, quote: &Quote) -> bool {
+        let key = quote_key(quote);
+        self.blacklist.remove(&key);
+        toggle(&mut self.favorites, key)
+    }
+
+    /// Blacklists `quote`, or allows it again if it already is blacklisted. Returns whether it
+    /// is blacklisted now. Blacklisting takes it off the favorites.
+    pub fn toggle_blacklisted(&mut self, quote: &Quote) -> bool {
+        let key = quote_key(quote);
+        self.favorites.remove(&key);
+        toggle(&mut self.blacklist, key)
+    }
+}
+
+/// Adds `key` to `set` if it is missing and removes it otherwise. Returns whether it was added.
+fn toggle(set: &mut BTreeSet<String>, key: String) -> bool {
+    if set.remove(&key) {
+        false
+    } else {
+        set.insert(key);
+        true
+    }
+}
+
+/// Returns the key `quote` is stored under: `id:<id>` if it has an id, or else `text:` and a
+/// hash of its text.
+///
+/// The hash is 64-bit FNV-1a, which unlike `std`'s hasher stays the same between Rust versions,
+/// so marks keep matching their quotes across builds.
+pub fn quote_key(quote: &Quote) -> String {
+    if let Some(id) = quote.id {
+        return format!("id:{}", id);
+    }
+    let hash = quote.text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("text:{:016x}", hash)
+}
+
+/// Returns the path of the quote marks file, if the platform has a config dir.
+pub fn quote_marks_file_path() -> Option<PathBuf> {
+    app_config_dir().map(|dir| dir.join(QUOTE_MARKS_FILE_NAME))
+}
+
+/// Loads the quote marks stored at `path`.
+///
+/// A missing file means nothing is marked yet. An unreadable one is logged and treated the
+/// same way, so a corrupt file never stops Quote mode.
+pub fn load_quote_marks(path: &Path) -> QuoteMarks {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return QuoteMarks::default();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable quote marks in {}: {}", path.display(), e);
+        QuoteMarks::default()
+    })
+}
+
+/// Saves `marks` to `path`, creating its directory if needed.
+pub fn save_quote_marks(path: &Path, marks: &QuoteMarks) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create quote marks directory {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(marks).context("Failed to serialize quote marks")?;
+    fs::write(path, json).with_context(|| format!("Failed to write quote marks file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a quote with the given text and id.
+    fn quote(text: &str, id: Option<u64>) -> Quote {
+        Quote { text: text.to_string(), source: "Someone".to_string(), id, ..Quote::default() }
+    }
+
+    #[test]
+    fn quotes_are_keyed_by_id_or_text() {
+        assert_eq!(quote_key(&quote("Hello.", Some(12))), "id:12");
+        assert_eq!(quote_key(&quote("", None)), "text:cbf29ce484222325");
+        assert_eq!(quote_key(&quote("Hello.", None)), quote_key(&quote("Hello.", None)));
+        assert_ne!(quote_key(&quote("Hello.", None)), quote_key(&quote("Hello!", None)));
+    }
+
+    #[test]
+    fn a_quote_is_either_a_favorite_or_blacklisted() {
+        let mut marks = QuoteMarks::default();
+        let hello = quote("Hello.", None);
+        assert!(marks.toggle_favorite(&hello));
+        assert_eq!(marks.weight(&hello), FAVORITE_WEIGHT);
+        assert!(marks.toggle_blacklisted(&hello));
+        assert!(marks.is_blacklisted(&hello) && !marks.is_favorite(&hello));
+        assert!(!marks.toggle_blacklisted(&hello));
+        assert_eq!(marks, QuoteMarks::default());
+    }
+
+    #[test]
+    fn marks_round_trip_through_the_file() {
+        let path = std::env::temp_dir()
+            .join(format!("monk_minal_rust_test_{}_quote_marks", std::process::id()))
+            .join(QUOTE_MARKS_FILE_NAME);
+        let _ = fs::remove_file(&path);
+        assert_eq!(load_quote_marks(&path), QuoteMarks::default());
+        let mut marks = QuoteMarks::default();
+        marks.toggle_favorite(&quote("Hello.", Some(1)));
+        marks.toggle_blacklisted(&quote("Bye.", None));
+        save_quote_marks(&path, &marks).unwrap();
+        assert_eq!(load_quote_marks(&path), marks);
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(load_quote_marks(&path), QuoteMarks::default());
+    }
+}
@@ -0,0 +1,188 @@
+//! # Replay Module
+//!
+//! This module saves a finished run as a recording (`--record`) and plays it back in the
+//! terminal (`--replay`), to review where the mistakes were made. A recording is a JSON-lines
+//! file: the first line holds the configuration, the text and the duration, and every further
+//! line one edit (see `LoggedEdit`) with the moment it was made and the character expected, so
+//! the file is also easy to analyze elsewhere. Playing it back feeds the edits through
+//! `apply_edit` at their original pace, so the replayed run ends up in exactly the state the
+//! original did. Paused time was never counted, so pauses are skipped.
+
+use crate::config::GameConfig;
+use crate::events::{EventSource, TerminalEvents};
+use crate::game::{apply_edit, is_quit_key, GameState, LoggedEdit, TerminalGuard};
+use crate::ui;
+use anyhow::{Context, Result};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{stdout, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// A recorded run: everything needed to play it back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    /// The configuration the run was played with.
+    pub config: GameConfig,
+    /// The text of the run, as words, including any added while it went on.
+    pub words: Vec<String>,
+    /// How long the run took, in seconds.
+    pub duration: f64,
+    /// Every edit of the run, in order. Saved on lines of their own after the rest.
+    #[serde(skip)]
+    pub edits: Vec<LoggedEdit>,
+}
+
+impl Recording {
+    /// Records the run in `game_state`, which took `duration` seconds.
+    pub fn from_state(game_state: &GameState, duration: f64) -> Self {
+        Recording {
+            config: game_state.config.clone(),
+            words: game_state.words_to_type.clone(),
+            duration,
+            edits: game_state.edit_log.clone(),
+        }
+    }
+}
+
+/// Writes `recording` to `path` as JSON lines, replacing any earlier recording there.
+pub fn save_recording(path: &Path, recording: &Recording) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create recording {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    let write_line = |out: &mut BufWriter<File>, json: String| writeln!(out, "{}", json)
+        .with_context(|| format!("Failed to write recording {}", path.display()));
+    write_line(&mut out, serde_json::to_string(recording).context("Failed to serialize recording")?)?;
+    for edit in &recording.edits {
+        write_line(&mut out, serde_json::to_string(edit).context("Failed to serialize recorded edit")?)?;
+    }
+    out.flush().with_context(|| format!("Failed to write recording {}", path.display()))
+}
+
+/// Reads the recording saved at `path`.
+pub fn load_recording(path: &Path) -> Result<Recording> {
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read recording {}", path.display()))?;
+    let mut lines = contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let (_, first) = lines.next().with_context(|| format!("Recording {} is empty", path.display()))?;
+    let mut recording: Recording = serde_json::from_str(first)
+        .with_context(|| format!("Failed to parse recording {}", path.display()))?;
+    for (i, line) in lines {
+        let edit = serde_json::from_str(line)
+            .with_context(|| format!("Failed to parse line {} of recording {}", i + 1, path.display()))?;
+        recording.edits.push(edit);
+    }
+    Ok(recording)
+}
+
+/// Plays back the recording saved at `path` in the terminal, at its original pace.
+pub fn replay(path: &Path) -> Result<()> {
+    let recording = load_recording(path)?;
+    let guard = TerminalGuard::enable()?;
+    play_recording(&mut stdout(), &mut TerminalEvents, &recording)?;
+    guard.restore()
+}
+
+/// Plays `recording` back on `stdout`, timed by `events`, and returns the replayed game state.
+///
+/// The typing screen is redrawn after every edit. Esc or Ctrl+C skips to the end of the run,
+/// whose results screen then stays up until a key is pressed.
+pub fn play_recording(stdout: &mut impl Write, events: &mut impl EventSource, recording: &Recording) -> Result<GameState> {
+    let mut game_state = GameState::new(recording.config.clone(), Vec::new(), Vec::new(), recording.words.clone());
+    let (mut term_cols, mut term_rows) = events.size().context("Failed to get terminal size")?;
+    let start = events.now();
+    let at = |seconds: f64| start + Duration::from_secs_f64(seconds.max(0.0));
+    game_state.timer.start_at(start);
+    let mut skipping = false;
+    ui::display_game_interface(stdout, &game_state, 0.0, term_cols, term_rows).context("Failed to display replay")?;
+    for LoggedEdit { at: seconds, edit, .. } in &recording.edits {
+        let due = at(*seconds);
+        while !skipping && events.now() < due {
+            if events.poll(due.saturating_duration_since(events.now())).context("Event polling failed during replay")? {
+                match events.read().context("Failed to read event during replay")? {
+                    Event::Key(key_event) if key_event.kind == KeyEventKind::Press
+                        && (key_event.code == KeyCode::Esc || is_quit_key(&key_event)) => skipping = true,
+                    Event::Resize(new_cols, new_rows) => (term_cols, term_rows) = (new_cols, new_rows),
+                    _ => {}
+                }
+            }
+        }
+        game_state.note_input_at(due);
+        game_state.record_wpm_sample(*seconds);
+        apply_edit(&mut game_state, edit.clone());
+        if !skipping {
+            if *seconds > 0.01 {
+                game_state.update_live_wpm(*seconds);
+            }
+            ui::display_game_interface(stdout, &game_state, *seconds, term_cols, term_rows)
+                .context("Failed to display replay")?;
+        }
+    }
+    game_state.end_game_at(at(recording.duration));
+    wait_on_results(stdout, events, &game_state, term_cols, term_rows)?;
+    Ok(game_state)
+}
+
+/// Shows the results of the replayed run until a key is pressed.
+fn wait_on_results(
+    stdout: &mut impl Write,
+    events: &mut impl EventSource,
+    game_state: &GameState,
+    mut term_cols: u16,
+    mut term_rows: u16,
+) -> Result<()> {
+    loop {
+        ui::display_game_over_screen(stdout, game_state, term_cols, term_rows).context("Failed to display replay results")?;
+        match events.read().context("Failed to read event on replay results")? {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => return Ok(()),
+            Event::Resize(new_cols, new_rows) => (term_cols, term_rows) = (new_cols, new_rows),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Difficulty, GameType};
+    use crate::events::ScriptedEvents;
+    use crate::game::{apply_key, Edit};
+    use crossterm::event::{KeyEvent, KeyModifiers};
+    use std::time::Instant;
+
+    #[test]
+    fn replaying_a_recording_reproduces_the_run() {
+        let base = Instant::now();
+        let due_at = |seconds: f64| base + Duration::from_secs_f64(seconds);
+        let config = GameConfig { game_type: GameType::Words, word_count: Some(3), difficulty: Difficulty::Hard, ..GameConfig::new() };
+        let words: Vec<String> = ["ab", "cd", "ef"].map(str::to_string).to_vec();
+        let mut original = GameState::new(config, Vec::new(), Vec::new(), words);
+        original.timer.start_at(base);
+        let keys = [KeyCode::Char('a'), KeyCode::Char('x'), KeyCode::Backspace, KeyCode::Char('b'), KeyCode::Char(' '), KeyCode::Char('c')];
+        for (i, code) in keys.into_iter().enumerate() {
+            original.note_input_at(due_at(0.25 * (i + 1) as f64));
+            apply_key(&mut original, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        original.note_input_at(due_at(2.0));
+        apply_edit(&mut original, Edit::Paste("d ef".to_string()));
+        let duration = original.end_game_at(due_at(2.0));
+
+        let path = std::env::temp_dir().join(format!("monk_minal_rust_test_{}_recording.json", std::process::id()));
+        save_recording(&path, &Recording::from_state(&original, duration)).unwrap();
+        let recording = load_recording(&path).unwrap();
+        assert_eq!(recording.edits.len(), 7);
+        // The results screen waits for a key, which comes long after the replay is over.
+        let close = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        let mut events = ScriptedEvents::new((80, 24), [(Duration::from_secs(60), close)]);
+        let replayed = play_recording(&mut Vec::new(), &mut events, &recording).unwrap();
+
+        let text = |state: &GameState| (state.words_to_type.clone(), state.typed_words.clone(),
+            state.user_input.clone(), state.errors.clone(), state.current_word_index, state.current_char_index);
+        let totals = |state: &GameState| (state.correct_chars_total, state.typed_chars_total, state.wrong_keystrokes,
+            state.words_completed, state.char_stats.clone(), state.game_over);
+        let timing = |state: &GameState| (state.word_seconds.clone(), state.final_elapsed_time_seconds, state.edit_log.clone());
+        assert_eq!(text(&replayed), text(&original));
+        assert_eq!(totals(&replayed), totals(&original));
+        assert_eq!(timing(&replayed), timing(&original));
+        assert!(replayed.is_text_complete());
+    }
+}
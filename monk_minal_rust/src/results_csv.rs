@@ -0,0 +1,92 @@
+//! # Results CSV Module
+//!
+//! This module appends each finished run as a row to a CSV file of the player's choosing
+//! (`--csv`), for analysis in a spreadsheet. Unlike the results history, the file lives wherever
+//! the player points it, and a header row is written when the file is first created.
+
+use crate::stats::RunResult;
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// The first row of every results CSV, naming the columns.
+pub const CSV_HEADER: &str = "timestamp,mode,difficulty,gross_wpm,net_wpm,accuracy,duration,source";
+
+/// Appends `result` as a row to the CSV file at `path`, with `source` crediting the quotes typed
+/// (empty outside Quote mode).
+///
+/// The header is written first if the file is new or empty. The file's directory must exist.
+pub fn append_csv_row(path: &Path, result: &RunResult, source: &str) -> Result<()> {
+    let is_new = fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open CSV file {}", path.display()))?;
+    let mut rows = String::new();
+    if is_new {
+        rows.push_str(CSV_HEADER);
+        rows.push('\n');
+    }
+    rows.push_str(&csv_row(result, source));
+    rows.push('\n');
+    file.write_all(rows.as_bytes()).with_context(|| format!("Failed to write CSV file {}", path.display()))
+}
+
+/// Formats `result` as one CSV row, in the column order of `CSV_HEADER`.
+fn csv_row(result: &RunResult, source: &str) -> String {
+    [
+        result.timestamp.to_string(),
+        format!("{:?}", result.game_type),
+        format!("{:?}", result.difficulty),
+        format!("{:.2}", result.gross_wpm),
+        format!("{:.2}", result.net_wpm),
+        format!("{:.2}", result.accuracy),
+        format!("{:.2}", result.duration),
+        csv_field(source).into_owned(),
+    ].join(",")
+}
+
+/// Quotes `value` for CSV if it contains a comma, a double quote or a line break, doubling any
+/// double quotes inside, as RFC 4180 asks. Other values are written as they are.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Difficulty, GameType};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("Ada Lovelace"), "Ada Lovelace");
+        assert_eq!(csv_field("Lovelace, Ada"), "\"Lovelace, Ada\"");
+        assert_eq!(csv_field("The \"Analyst\""), "\"The \"\"Analyst\"\"\"");
+    }
+
+    #[test]
+    fn runs_are_appended_below_one_header() {
+        let dir = std::env::temp_dir().join(format!("monk_minal_rust_test_{}_csv", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("results.csv");
+        let _ = fs::remove_file(&path);
+        let run = |game_type, gross_wpm, net_wpm| RunResult {
+            timestamp: 1_700_000_000, game_type, difficulty: Difficulty::Medium, gross_wpm, net_wpm,
+            accuracy: 97.5, duration: 30.0, char_stats: BTreeMap::new(),
+        };
+        append_csv_row(&path, &run(GameType::Time, 62.0, 60.5), "").unwrap();
+        append_csv_row(&path, &run(GameType::Quote, 55.25, 51.0), "Lovelace, Ada").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), format!("{}\n{}\n{}\n",
+            CSV_HEADER,
+            "1700000000,Time,Medium,62.00,60.50,97.50,30.00,",
+            "1700000000,Quote,Medium,55.25,51.00,97.50,30.00,\"Lovelace, Ada\""));
+    }
+}
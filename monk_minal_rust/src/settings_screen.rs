@@ -0,0 +1,442 @@
+//! # Settings Screen Module
+//!
+//! This module asks for the game settings on a screen of its own, drawn with crossterm in raw
+//! mode like the game itself, instead of through one `dialoguer` prompt after another. Each
+//! setting is a row: Up and Down pick a row, Left and Right change its value, Enter starts the
+//! game and Esc leaves.
+//!
+//! `SettingsScreen` holds the choices and handles the keys without drawing anything, so the
+//! navigation can be tested without a terminal; `run_settings_screen` draws it and feeds it
+//! events. The prompts of `config::get_game_config` stay as the fallback.
+
+use crate::config::{
+    self, CaretStyle, ConfigOverrides, Difficulty, GameConfig, GameType, QuoteLength, ThemeName,
+    TIME_LIMIT_CHOICES, WORD_COUNT_CHOICES,
+};
+use crate::data_loader;
+use crate::events::{EventSource, TerminalEvents};
+use crate::game::{is_quit_key, TerminalGuard};
+use crate::ui::{centered_column, pad_to_common_width};
+use anyhow::{bail, Context, Result};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, style::Print, terminal};
+use std::io::{stdout, Write};
+
+/// A setting the screen can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    /// The game mode: Time, Words, Quote or Zen.
+    GameType,
+    /// The length of a Time mode run.
+    TimeLimit,
+    /// The number of words in a Words mode run.
+    WordCount,
+    /// The length of the quote in Quote mode.
+    QuoteLength,
+    /// How long the generated words may be.
+    Difficulty,
+    /// Whether punctuation is added to the words.
+    Punctuation,
+    /// Whether numbers are mixed into the words.
+    Numbers,
+    /// The language pack the words come from.
+    Language,
+    /// How the character to type next is marked.
+    CaretStyle,
+    /// The color theme of the typing area.
+    Theme,
+    /// Whether a 3-2-1 countdown runs before the timer starts.
+    Countdown,
+}
+
+/// Every setting, in the order of the rows. Each one's position is its `Setting as usize`.
+const SETTINGS: [Setting; 11] = [
+    Setting::GameType, Setting::TimeLimit, Setting::WordCount, Setting::QuoteLength, Setting::Difficulty,
+    Setting::Punctuation, Setting::Numbers, Setting::Language, Setting::CaretStyle, Setting::Theme,
+    Setting::Countdown,
+];
+/// The game types on offer; Custom and Code modes come with their text from the command line.
+const GAME_TYPES: [GameType; 4] = [GameType::Time, GameType::Words, GameType::Quote, GameType::Zen];
+/// The quote lengths on offer, "Any" first.
+const QUOTE_LENGTHS: [QuoteLength; 4] = [QuoteLength::Any, QuoteLength::Short, QuoteLength::Medium, QuoteLength::Long];
+/// The difficulties on offer, easiest first.
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+/// The caret styles on offer, the default first.
+const CARET_STYLES: [CaretStyle; 4] = [CaretStyle::Highlight, CaretStyle::Block, CaretStyle::Underline, CaretStyle::Bar];
+/// The color themes on offer, the default first.
+const THEMES: [ThemeName; 4] = [ThemeName::Default, ThemeName::Solarized, ThemeName::Mono, ThemeName::HighContrast];
+/// Width the setting names are padded to, so the values line up.
+const LABEL_WIDTH: usize = 13;
+/// The key help shown below the settings.
+const KEY_HELP: &str = "Up/Down: pick a setting | Left/Right: change it | Enter: start | Esc: quit";
+
+/// What a key press on the settings screen asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsAction {
+    /// Stay on the screen.
+    Continue,
+    /// Start the game with the settings chosen.
+    Start,
+    /// Leave without playing.
+    Quit,
+}
+
+/// The state of the settings screen: the value chosen for every setting and the row selected.
+#[derive(Debug, Clone)]
+pub struct SettingsScreen {
+    /// Index of the chosen value of each setting among its options, in `SETTINGS` order.
+    choices: [usize; SETTINGS.len()],
+    /// Index of the selected row among `visible_settings`.
+    selected: usize,
+    /// The word list languages to choose from.
+    languages: Vec<String>,
+    /// The language kept when it is not asked for, e.g. in Quote mode.
+    previous_language: String,
+    /// The settings given on the command line, which are not asked for.
+    overrides: ConfigOverrides,
+}
+
+impl SettingsScreen {
+    /// Creates the screen with the values of `previous` chosen, asking for whatever `overrides`
+    /// leaves out, with `languages` to pick from.
+    ///
+    /// Fails for Custom and Code modes, which take their text from the command line.
+    pub fn new(previous: &GameConfig, overrides: &ConfigOverrides, languages: Vec<String>) -> Result<Self> {
+        if matches!(overrides.implied_game_type(), Some(GameType::Custom | GameType::Code)) {
+            bail!("Custom and Code modes need the text to type, given with --text or --text-file");
+        }
+        let position = |found: Option<usize>, default: usize| found.unwrap_or(default);
+        let mut choices = [0; SETTINGS.len()];
+        choices[Setting::GameType as usize] = position(GAME_TYPES.iter().position(|game_type| *game_type == previous.game_type), 0);
+        choices[Setting::TimeLimit as usize] = position(
+            previous.time_seconds.and_then(|seconds| TIME_LIMIT_CHOICES.iter().position(|&choice| choice == seconds)), 1);
+        choices[Setting::WordCount as usize] = position(
+            previous.word_count.and_then(|count| WORD_COUNT_CHOICES.iter().position(|&choice| choice == count)), 1);
+        choices[Setting::QuoteLength as usize] = position(QUOTE_LENGTHS.iter().position(|length| *length == previous.quote_length), 0);
+        choices[Setting::Difficulty as usize] = position(DIFFICULTIES.iter().position(|difficulty| *difficulty == previous.difficulty), 1);
+        choices[Setting::Punctuation as usize] = previous.punctuation as usize;
+        choices[Setting::Numbers as usize] = previous.numbers as usize;
+        choices[Setting::Language as usize] = position(languages.iter().position(|language| *language == previous.language), 0);
+        choices[Setting::CaretStyle as usize] = position(CARET_STYLES.iter().position(|style| *style == previous.caret_style), 0);
+        choices[Setting::Theme as usize] = position(THEMES.iter().position(|theme| *theme == previous.theme), 0);
+        choices[Setting::Countdown as usize] = previous.countdown as usize;
+        Ok(SettingsScreen {
+            choices,
+            selected: 0,
+            languages,
+            previous_language: previous.language.clone(),
+            overrides: overrides.clone(),
+        })
+    }
+
+    /// Returns the game type chosen, or the one the command line implies.
+    pub fn game_type(&self) -> GameType {
+        self.overrides.implied_game_type().unwrap_or_else(|| GAME_TYPES[self.choice(Setting::GameType)].clone())
+    }
+
+    /// Returns the settings shown as rows, in order: those the command line leaves out and that
+    /// matter for the chosen game type, the same ones `config::get_game_config` prompts for.
+    pub fn visible_settings(&self) -> Vec<Setting> {
+        SETTINGS.into_iter().filter(|&setting| self.is_asked(setting)).collect()
+    }
+
+    /// Returns the setting on the selected row, if any row is shown.
+    pub fn selected_setting(&self) -> Option<Setting> {
+        self.visible_settings().get(self.selected).copied()
+    }
+
+    /// Handles a key press: Up and Down (or Shift+Tab and Tab) move between rows, Left and Right
+    /// change the selected setting, both wrapping around, Enter starts and Esc quits.
+    pub fn handle_key(&mut self, code: KeyCode) -> SettingsAction {
+        let rows = self.visible_settings().len();
+        match code {
+            KeyCode::Enter => return SettingsAction::Start,
+            KeyCode::Esc => return SettingsAction::Quit,
+            _ if rows == 0 => {}
+            KeyCode::Up | KeyCode::BackTab => self.selected = (self.selected + rows - 1) % rows,
+            KeyCode::Down | KeyCode::Tab => self.selected = (self.selected + 1) % rows,
+            KeyCode::Left => self.change_selected(-1),
+            KeyCode::Right => self.change_selected(1),
+            _ => {}
+        }
+        // Changing the game type can take rows away.
+        self.selected = self.selected.min(self.visible_settings().len().saturating_sub(1));
+        SettingsAction::Continue
+    }
+
+    /// Returns the configuration chosen, before the settings that are never asked for are added
+    /// (see `config::finish_game_config`).
+    pub fn to_config(&self) -> GameConfig {
+        let overrides = &self.overrides;
+        let mut config = GameConfig::new();
+        config.game_type = self.game_type();
+        config.time_seconds = None;
+        config.word_count = None;
+        match config.game_type {
+            GameType::Time => {
+                config.time_seconds = overrides.time_seconds.or(Some(TIME_LIMIT_CHOICES[self.choice(Setting::TimeLimit)]));
+            }
+            GameType::Words => {
+                config.word_count = overrides.word_count.or(Some(WORD_COUNT_CHOICES[self.choice(Setting::WordCount)]));
+            }
+            GameType::Quote => config.quote_length = QUOTE_LENGTHS[self.choice(Setting::QuoteLength)].clone(),
+            _ => {}
+        }
+        config.difficulty = overrides.difficulty.clone().unwrap_or_else(|| DIFFICULTIES[self.choice(Setting::Difficulty)].clone());
+        config.key_rows = overrides.key_rows.clone();
+        let word_options = self.word_options_apply();
+        config.punctuation = word_options && overrides.punctuation.unwrap_or(self.choice(Setting::Punctuation) == 1);
+        config.numbers = word_options && overrides.numbers.unwrap_or(self.choice(Setting::Numbers) == 1);
+        config.language = overrides.language.clone().unwrap_or_else(|| {
+            if self.is_asked(Setting::Language) {
+                self.languages[self.choice(Setting::Language)].clone()
+            } else {
+                self.previous_language.clone()
+            }
+        });
+        config.caret_style = overrides.caret_style.clone().unwrap_or_else(|| CARET_STYLES[self.choice(Setting::CaretStyle)].clone());
+        config.theme = overrides.theme.clone().unwrap_or_else(|| THEMES[self.choice(Setting::Theme)].clone());
+        config.countdown = overrides.countdown.unwrap_or(self.choice(Setting::Countdown) == 1);
+        config
+    }
+
+    /// Returns the lines of the screen: a title, a row per setting with the selected one marked
+    /// and its value between arrows, and the key help.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec!["Settings".to_string(), String::new()];
+        for (row, setting) in self.visible_settings().into_iter().enumerate() {
+            let value = &self.options(setting)[self.choice(setting)];
+            lines.push(if row == self.selected {
+                format!("> {:<LABEL_WIDTH$}< {} >", setting_label(setting), value)
+            } else {
+                format!("  {:<LABEL_WIDTH$}  {}", setting_label(setting), value)
+            });
+        }
+        lines.push(String::new());
+        lines.push(KEY_HELP.to_string());
+        lines
+    }
+
+    /// Returns the index of the value chosen for `setting`.
+    fn choice(&self, setting: Setting) -> usize {
+        self.choices[setting as usize]
+    }
+
+    /// Moves the selected setting `step` values along its options, wrapping around.
+    fn change_selected(&mut self, step: isize) {
+        let Some(setting) = self.selected_setting() else { return };
+        let count = self.options(setting).len();
+        if count == 0 {
+            return;
+        }
+        let choice = &mut self.choices[setting as usize];
+        *choice = (*choice as isize + step).rem_euclid(count as isize) as usize;
+    }
+
+    /// Returns whether punctuation and numbers apply: not to quotes, nor to key-row drills.
+    fn word_options_apply(&self) -> bool {
+        self.game_type() != GameType::Quote && self.overrides.key_rows.is_none()
+    }
+
+    /// Returns whether `setting` is shown as a row.
+    fn is_asked(&self, setting: Setting) -> bool {
+        let overrides = &self.overrides;
+        let game_type = self.game_type();
+        match setting {
+            Setting::GameType => overrides.implied_game_type().is_none(),
+            Setting::TimeLimit => game_type == GameType::Time && overrides.time_seconds.is_none(),
+            Setting::WordCount => game_type == GameType::Words && overrides.word_count.is_none(),
+            // A quote picked with --quote has its own length.
+            Setting::QuoteLength => game_type == GameType::Quote && overrides.quote_number.is_none(),
+            Setting::Difficulty => overrides.difficulty.is_none(),
+            Setting::Punctuation => self.word_options_apply() && overrides.punctuation.is_none(),
+            Setting::Numbers => self.word_options_apply() && overrides.numbers.is_none(),
+            Setting::Language => {
+                game_type != GameType::Quote && self.languages.len() > 1 && overrides.language.is_none()
+            }
+            Setting::CaretStyle => overrides.caret_style.is_none(),
+            Setting::Theme => overrides.theme.is_none(),
+            Setting::Countdown => overrides.countdown.is_none(),
+        }
+    }
+
+    /// Returns the values `setting` can take, as shown on screen.
+    fn options(&self, setting: Setting) -> Vec<String> {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        match setting {
+            Setting::GameType => names(&["Time", "Words", "Quote", "Zen"]),
+            Setting::TimeLimit => TIME_LIMIT_CHOICES.iter().map(|seconds| format!("{}s", seconds)).collect(),
+            Setting::WordCount => WORD_COUNT_CHOICES.iter().map(|count| count.to_string()).collect(),
+            Setting::QuoteLength => names(&["Any", "Short (up to 20 words)", "Medium (21-60 words)", "Long (over 60 words)"]),
+            Setting::Difficulty => names(&["Easy", "Medium", "Hard"]),
+            Setting::Punctuation | Setting::Numbers | Setting::Countdown => names(&["Off", "On"]),
+            Setting::Language => self.languages.clone(),
+            Setting::CaretStyle => names(&["Highlight", "Block", "Underline", "Bar"]),
+            Setting::Theme => names(&["Default", "Solarized", "Mono", "High contrast"]),
+        }
+    }
+}
+
+/// Returns the name `setting` is shown under.
+fn setting_label(setting: Setting) -> &'static str {
+    match setting {
+        Setting::GameType => "Game type",
+        Setting::TimeLimit => "Time limit",
+        Setting::WordCount => "Words",
+        Setting::QuoteLength => "Quote length",
+        Setting::Difficulty => "Difficulty",
+        Setting::Punctuation => "Punctuation",
+        Setting::Numbers => "Numbers",
+        Setting::Language => "Language",
+        Setting::CaretStyle => "Caret",
+        Setting::Theme => "Theme",
+        Setting::Countdown => "Countdown",
+    }
+}
+
+/// Asks for the settings `overrides` leaves out on the settings screen, pre-selecting the saved
+/// configuration, and completes and saves the configuration chosen (see `config::finish_game_config`).
+///
+/// Returns `None` if the player left with Esc or Ctrl+C. The terminal is in raw mode only while
+/// the screen is shown.
+pub fn choose_game_config(overrides: &ConfigOverrides) -> Result<Option<GameConfig>> {
+    let previous = config::load_saved_config().unwrap_or_default();
+    let screen = SettingsScreen::new(&previous, overrides, data_loader::available_languages())?;
+    let guard = TerminalGuard::enable()?;
+    let chosen = run_settings_screen(&mut stdout(), &mut TerminalEvents, screen);
+    guard.restore()?;
+    Ok(chosen?.map(|config| config::finish_game_config(config, overrides, &previous)))
+}
+
+/// Shows `screen` on `stdout` and handles `events` until the player starts or leaves, then
+/// clears the screen. Returns the configuration chosen, or `None` if the player left.
+pub fn run_settings_screen(
+    stdout: &mut impl Write,
+    events: &mut impl EventSource,
+    mut screen: SettingsScreen,
+) -> Result<Option<GameConfig>> {
+    let (mut term_cols, mut term_rows) = events.size().context("Failed to get terminal size")?;
+    let chosen = loop {
+        draw_settings_screen(stdout, &screen, term_cols, term_rows)?;
+        match events.read().context("Failed to read event")? {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                if is_quit_key(&key_event) {
+                    break None;
+                }
+                match screen.handle_key(key_event.code) {
+                    SettingsAction::Continue => {}
+                    SettingsAction::Start => break Some(screen.to_config()),
+                    SettingsAction::Quit => break None,
+                }
+            }
+            Event::Resize(cols, rows) => (term_cols, term_rows) = (cols, rows),
+            _ => {}
+        }
+    };
+    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))
+        .context("Failed to clear the settings screen")?;
+    Ok(chosen)
+}
+
+/// Draws `screen` in the middle of the terminal, with the setting rows lined up as one block.
+fn draw_settings_screen(stdout: &mut impl Write, screen: &SettingsScreen, terminal_width: u16, terminal_height: u16) -> Result<()> {
+    let mut lines = screen.lines();
+    let rows_end = lines.len() - 2;
+    pad_to_common_width(&mut lines[2..rows_end]);
+    let start_row = terminal_height.saturating_sub(lines.len() as u16) / 2;
+    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::Hide)?;
+    for (i, line) in lines.iter().enumerate() {
+        execute!(stdout, cursor::MoveTo(centered_column(line, terminal_width), start_row + i as u16), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::ScriptedEvents;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+    use std::time::Duration;
+
+    /// Returns a screen over the default configuration with two languages to choose from.
+    fn screen(overrides: ConfigOverrides) -> SettingsScreen {
+        let languages = vec!["english".to_string(), "german".to_string()];
+        SettingsScreen::new(&GameConfig::new(), &overrides, languages).unwrap()
+    }
+
+    #[test]
+    fn rows_follow_the_game_type() {
+        let mut screen = screen(ConfigOverrides::default());
+        assert_eq!(screen.visible_settings(), [
+            Setting::GameType, Setting::TimeLimit, Setting::Difficulty, Setting::Punctuation, Setting::Numbers,
+            Setting::Language, Setting::CaretStyle, Setting::Theme, Setting::Countdown,
+        ]);
+        screen.handle_key(KeyCode::Right);
+        screen.handle_key(KeyCode::Right);
+        assert_eq!(screen.game_type(), GameType::Quote);
+        assert_eq!(screen.visible_settings(), [
+            Setting::GameType, Setting::QuoteLength, Setting::Difficulty, Setting::CaretStyle, Setting::Theme,
+            Setting::Countdown,
+        ]);
+    }
+
+    #[test]
+    fn keys_move_between_rows_and_change_values_with_wrap_around() {
+        let mut screen = screen(ConfigOverrides::default());
+        assert_eq!(screen.handle_key(KeyCode::Up), SettingsAction::Continue);
+        assert_eq!(screen.selected_setting(), Some(Setting::Countdown));
+        screen.handle_key(KeyCode::Right);
+        screen.handle_key(KeyCode::Down);
+        assert_eq!(screen.selected_setting(), Some(Setting::GameType));
+        // From Time, Left wraps round to Zen.
+        screen.handle_key(KeyCode::Left);
+        screen.handle_key(KeyCode::Tab);
+        screen.handle_key(KeyCode::Right);
+        assert_eq!(screen.handle_key(KeyCode::Enter), SettingsAction::Start);
+        let config = screen.to_config();
+        assert_eq!((config.game_type, config.difficulty, config.countdown), (GameType::Zen, Difficulty::Hard, true));
+        assert_eq!((config.time_seconds, config.word_count), (None, None));
+        assert_eq!(screen.handle_key(KeyCode::Esc), SettingsAction::Quit);
+    }
+
+    #[test]
+    fn command_line_settings_are_not_asked_for() {
+        let overrides = ConfigOverrides {
+            word_count: Some(15), difficulty: Some(Difficulty::Easy), theme: Some(ThemeName::Mono), ..ConfigOverrides::default()
+        };
+        let mut screen = screen(overrides);
+        assert_eq!(screen.visible_settings(), [
+            Setting::Punctuation, Setting::Numbers, Setting::Language, Setting::CaretStyle, Setting::Countdown,
+        ]);
+        screen.handle_key(KeyCode::Right);
+        let config = screen.to_config();
+        assert_eq!((config.game_type, config.word_count, config.difficulty), (GameType::Words, Some(15), Difficulty::Easy));
+        assert_eq!((config.theme, config.punctuation, config.numbers), (ThemeName::Mono, true, false));
+
+        let custom = ConfigOverrides { game_type: Some(GameType::Code), ..ConfigOverrides::default() };
+        assert!(SettingsScreen::new(&GameConfig::new(), &custom, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn the_selected_row_is_marked() {
+        let mut screen = screen(ConfigOverrides::default());
+        screen.handle_key(KeyCode::Down);
+        let lines = screen.lines();
+        assert_eq!(lines[2], "  Game type      Time");
+        assert_eq!(lines[3], "> Time limit   < 30s >");
+        assert_eq!(lines.last().map(String::as_str), Some(KEY_HELP));
+    }
+
+    #[test]
+    fn the_screen_runs_on_scripted_keys() {
+        let key = |code| (Duration::ZERO, Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+        let mut events = ScriptedEvents::new((80, 24), [key(KeyCode::Down), key(KeyCode::Right), key(KeyCode::Enter)]);
+        let chosen = run_settings_screen(&mut Vec::new(), &mut events, screen(ConfigOverrides::default())).unwrap();
+        assert_eq!(chosen.map(|config| config.time_seconds), Some(Some(60)));
+
+        let ctrl_c = (Duration::ZERO, Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        let mut events = ScriptedEvents::new((80, 24), [key(KeyCode::Down), ctrl_c]);
+        assert!(run_settings_screen(&mut Vec::new(), &mut events, screen(ConfigOverrides::default())).unwrap().is_none());
+    }
+}
@@ -0,0 +1,102 @@
+//! # Loading Spinner Module
+//!
+//! This module shows a small spinner on stderr while data is loading, so a big custom word list
+//! or quotes file does not look like a hang at startup. The spinner only appears once loading has
+//! taken longer than `SPINNER_DELAY`, so ordinary lists load without any flash, and it is never
+//! drawn when stderr is not a terminal.
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// How long loading may take before the spinner appears.
+const SPINNER_DELAY: Duration = Duration::from_millis(250);
+/// How long each frame of the spinner is shown.
+const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+/// The frames the spinner cycles through.
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Runs `load` and returns its result, showing a spinner with `message` on stderr if it takes
+/// longer than `SPINNER_DELAY`. Without a terminal on stderr, `load` simply runs.
+pub fn with_spinner<T>(message: &str, load: impl FnOnce() -> T) -> T {
+    if !io::stderr().is_terminal() {
+        return load();
+    }
+    spin_while(io::stderr(), message, SPINNER_DELAY, load)
+}
+
+/// Runs `load` while a spinner thread writes to `out` once `delay` has passed, and erases the
+/// spinner again before returning.
+fn spin_while<T, W: Write + Send + 'static>(mut out: W, message: &str, delay: Duration, load: impl FnOnce() -> T) -> T {
+    let (done, finished) = mpsc::channel::<()>();
+    let line = message.to_string();
+    let spinner = thread::spawn(move || {
+        // Dropping `done` disconnects the channel, which ends the wait just like a message would.
+        if finished.recv_timeout(delay) != Err(RecvTimeoutError::Timeout) {
+            return;
+        }
+        for frame in SPINNER_FRAMES.iter().cycle() {
+            write!(out, "\r{} {}", frame, line).ok();
+            out.flush().ok();
+            if finished.recv_timeout(SPINNER_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+                break;
+            }
+        }
+        // Blank the spinner line, so whatever is printed next starts on a clean line.
+        write!(out, "\r{}\r", " ".repeat(line.chars().count() + 2)).ok();
+        out.flush().ok();
+    });
+    let result = load();
+    drop(done);
+    spinner.join().ok();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer whose output the test can read back after the spinner thread is done.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedOutput {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn quick_loads_show_no_spinner() {
+        let output = SharedOutput::default();
+        let result = spin_while(output.clone(), "Loading words...", Duration::from_secs(5), || 42);
+        assert_eq!(result, 42);
+        assert_eq!(output.text(), "");
+    }
+
+    #[test]
+    fn slow_loads_show_the_spinner_then_erase_it() {
+        let output = SharedOutput::default();
+        let result = spin_while(output.clone(), "Loading words...", Duration::from_millis(10), || {
+            thread::sleep(Duration::from_millis(200));
+            "done"
+        });
+        assert_eq!(result, "done");
+        let text = output.text();
+        assert!(text.starts_with("\r| Loading words..."), "{text:?}");
+        assert!(text.ends_with(&format!("\r{}\r", " ".repeat(18))), "{text:?}");
+    }
+}
@@ -0,0 +1,279 @@
+//! # Results History Module
+//!
+//! This module keeps a history of finished runs so players can track their progress.
+//! Each run is stored as one JSON object per line in `history.jsonl`, next to the saved
+//! configuration in the platform config directory. The history is also used to tell
+//! whether a run is a new personal best for its game type and difficulty.
+
+use crate::config::{app_config_dir, Difficulty, GameConfig, GameType};
+use crate::ui::most_missed_chars;
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the results history, inside the app config directory.
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+/// Fewest attempts at a character, across the whole history, before it can count as a weak key.
+const MIN_WEAK_KEY_ATTEMPTS: usize = 20;
+/// Most characters a weak-key drill focuses on.
+const WEAK_KEY_COUNT: usize = 5;
+
+/// The outcome of a single finished run.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RunResult {
+    /// When the run finished, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The game type that was played.
+    pub game_type: GameType,
+    /// The difficulty that was played.
+    pub difficulty: Difficulty,
+    /// Gross words per minute.
+    pub gross_wpm: f64,
+    /// Net words per minute, after the error penalty.
+    pub net_wpm: f64,
+    /// Accuracy as a percentage from 0 to 100.
+    pub accuracy: f64,
+    /// How long the run took, in seconds.
+    pub duration: f64,
+    /// `(attempts, errors)` for each character the run asked for. Empty for runs saved before
+    /// this was recorded.
+    #[serde(default)]
+    pub char_stats: BTreeMap<char, (usize, usize)>,
+}
+
+impl RunResult {
+    /// Creates a `RunResult` for a run played with `config`, timestamped now.
+    pub fn new(config: &GameConfig, gross_wpm: f64, net_wpm: f64, accuracy: f64, duration: f64) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs());
+        RunResult {
+            timestamp,
+            game_type: config.game_type.clone(),
+            difficulty: config.difficulty.clone(),
+            gross_wpm,
+            net_wpm,
+            accuracy,
+            duration,
+            char_stats: BTreeMap::new(),
+        }
+    }
+}
+
+/// How earlier runs of the same mode and difficulty went, to compare a new run against.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PastRuns {
+    /// Best net WPM, or `None` if the combination had never been played.
+    pub best_wpm: Option<f64>,
+    /// Average net WPM, or `None` if the combination had never been played.
+    pub average_wpm: Option<f64>,
+}
+
+/// Returns the path of the results history file, if the platform has a config dir.
+pub fn history_file_path() -> Option<PathBuf> {
+    app_config_dir().map(|dir| dir.join(HISTORY_FILE_NAME))
+}
+
+/// Appends `result` as a new line to the history file at `path`.
+///
+/// The file and its directory are created if they do not exist yet.
+pub fn append_result(path: &Path, result: &RunResult) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create history directory {}", parent.display()))?;
+    }
+    let line = serde_json::to_string(result).context("Failed to serialize run result")?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open history file {}", path.display()))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write history file {}", path.display()))?;
+    Ok(())
+}
+
+/// Loads every run stored in the history file at `path`.
+///
+/// A missing file is simply an empty history. Lines that cannot be parsed are logged
+/// and skipped, so one corrupt entry does not lose the rest of the history.
+pub fn load_history(path: &Path) -> Vec<RunResult> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| match serde_json::from_str(line) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                warn!("Skipping unreadable line {} of {}: {}", i + 1, path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the best net WPM in `history` for the given game type and difficulty,
+/// or `None` if that combination has never been played.
+pub fn personal_best(history: &[RunResult], game_type: &GameType, difficulty: &Difficulty) -> Option<f64> {
+    history
+        .iter()
+        .filter(|run| &run.game_type == game_type && &run.difficulty == difficulty)
+        .map(|run| run.net_wpm)
+        .fold(None, |best, wpm| Some(best.map_or(wpm, |best: f64| best.max(wpm))))
+}
+
+/// Returns the average net WPM in `history` for the given game type and difficulty,
+/// or `None` if that combination has never been played.
+pub fn average_wpm(history: &[RunResult], game_type: &GameType, difficulty: &Difficulty) -> Option<f64> {
+    let (count, total) = history
+        .iter()
+        .filter(|run| &run.game_type == game_type && &run.difficulty == difficulty)
+        .fold((0, 0.0), |(count, total), run| (count + 1, total + run.net_wpm));
+    (count > 0).then(|| total / count as f64)
+}
+
+/// Summarizes the runs in `history` of the given game type and difficulty.
+pub fn past_runs(history: &[RunResult], game_type: &GameType, difficulty: &Difficulty) -> PastRuns {
+    PastRuns {
+        best_wpm: personal_best(history, game_type, difficulty),
+        average_wpm: average_wpm(history, game_type, difficulty),
+    }
+}
+
+/// Adds up the per-character `(attempts, errors)` of every run in `history`.
+pub fn char_totals(history: &[RunResult]) -> HashMap<char, (usize, usize)> {
+    let mut totals: HashMap<char, (usize, usize)> = HashMap::new();
+    for (&c, &(attempts, errors)) in history.iter().flat_map(|run| &run.char_stats) {
+        let total = totals.entry(c).or_default();
+        total.0 += attempts;
+        total.1 += errors;
+    }
+    totals
+}
+
+/// Returns the characters missed most across `history`, worst first, for a weak-key drill.
+///
+/// Only characters typed at least `MIN_WEAK_KEY_ATTEMPTS` times count, so a single slip does
+/// not make a weak key, and word separators are left out since words never contain them.
+/// Returns nothing until the history has enough data.
+pub fn weak_chars(history: &[RunResult]) -> Vec<char> {
+    let totals: HashMap<char, (usize, usize)> = char_totals(history)
+        .into_iter()
+        .filter(|&(c, (attempts, _))| attempts >= MIN_WEAK_KEY_ATTEMPTS && !c.is_whitespace())
+        .collect();
+    most_missed_chars(&totals, WEAK_KEY_COUNT).into_iter().map(|(c, _, _)| c).collect()
+}
+
+/// Saves `result` to the history file at `path` and returns the earlier runs it is compared against.
+///
+/// The returned summary covers the runs of the same mode and difficulty *before* this run, so
+/// it is empty if this is the first run of its kind.
+pub fn record_result(path: &Path, result: &RunResult) -> Result<PastRuns> {
+    let past = past_runs(&load_history(path), &result.game_type, &result.difficulty);
+    append_result(path, result)?;
+    Ok(past)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a path in the system temp dir that is unique to this test.
+    fn temp_history_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("monk_minal_rust_test_{}_{}", std::process::id(), name))
+            .join(HISTORY_FILE_NAME);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Builds a result with the given mode, difficulty and net WPM.
+    fn run(game_type: GameType, difficulty: Difficulty, net_wpm: f64) -> RunResult {
+        RunResult {
+            timestamp: 0, game_type, difficulty, gross_wpm: net_wpm, net_wpm, accuracy: 100.0, duration: 30.0,
+            char_stats: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn history_round_trips_through_the_file() {
+        let path = temp_history_path("round_trip");
+        assert!(load_history(&path).is_empty());
+        let first = run(GameType::Time, Difficulty::Easy, 50.0);
+        let second = run(GameType::Words, Difficulty::Hard, 70.0);
+        append_result(&path, &first).unwrap();
+        append_result(&path, &second).unwrap();
+        assert_eq!(load_history(&path), vec![first, second]);
+    }
+
+    #[test]
+    fn corrupt_history_lines_are_skipped() {
+        let path = temp_history_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "not json\n{\"timestamp\": 1}\n").unwrap();
+        let good = run(GameType::Quote, Difficulty::Medium, 40.0);
+        append_result(&path, &good).unwrap();
+        assert_eq!(load_history(&path), vec![good]);
+    }
+
+    #[test]
+    fn personal_best_only_considers_matching_runs() {
+        let history = vec![
+            run(GameType::Time, Difficulty::Easy, 50.0),
+            run(GameType::Time, Difficulty::Easy, 65.0),
+            run(GameType::Time, Difficulty::Hard, 90.0),
+            run(GameType::Words, Difficulty::Easy, 80.0),
+        ];
+        assert_eq!(personal_best(&history, &GameType::Time, &Difficulty::Easy), Some(65.0));
+        assert_eq!(personal_best(&history, &GameType::Quote, &Difficulty::Easy), None);
+        assert_eq!(personal_best(&[], &GameType::Time, &Difficulty::Easy), None);
+    }
+
+    #[test]
+    fn average_only_considers_matching_runs() {
+        let history = vec![
+            run(GameType::Time, Difficulty::Easy, 50.0),
+            run(GameType::Time, Difficulty::Easy, 65.0),
+            run(GameType::Time, Difficulty::Easy, 74.0),
+            run(GameType::Time, Difficulty::Hard, 90.0),
+        ];
+        assert_eq!(average_wpm(&history, &GameType::Time, &Difficulty::Easy), Some(63.0));
+        assert_eq!(average_wpm(&history, &GameType::Time, &Difficulty::Hard), Some(90.0));
+        assert_eq!(past_runs(&history, &GameType::Words, &Difficulty::Easy), PastRuns::default());
+        assert_eq!(past_runs(&history, &GameType::Time, &Difficulty::Easy).best_wpm, Some(74.0));
+    }
+
+    #[test]
+    fn recording_compares_against_the_runs_before() {
+        let path = temp_history_path("record");
+        let first = record_result(&path, &run(GameType::Words, Difficulty::Medium, 60.0)).unwrap();
+        assert_eq!(first, PastRuns::default());
+        record_result(&path, &run(GameType::Words, Difficulty::Medium, 70.0)).unwrap();
+        let third = record_result(&path, &run(GameType::Words, Difficulty::Medium, 10.0)).unwrap();
+        assert_eq!(third, PastRuns { best_wpm: Some(70.0), average_wpm: Some(65.0) });
+    }
+
+    #[test]
+    fn weak_chars_need_enough_attempts_across_runs() {
+        let with_stats = |stats: &[(char, usize, usize)]| RunResult {
+            char_stats: stats.iter().map(|&(c, attempts, errors)| (c, (attempts, errors))).collect(),
+            ..run(GameType::Time, Difficulty::Hard, 50.0)
+        };
+        assert!(weak_chars(&[]).is_empty());
+        let history = vec![
+            with_stats(&[('q', 12, 6), ('e', 100, 1), (' ', 50, 40), ('x', 3, 3)]),
+            with_stats(&[('q', 12, 0), ('t', 30, 6)]),
+        ];
+        assert_eq!(char_totals(&history)[&'q'], (24, 6));
+        // 'x' is missed every time but typed too rarely; space is no key a word can drill.
+        assert_eq!(weak_chars(&history), vec!['q', 't', 'e']);
+    }
+}
@@ -0,0 +1,181 @@
+//! # Color Theme Module
+//!
+//! This module defines the color themes used to draw the typing area. A `Theme` holds one
+//! `StateStyle` per character state (correct, incorrect, wrong case, pending, current), and
+//! `Theme::from_name` builds the theme selected in the `GameConfig`. `preview_lines` shows every
+//! theme side by side for `--theme-preview`.
+
+use crate::config::ThemeName;
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+
+/// The sample line drawn in each theme by `--theme-preview`.
+const PREVIEW_TEXT: &str = "The quick brown fox";
+/// Characters of `PREVIEW_TEXT` shown as typed in the preview: the last of them was mistyped,
+/// and the one after it is the current character.
+const PREVIEW_TYPED: usize = 7;
+
+/// How text in one particular state is drawn.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StateStyle {
+    /// Foreground color, or `None` for the terminal default.
+    pub fg: Option<Color>,
+    /// Background color, or `None` for the terminal default.
+    pub bg: Option<Color>,
+    /// Whether the text is bold.
+    pub bold: bool,
+    /// Whether the text is dimmed.
+    pub dimmed: bool,
+    /// Whether the text is underlined.
+    pub underline: bool,
+    /// Whether foreground and background are swapped.
+    pub reversed: bool,
+}
+
+impl StateStyle {
+    /// Applies this style to `text`.
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut styled = text.normal();
+        if let Some(fg) = self.fg { styled = styled.color(fg); }
+        if let Some(bg) = self.bg { styled = styled.on_color(bg); }
+        if self.bold { styled = styled.bold(); }
+        if self.dimmed { styled = styled.dimmed(); }
+        if self.underline { styled = styled.underline(); }
+        if self.reversed { styled = styled.reversed(); }
+        styled
+    }
+}
+
+/// The styles used for each state of the text being typed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Characters the user has typed correctly.
+    pub correct: StateStyle,
+    /// Characters the user has typed incorrectly.
+    pub incorrect: StateStyle,
+    /// Mistyped characters that were the right letter in the wrong case, e.g. "t" for "T".
+    pub wrong_case: StateStyle,
+    /// Characters (and words) that have not been typed yet.
+    pub pending: StateStyle,
+    /// The character the user should type next.
+    pub current: StateStyle,
+}
+
+impl Theme {
+    /// Returns the theme called `name`.
+    pub fn from_name(name: &ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Theme {
+                correct: StateStyle { fg: Some(Color::Green), ..StateStyle::default() },
+                incorrect: StateStyle { bg: Some(Color::Red), ..StateStyle::default() },
+                wrong_case: StateStyle { bg: Some(Color::Magenta), ..StateStyle::default() },
+                pending: StateStyle { dimmed: true, ..StateStyle::default() },
+                current: StateStyle { fg: Some(Color::Black), bg: Some(Color::Yellow), ..StateStyle::default() },
+            },
+            ThemeName::Solarized => Theme {
+                correct: StateStyle { fg: Some(Color::TrueColor { r: 133, g: 153, b: 0 }), ..StateStyle::default() },
+                incorrect: StateStyle {
+                    fg: Some(Color::TrueColor { r: 253, g: 246, b: 227 }),
+                    bg: Some(Color::TrueColor { r: 220, g: 50, b: 47 }),
+                    ..StateStyle::default()
+                },
+                wrong_case: StateStyle {
+                    fg: Some(Color::TrueColor { r: 253, g: 246, b: 227 }),
+                    bg: Some(Color::TrueColor { r: 211, g: 54, b: 130 }),
+                    ..StateStyle::default()
+                },
+                pending: StateStyle { fg: Some(Color::TrueColor { r: 88, g: 110, b: 117 }), ..StateStyle::default() },
+                current: StateStyle {
+                    fg: Some(Color::TrueColor { r: 0, g: 43, b: 54 }),
+                    bg: Some(Color::TrueColor { r: 181, g: 137, b: 0 }),
+                    ..StateStyle::default()
+                },
+            },
+            // Attributes only, for terminals without color support.
+            ThemeName::Mono => Theme {
+                correct: StateStyle { bold: true, ..StateStyle::default() },
+                incorrect: StateStyle { reversed: true, ..StateStyle::default() },
+                wrong_case: StateStyle { reversed: true, underline: true, ..StateStyle::default() },
+                pending: StateStyle { dimmed: true, ..StateStyle::default() },
+                current: StateStyle { underline: true, ..StateStyle::default() },
+            },
+            ThemeName::HighContrast => Theme {
+                correct: StateStyle { fg: Some(Color::BrightGreen), bold: true, ..StateStyle::default() },
+                incorrect: StateStyle { fg: Some(Color::BrightWhite), bg: Some(Color::BrightRed), bold: true, ..StateStyle::default() },
+                wrong_case: StateStyle { fg: Some(Color::Black), bg: Some(Color::BrightMagenta), bold: true, ..StateStyle::default() },
+                pending: StateStyle { fg: Some(Color::White), ..StateStyle::default() },
+                current: StateStyle { fg: Some(Color::Black), bg: Some(Color::BrightYellow), bold: true, ..StateStyle::default() },
+            },
+        }
+    }
+}
+
+impl Theme {
+    /// Draws `PREVIEW_TEXT` as if partly typed: correct characters, one mistake, the current
+    /// character and the pending rest.
+    fn preview(&self) -> String {
+        let chars: Vec<char> = PREVIEW_TEXT.chars().collect();
+        let text = |range: &[char]| range.iter().collect::<String>();
+        format!("{}{}{}{}",
+            self.correct.paint(&text(&chars[..PREVIEW_TYPED - 1])),
+            self.incorrect.paint(&text(&chars[PREVIEW_TYPED - 1..PREVIEW_TYPED])),
+            self.current.paint(&text(&chars[PREVIEW_TYPED..PREVIEW_TYPED + 1])),
+            self.pending.paint(&text(&chars[PREVIEW_TYPED + 1..])))
+    }
+}
+
+/// Returns one line per theme for `--theme-preview`: its name and, if `color` is on, a sample
+/// line drawn in it. Without color (e.g. under `NO_COLOR`) only the names are listed, since the
+/// samples would all look alike.
+pub fn preview_lines(color: bool) -> Vec<String> {
+    ThemeName::value_variants()
+        .iter()
+        .map(|name| {
+            let label = name.to_possible_value().map_or_else(|| format!("{name:?}"), |value| value.get_name().to_string());
+            if color {
+                format!("{:<15}{}", label, Theme::from_name(name).preview())
+            } else {
+                label
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_theme_styles_each_state_distinctly() {
+        colored::control::set_override(true);
+        for name in ThemeName::value_variants() {
+            let theme = Theme::from_name(name);
+            let styles = [&theme.correct, &theme.incorrect, &theme.wrong_case, &theme.pending, &theme.current];
+            for (i, a) in styles.iter().enumerate() {
+                for b in &styles[i + 1..] {
+                    assert_ne!(a, b, "{name:?} reuses a style");
+                    assert_ne!(a.paint("x").to_string(), b.paint("x").to_string(), "{name:?} renders two states alike");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn preview_shows_every_theme() {
+        colored::control::set_override(true);
+        let lines = preview_lines(true);
+        assert_eq!(lines.len(), ThemeName::value_variants().len());
+        for (line, name) in lines.iter().zip(ThemeName::value_variants()) {
+            assert!(line.contains(&Theme::from_name(name).preview()), "{name:?} is not previewed");
+        }
+        assert_eq!(preview_lines(false), vec!["default", "solarized", "mono", "high-contrast"]);
+    }
+
+    #[test]
+    fn mono_theme_uses_no_colors() {
+        let theme = Theme::from_name(&ThemeName::Mono);
+        for style in [&theme.correct, &theme.incorrect, &theme.wrong_case, &theme.pending, &theme.current] {
+            assert!(style.fg.is_none() && style.bg.is_none());
+        }
+    }
+}
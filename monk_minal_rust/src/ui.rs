@@ -0,0 +1,1459 @@
+//! # Terminal UI Module
+//!
+//! This module draws the typing game: the typing screen with its timer and live stats, the
+//! game over screen, and the notice shown when the terminal is too small. Everything here
+//! reads a `GameState` and the terminal size and only writes to the terminal; the state
+//! itself and the event loop live in the `game` module.
+//!
+//! The layout helpers (`centered_column`, `text_layout`, ...) are plain functions of the
+//! terminal dimensions, so the screen geometry can be tested without a terminal. All of them
+//! measure text in terminal cells (see `visible_width`), never in bytes or `char`s, so word
+//! lists in CJK scripts, whose characters take two cells each, wrap and center like any other.
+
+use crate::config::{CaretStyle, GameConfig, GameType, WpmScheme, DEFAULT_ERROR_PENALTY};
+use crate::game::{calculate_consistency, calculate_kpm, calculate_wpm, words_per_minute, GameState, RunFailure, SessionResult};
+use crate::theme::{StateStyle, Theme};
+use anyhow::Result;
+use crossterm::{
+    cursor, execute,
+    style::{Attribute, ContentStyle, Print},
+    terminal,
+};
+use figlet_rs::FIGfont;
+use std::collections::HashMap;
+use std::io::Write;
+use textwrap::core::display_width;
+
+/// Narrowest terminal, in columns, the typing screen is drawn on.
+pub const MIN_TERMINAL_WIDTH: u16 = 20;
+/// Shortest terminal, in rows, the typing screen is drawn on: up to three header rows,
+/// three lines of text and the footer.
+pub const MIN_TERMINAL_HEIGHT: u16 = 7;
+/// Most lines of words shown in the typing area, however tall the terminal is.
+const MAX_TEXT_LINES: usize = 3;
+/// Number of most-missed characters listed on the results screen.
+const MOST_MISSED_SHOWN: usize = 3;
+/// Number of slowest words listed on the results screen.
+const SLOWEST_WORDS_SHOWN: usize = 3;
+/// Drawn at the end of each line in Code mode, where Enter is the next key to press.
+const LINE_END_MARKER: &str = "↵";
+/// Block characters used to draw the WPM sparkline, from lowest to highest.
+const SPARKLINE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Widest the header's progress bar gets, in columns.
+const PROGRESS_BAR_MAX_WIDTH: usize = 30;
+/// Narrowest a column of text gets with `--columns`; terminals too narrow for that use fewer columns.
+const MIN_TEXT_COLUMN_WIDTH: usize = 30;
+/// Blank columns between two columns of text.
+const TEXT_COLUMN_GAP: usize = 4;
+
+/// Returns the number of terminal columns `line` occupies once printed.
+///
+/// ANSI escape sequences (as produced by `colored`) take up no space, and wide characters
+/// count as two columns, so styled text is measured by what the user actually sees.
+pub fn visible_width(line: &str) -> u16 {
+    display_width(line).min(u16::MAX as usize) as u16
+}
+
+/// Returns the starting column that centers `line` within `terminal_width`.
+pub fn centered_column(line: &str, terminal_width: u16) -> u16 {
+    terminal_width.saturating_sub(visible_width(line)) / 2
+}
+
+/// Returns the screen position, as `(column, row)`, of the caret at `(line_index, column)` in
+/// `lines`, where each line is centered within `terminal_width` and the first is drawn at `start_row`.
+pub fn caret_screen_position(lines: &[String], caret: (usize, usize), start_row: u16, terminal_width: u16) -> (u16, u16) {
+    let (line_idx, column) = caret;
+    let padding = lines.get(line_idx).map_or(0, |line| centered_column(line, terminal_width));
+    (padding + column as u16, start_row + line_idx as u16)
+}
+
+/// Pads each of `lines` with trailing spaces to the visible width of the widest one.
+///
+/// Lines are centered one by one, so a block whose rows differ in width (a figlet banner,
+/// or labels padded to line up) would otherwise come out ragged. Padded to a common width,
+/// every row gets the same starting column and the block keeps its shape.
+pub fn pad_to_common_width(lines: &mut [String]) {
+    let width = lines.iter().map(|line| visible_width(line)).max().unwrap_or(0);
+    for line in lines.iter_mut() {
+        let padding = width - visible_width(line);
+        line.extend(std::iter::repeat_n(' ', padding as usize));
+    }
+}
+
+/// Styles the character the user should type next according to `caret_style`.
+///
+/// `CaretStyle::Highlight` uses the theme's `current` style. With `CaretStyle::Bar` the
+/// character is left unstyled, since the terminal cursor marks it.
+fn style_caret(current_char: &str, caret_style: &CaretStyle, theme: &Theme) -> String {
+    match caret_style {
+        CaretStyle::Highlight => theme.current.paint(current_char).to_string(),
+        CaretStyle::Block => with_attribute(current_char, Attribute::Reverse),
+        CaretStyle::Underline => with_attribute(current_char, Attribute::Underlined),
+        CaretStyle::Bar => current_char.to_string(),
+    }
+}
+
+/// Renders `text` with a single crossterm text attribute applied.
+fn with_attribute(text: &str, attribute: Attribute) -> String {
+    ContentStyle { attributes: attribute.into(), ..ContentStyle::new() }.apply(text).to_string()
+}
+
+/// Returns whether ANSI styling is enabled, i.e. not turned off via `NO_COLOR` or a non-TTY stdout.
+fn colors_enabled() -> bool {
+    colored::control::SHOULD_COLORIZE.should_colorize()
+}
+
+/// Splits words with the given display widths into lines at most `width` columns wide,
+/// with a single space between neighbouring words.
+///
+/// Returns the range `start..end` of word indices on each line. Lines hold at most
+/// `max_words_per_line` words, and a word wider than `width` gets a line of its own.
+pub fn layout_word_lines(word_widths: &[usize], width: usize, max_words_per_line: usize) -> Vec<(usize, usize)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut line_width = 0;
+    for (i, &word_width) in word_widths.iter().enumerate() {
+        let fits = line_width + 1 + word_width <= width && i - start < max_words_per_line.max(1);
+        if i > start && !fits {
+            lines.push((start, i));
+            start = i;
+        }
+        line_width = if i == start { word_width } else { line_width + 1 + word_width };
+    }
+    if start < word_widths.len() {
+        lines.push((start, word_widths.len()));
+    }
+    lines
+}
+
+/// Returns the range `first..end` of the `line_count` laid out lines shown in the typing area.
+///
+/// The current line stays on the second of the `max_lines` rows, with the last completed line
+/// above it, so finished lines scroll up one at a time instead of the text jumping. Only the
+/// first line of the text is shown on the top row.
+pub fn visible_line_range(line_count: usize, current_line: usize, max_lines: usize) -> (usize, usize) {
+    let current_line = current_line.min(line_count.saturating_sub(1));
+    let first = if max_lines > 1 { current_line.saturating_sub(1) } else { current_line };
+    (first, (first + max_lines).min(line_count))
+}
+
+/// Returns the range `first..end` of the `line_count` laid out lines shown with the full text view.
+///
+/// The whole text is shown when it fits in `max_lines`. Otherwise it scrolls so the current line
+/// sits a third of the way down, with finished lines above it for context, until the end of the
+/// text reaches the bottom row.
+pub fn full_text_line_range(line_count: usize, current_line: usize, max_lines: usize) -> (usize, usize) {
+    let first = current_line.saturating_sub(max_lines / 3).min(line_count.saturating_sub(max_lines));
+    (first, (first + max_lines).min(line_count))
+}
+
+/// Builds the lines of words shown in the typing area, at most `width` columns wide.
+///
+/// All words are laid out into lines first (see `layout_word_lines`), so a word keeps its line
+/// while the text scrolls; then at most `max_lines` of them are shown (see `visible_line_range`).
+/// With `GameConfig::window_words` set, the lines shown hold no more than that many words.
+/// With the full text view (see `GameConfig::shows_full_text`), as much of the text as fits in
+/// `max_lines` is shown instead (see `full_text_line_range`), and finished words are drawn as correct.
+/// Returns them with the caret position as `(line_index, column)`, if the current character is
+/// visible. With `use_color` off the lines are plain text: the current character (or the
+/// pending errors) is wrapped in brackets instead of being styled.
+pub fn render_typing_lines(game_state: &GameState, theme: &Theme, use_color: bool, width: usize, max_lines: usize) -> (Vec<String>, Option<(usize, usize)>) {
+    let words = &game_state.words_to_type;
+    let current = game_state.current_word_index;
+    let current_word = words.get(current).map(|word| render_current_word(game_state, word, theme, use_color));
+    // The current word is measured as drawn, so pending errors or brackets push it along too.
+    let word_widths: Vec<usize> = words.iter().enumerate().map(|(i, word)| match &current_word {
+        Some((rendered, _)) if i == current => display_width(rendered),
+        _ => display_width(word),
+    }).collect();
+    let full_text = game_state.config.shows_full_text();
+    let max_words_per_line = match game_state.config.window_words {
+        Some(count) if !full_text => (count as usize).div_ceil(max_lines.max(1)),
+        _ => usize::MAX,
+    };
+    let lines = layout_word_lines(&word_widths, width, max_words_per_line);
+    let current_line = lines.iter().position(|&(_, end)| current < end).unwrap_or(lines.len());
+    let (first, end) = if full_text {
+        full_text_line_range(lines.len(), current_line, max_lines)
+    } else {
+        visible_line_range(lines.len(), current_line, max_lines)
+    };
+    let mut caret_position = None;
+    let rendered = lines[first..end].iter().enumerate().map(|(row, &(start, end))| {
+        let mut parts: Vec<String> = Vec::new();
+        for (i, word) in words.iter().enumerate().take(end).skip(start) {
+            match &current_word {
+                Some((rendered, caret_in_word)) if i == current => {
+                    let word_start = display_width(&parts.concat());
+                    caret_position = caret_in_word.map(|column| (row, word_start + column));
+                    parts.push(rendered.clone());
+                }
+                _ if full_text && i < current => parts.push(paint(&theme.correct, word, use_color)),
+                _ => parts.push(paint(&theme.pending, word, use_color)),
+            }
+            parts.push(" ".to_string());
+        }
+        parts.pop();
+        parts.concat()
+    }).collect();
+    (rendered, caret_position)
+}
+
+/// Builds the lines shown in the typing area in Code mode, one per line of the code.
+///
+/// Shows at most `max_lines` lines, keeping the current one in the upper third where possible.
+/// Returns them with the caret position as `(line_index, column)`. At the end of a line the
+/// caret sits on `LINE_END_MARKER`, which stands for the Enter that is due.
+pub fn render_code_lines(game_state: &GameState, theme: &Theme, use_color: bool, max_lines: usize) -> (Vec<String>, Option<(usize, usize)>) {
+    let lines = &game_state.words_to_type;
+    let current = game_state.current_word_index;
+    let start = current.saturating_sub(max_lines / 3).min(lines.len().saturating_sub(max_lines));
+    let end = (start + max_lines).min(lines.len());
+    let mut caret_position = None;
+    let rendered = (start..end).map(|i| {
+        if i != current {
+            return paint(&theme.pending, &lines[i], use_color);
+        }
+        let (mut line, caret_in_line) = render_current_word(game_state, &lines[i], theme, use_color);
+        let column = match caret_in_line {
+            Some(column) => column,
+            None => {
+                let (marker, shift) = render_caret_char(game_state, LINE_END_MARKER, theme, use_color);
+                let column = display_width(&line) + shift;
+                line.push_str(&marker);
+                column
+            }
+        };
+        caret_position = Some((i - start, column));
+        line
+    }).collect();
+    (rendered, caret_position)
+}
+
+/// Applies `style` to `text`, or leaves it plain when `use_color` is off.
+fn paint(style: &StateStyle, text: &str, use_color: bool) -> String {
+    if use_color { style.paint(text).to_string() } else { text.to_string() }
+}
+
+/// Renders the word being typed: the correct input, any errors, the current character and the rest.
+///
+/// Returns the rendered word and the column of the current character within it, or `None`
+/// once the whole word has been typed.
+fn render_current_word(game_state: &GameState, word: &str, theme: &Theme, use_color: bool) -> (String, Option<usize>) {
+    let mut parts: Vec<String> = Vec::new();
+    // Index by char, not byte, so multibyte words never get sliced mid-codepoint.
+    let target_chars: Vec<char> = word.chars().collect();
+    if !game_state.user_input.is_empty() { parts.push(render_typed_input(game_state, theme, use_color)); }
+    // Blind mode draws mistakes exactly like correct input, so they only show up in the results.
+    let blind = game_state.config.blind;
+    if !game_state.errors.is_empty() {
+        let errors = if blind {
+            paint(&theme.correct, &game_state.errors, use_color)
+        } else if use_color {
+            render_errors(&game_state.errors, &target_chars[game_state.current_char_index.min(target_chars.len())..], theme)
+        } else {
+            format!("[{}]", game_state.errors)
+        };
+        parts.push(errors);
+    }
+    let mut caret_offset = None;
+    if let Some(current_char) = target_chars.get(game_state.current_char_index) {
+        let (marked, shift) = render_caret_char(game_state, &current_char.to_string(), theme, use_color);
+        caret_offset = Some(display_width(&parts.concat()) + shift);
+        parts.push(marked);
+        let remaining: String = target_chars[(game_state.current_char_index + 1)..].iter().collect();
+        if !remaining.is_empty() {
+            parts.push(paint(&theme.pending, &remaining, use_color));
+        }
+    }
+    (parts.concat(), caret_offset)
+}
+
+/// Returns whether `typed`, typed where `expected` was due, is the right letter in the wrong
+/// case, e.g. "t" for "T". Past the end of the word nothing is expected, so it never is.
+pub fn is_case_error(typed: char, expected: Option<char>) -> bool {
+    expected.is_some_and(|expected| typed != expected && typed.to_lowercase().eq(expected.to_lowercase()))
+}
+
+/// Paints pending `errors`, each against the character of the word it was typed in place of
+/// (`expected` starts at the first error), as a wrong letter or, if only its case is wrong, as such.
+fn render_errors(errors: &str, expected: &[char], theme: &Theme) -> String {
+    errors.chars().enumerate().map(|(i, c)| {
+        let style = if is_case_error(c, expected.get(i).copied()) { &theme.wrong_case } else { &theme.incorrect };
+        style.paint(&c.to_string()).to_string()
+    }).collect()
+}
+
+/// Renders the input typed so far for the current word.
+///
+/// Characters mistyped with `GameConfig::no_backspace` on (see `GameState::missed_char_indices`)
+/// are drawn as errors, or in brackets without color, except in blind mode.
+fn render_typed_input(game_state: &GameState, theme: &Theme, use_color: bool) -> String {
+    let missed = &game_state.missed_char_indices;
+    if missed.is_empty() || game_state.config.blind {
+        return paint(&theme.correct, &game_state.user_input, use_color);
+    }
+    game_state.user_input.chars().enumerate().map(|(i, c)| {
+        let c = c.to_string();
+        if !missed.contains(&i) {
+            paint(&theme.correct, &c, use_color)
+        } else if use_color {
+            paint(&theme.incorrect, &c, use_color)
+        } else {
+            format!("[{}]", c)
+        }
+    }).collect()
+}
+
+/// Marks `current_char` as the next character to type.
+///
+/// Returns the rendered character and how many columns it is shifted to the right by its
+/// marking. Behind pending errors it is drawn as pending text, since the errors must go first.
+/// Without color it is wrapped in brackets instead of being styled.
+fn render_caret_char(game_state: &GameState, current_char: &str, theme: &Theme, use_color: bool) -> (String, usize) {
+    if !game_state.errors.is_empty() && !game_state.config.blind {
+        (paint(&theme.pending, current_char, use_color), 0)
+    } else if use_color {
+        (style_caret(current_char, &game_state.config.caret_style, theme), 0)
+    } else {
+        (format!("[{}]", current_char), 1)
+    }
+}
+
+/// Returns whether the terminal is too small to draw the typing screen on.
+pub fn terminal_is_too_small(terminal_width: u16, terminal_height: u16) -> bool {
+    terminal_width < MIN_TERMINAL_WIDTH || terminal_height < MIN_TERMINAL_HEIGHT
+}
+
+/// Returns the width the words are wrapped to: the terminal width minus a small margin.
+pub fn text_wrap_width(terminal_width: u16) -> usize {
+    (terminal_width.saturating_sub(4)).max(10) as usize
+}
+
+/// Returns how many of the `requested` text columns fit in `wrap_width`: the most, up to
+/// `requested`, that are each at least `MIN_TEXT_COLUMN_WIDTH` wide. Always at least one.
+pub fn fitting_columns(requested: usize, wrap_width: usize) -> usize {
+    (2..=requested).rev().find(|&columns| text_column_width(wrap_width, columns) >= MIN_TEXT_COLUMN_WIDTH).unwrap_or(1)
+}
+
+/// Returns the width the words are wrapped to in each of `columns` columns sharing `wrap_width`,
+/// with `TEXT_COLUMN_GAP` between them.
+pub fn text_column_width(wrap_width: usize, columns: usize) -> usize {
+    wrap_width.saturating_sub(TEXT_COLUMN_GAP * columns.saturating_sub(1)) / columns.max(1)
+}
+
+/// Returns where line `line_index` of the text goes in a layout with `rows` rows per column,
+/// as `(column, row)`. Lines fill the first column from top to bottom, then the next.
+pub fn column_position(line_index: usize, rows: usize) -> (usize, usize) {
+    let rows = rows.max(1);
+    (line_index / rows, line_index % rows)
+}
+
+/// Arranges `lines`, each wrapped to `column_width`, into `columns` columns of `rows` rows (see
+/// `column_position`), and moves the caret at `(line_index, column)` along with its line.
+///
+/// Every cell is padded to `column_width`, so the rows are all as wide and center as one block.
+/// A single column is returned as it is, each line to be centered on its own.
+pub fn arrange_in_columns(
+    lines: &[String],
+    caret: Option<(usize, usize)>,
+    rows: usize,
+    columns: usize,
+    column_width: usize,
+) -> (Vec<String>, Option<(usize, usize)>) {
+    if columns <= 1 {
+        return (lines.to_vec(), caret);
+    }
+    let column_start = |column: usize| column * (column_width + TEXT_COLUMN_GAP);
+    let mut screen_rows = vec![String::new(); rows.min(lines.len())];
+    for (i, line) in lines.iter().enumerate().take(rows * columns) {
+        let (column, row) = column_position(i, rows);
+        let cell = &mut screen_rows[row];
+        let padding = column_start(column).saturating_sub(visible_width(cell) as usize);
+        cell.extend(std::iter::repeat_n(' ', padding));
+        cell.push_str(line);
+    }
+    for row in screen_rows.iter_mut() {
+        let padding = (column_start(columns) - TEXT_COLUMN_GAP).saturating_sub(visible_width(row) as usize);
+        row.extend(std::iter::repeat_n(' ', padding));
+    }
+    let caret = caret.map(|(line_index, offset)| {
+        let (column, row) = column_position(line_index, rows);
+        (row, column_start(column) + offset)
+    });
+    (screen_rows, caret)
+}
+
+/// Returns the rows of the first text line and of the footer for a screen with
+/// `header_height` header rows and `text_lines` wrapped lines of words.
+///
+/// The text is centered in the rows between header and footer, and never starts above the header's end.
+pub fn text_layout(terminal_height: u16, header_height: u16, text_lines: u16) -> (u16, u16) {
+    let footer_row = terminal_height.saturating_sub(1);
+    let available_height_for_text = terminal_height.saturating_sub(header_height).saturating_sub(1);
+    let text_start_row = header_height + available_height_for_text.saturating_sub(text_lines) / 2;
+    (text_start_row, footer_row)
+}
+
+/// Shows a centered "Terminal too small" notice in place of the game.
+pub fn display_terminal_too_small(stdout: &mut impl Write, terminal_width: u16, terminal_height: u16) -> Result<()> {
+    let lines = ["Terminal too small".to_string(), format!("(need {}x{})", MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT)];
+    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::Hide)?;
+    let start_row = terminal_height.saturating_sub(lines.len() as u16) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        execute!(stdout, cursor::MoveTo(centered_column(line, terminal_width), start_row + i as u16), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Returns the header rows of the typing screen: the timer (and pause notice), the progress
+/// through the text where the mode has one (see `progress_indicator`), and the live stats.
+/// In Time, Words and Quote mode a progress bar, fitted to `terminal_width`, follows the timer
+/// or the progress.
+pub fn header_lines(game_state: &GameState, theme: &Theme, elapsed_seconds: f64, terminal_width: u16) -> Vec<String> {
+    let mut header_lines: Vec<String> = Vec::new();
+    let timer_display = if game_state.config.game_type == GameType::Time {
+        let total_duration = game_state.config.time_seconds.unwrap_or(0) as f64;
+        let remaining_time = (total_duration - elapsed_seconds).max(0.0);
+        format!("Time Left: {:02}:{:02}", (remaining_time / 60.0).floor() as u32, (remaining_time % 60.0).floor() as u32)
+    } else {
+        format!("Time Elapsed: {:02}:{:02}", (elapsed_seconds / 60.0).floor() as u32, (elapsed_seconds % 60.0).floor() as u32)
+    };
+    let timer_line = if game_state.is_paused() {
+        let notice = if game_state.idle_paused { "PAUSED, idle (Ctrl+P to resume)" } else { "PAUSED (Ctrl+P to resume)" };
+        format!("{}  {}", timer_display, theme.current.paint(notice))
+    } else {
+        timer_display
+    };
+    // Time mode shows the time used up next to the timer; Words and Quote mode the words
+    // done next to the word count.
+    let total_words = game_state.words_to_type.len();
+    header_lines.push(match game_state.config.game_type {
+        GameType::Time => {
+            let total_seconds = game_state.config.time_seconds.unwrap_or(0) as usize;
+            with_progress_bar(timer_line, elapsed_seconds as usize, total_seconds, terminal_width)
+        }
+        _ => timer_line,
+    });
+    header_lines.extend(progress_indicator(game_state).map(|progress| match game_state.config.game_type {
+        GameType::Words | GameType::Quote => {
+            let progress = if near_finish(game_state) {
+                StateStyle { bold: true, ..theme.incorrect.clone() }.paint(&progress).to_string()
+            } else {
+                progress
+            };
+            with_progress_bar(progress, game_state.current_word_index.min(total_words), total_words, terminal_width)
+        }
+        _ => progress,
+    }));
+    if elapsed_seconds > 0.01 {
+        let stats = calculate_wpm(game_state.correct_chars_total, game_state.typed_chars_total, elapsed_seconds,
+            &game_state.config.wpm_scheme, game_state.config.error_penalty);
+        // The header shows the smoothed figures when the game loop has kept them up to date.
+        let (gross_wpm, wpm) = game_state.live_wpm.unwrap_or((stats.gross_wpm, stats.wpm));
+        let current_wpm = game_state.rolling_wpm(elapsed_seconds).map_or("-".to_string(), |wpm| format!("{:.0}", wpm));
+        let kpm = calculate_kpm(game_state.typed_chars_total, elapsed_seconds);
+        let mut stats_line = format!("{} | Now: {} | KPM: {:.0} | Accuracy: {:.2}%",
+            speed_summary(&game_state.config.wpm_scheme, format!("{:.0}", gross_wpm), format!("{:.0}", wpm)),
+            current_wpm, kpm, stats.accuracy);
+        if let Some(target) = game_state.config.target_wpm {
+            let status = if wpm >= target as f64 {
+                theme.correct.paint(&format!("above target {}", target))
+            } else {
+                theme.incorrect.paint(&format!("below target {}", target))
+            };
+            stats_line.push_str(&format!(" | {}", status));
+        }
+        header_lines.push(stats_line);
+    } else {
+        header_lines.push(format!("{} | Now: - | KPM: - | Accuracy: -%",
+            speed_summary(&game_state.config.wpm_scheme, "-".to_string(), "-".to_string())));
+    }
+    header_lines
+}
+
+/// Formats the speeds in the header's stats line: gross WPM, then the headline WPM under
+/// `scheme`. The raw scheme's headline is gross WPM itself, so it is shown once.
+fn speed_summary(scheme: &WpmScheme, gross_wpm: String, wpm: String) -> String {
+    match scheme {
+        WpmScheme::Raw => format!("{}: {}", scheme.label(), wpm),
+        _ => format!("Gross WPM: {} | {}: {}", gross_wpm, scheme.label(), wpm),
+    }
+}
+
+/// Returns the progress shown in the header: words completed out of the total in Words
+/// mode, the number of the word being typed in Quote mode, and the words completed so far in
+/// a time attack. Other modes show none.
+pub fn progress_indicator(game_state: &GameState) -> Option<String> {
+    let total = game_state.words_to_type.len();
+    match game_state.config.game_type {
+        GameType::Words => Some(format!("Words: {}/{}", game_state.current_word_index.min(total), total)),
+        GameType::Quote => match game_state.quote_progress() {
+            Some((current, quotes)) if quotes > 1 => Some(format!(
+                "Quote {}/{}, word {}", current, quotes, (game_state.current_word_index + 1).min(total))),
+            _ => Some(format!("Word {}", (game_state.current_word_index + 1).min(total))),
+        },
+        GameType::Time if game_state.config.is_time_attack() => Some(format!("Words: {}", game_state.words_completed)),
+        _ => None,
+    }
+}
+
+/// Returns whether the word progress in the header should signal the finish line: in Words and
+/// Quote mode, once fewer than `config.finish_warning_words` words are left, counting the one
+/// being typed.
+pub fn near_finish(game_state: &GameState) -> bool {
+    let words_left = game_state.words_to_type.len().saturating_sub(game_state.current_word_index);
+    matches!(game_state.config.game_type, GameType::Words | GameType::Quote)
+        && words_left > 0
+        && words_left < game_state.config.finish_warning_words as usize
+}
+
+/// Draws a bar `width` columns wide, filled in proportion to `current` out of `total`.
+///
+/// The bar is only full once `current` reaches `total`; a `total` of zero draws an empty bar.
+pub fn render_progress_bar(current: usize, total: usize, width: usize) -> String {
+    let filled = (current.min(total) * width).checked_div(total).unwrap_or(0);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Appends a progress bar to the header line `line`, as wide as fits in `terminal_width`
+/// up to `PROGRESS_BAR_MAX_WIDTH`. The line is left alone when there is no room for a bar.
+fn with_progress_bar(line: String, current: usize, total: usize, terminal_width: u16) -> String {
+    // Two columns between line and bar, and a margin of two on either side of the screen.
+    let room = (terminal_width as usize).saturating_sub(display_width(&line) + 6);
+    let width = room.min(PROGRESS_BAR_MAX_WIDTH);
+    if width == 0 {
+        return line;
+    }
+    format!("{}  {}", line, render_progress_bar(current, total, width))
+}
+
+/// Displays the main game interface (typing area, stats, timer), `elapsed_seconds` into the run.
+pub fn display_game_interface(stdout: &mut impl Write, game_state: &GameState, elapsed_seconds: f64, terminal_width: u16, terminal_height: u16) -> Result<()> {
+    execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+    let theme = Theme::from_name(&game_state.config.theme);
+    let header_lines = header_lines(game_state, &theme, elapsed_seconds, terminal_width);
+    for (i, line) in header_lines.iter().enumerate() {
+        let padding = centered_column(line, terminal_width);
+        execute!(stdout, cursor::MoveTo(padding, i as u16), Print(line))?;
+    }
+    let available_rows = terminal_height.saturating_sub(header_lines.len() as u16 + 1).max(1) as usize;
+    let (wrapped_text_lines, caret_position, text_rows) = if game_state.config.game_type == GameType::Code {
+        let (mut code_lines, caret_position) = render_code_lines(game_state, &theme, colors_enabled(), available_rows);
+        // Code keeps its line breaks and is centered as one block, so indentation lines up.
+        pad_to_common_width(&mut code_lines);
+        let text_rows = code_lines.len();
+        (code_lines, caret_position, text_rows)
+    } else {
+        // The full text view uses every row there is; the window only a few.
+        let max_lines = if game_state.config.shows_full_text() { available_rows } else { available_rows.min(MAX_TEXT_LINES) };
+        let columns = fitting_columns(game_state.config.text_columns as usize, text_wrap_width(terminal_width));
+        let column_width = text_column_width(text_wrap_width(terminal_width), columns);
+        let (lines, caret_position) = render_typing_lines(
+            game_state, &theme, colors_enabled(), column_width, max_lines * columns);
+        let (lines, caret_position) = arrange_in_columns(&lines, caret_position, max_lines, columns, column_width);
+        // Rows are reserved for every line, so the text stays put once the last lines scroll up.
+        (lines, caret_position, max_lines)
+    };
+    let (text_display_start_row, footer_row) = text_layout(terminal_height, header_lines.len() as u16, text_rows as u16);
+    for (i, line) in wrapped_text_lines.iter().enumerate() {
+        let padding = centered_column(line, terminal_width);
+        execute!(stdout, cursor::MoveTo(padding, text_display_start_row + i as u16), Print(line))?;
+    }
+    let quit_msg = footer_text(game_state, elapsed_seconds);
+    let quit_msg_padding = centered_column(quit_msg, terminal_width);
+    execute!(stdout, cursor::MoveTo(quit_msg_padding, footer_row), Print(quit_msg))?;
+    let cursor_at_caret = caret_position
+        .map(|caret| caret_screen_position(&wrapped_text_lines, caret, text_display_start_row, terminal_width));
+    match cursor_at_caret {
+        Some((column, row)) if game_state.config.caret_style == CaretStyle::Bar && colors_enabled() => {
+            execute!(stdout, cursor::SetCursorStyle::SteadyBar, cursor::MoveTo(column, row), cursor::Show)?;
+        }
+        Some((column, row)) if game_state.config.show_cursor => {
+            execute!(stdout, cursor::SetCursorStyle::DefaultUserShape, cursor::MoveTo(column, row), cursor::Show)?;
+        }
+        _ => execute!(stdout, cursor::Hide)?,
+    }
+    stdout.flush()?; 
+    Ok(())
+}
+
+/// Returns the footer of the typing screen: how to quit, or, while wrong keys have been
+/// blocking the word for a while (see `GameState::backspace_hint_due`), how to get unstuck.
+pub fn footer_text(game_state: &GameState, elapsed_seconds: f64) -> &'static str {
+    if game_state.backspace_hint_due(elapsed_seconds) {
+        "Backspace to fix errors | Esc to quit"
+    } else {
+        "Press Esc to quit"
+    }
+}
+
+/// Draws `values` as a one-line sparkline at most `max_width` characters wide.
+///
+/// Bars are scaled from zero to the largest value. When there are more values than columns,
+/// neighbouring values are averaged into a single column.
+fn render_sparkline(values: &[f64], max_width: usize) -> String {
+    if values.is_empty() || max_width == 0 {
+        return String::new();
+    }
+    let columns = values.len().min(max_width);
+    let buckets: Vec<f64> = (0..columns).map(|col| {
+        let start = col * values.len() / columns;
+        let end = ((col + 1) * values.len() / columns).max(start + 1);
+        values[start..end].iter().sum::<f64>() / (end - start) as f64
+    }).collect();
+    let max = buckets.iter().cloned().fold(0.0, f64::max);
+    let top_level = SPARKLINE_LEVELS.len() - 1;
+    buckets.iter().map(|&value| {
+        let level = if max > 0.0 { ((value / max) * top_level as f64).round() as usize } else { 0 };
+        SPARKLINE_LEVELS[level.min(top_level)]
+    }).collect()
+}
+
+/// Builds the WPM graph lines for the game over screen: the sparkline and a min/avg/max summary.
+///
+/// Returns no lines if no samples were recorded.
+fn wpm_graph_lines(samples: &[(f64, f64)], terminal_width: u16) -> Vec<String> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let values: Vec<f64> = samples.iter().map(|&(_, wpm)| wpm).collect();
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    vec![
+        render_sparkline(&values, terminal_width.saturating_sub(4) as usize),
+        format!("WPM  min {:.0} | avg {:.0} | max {:.0}", min, avg, max),
+    ]
+}
+
+/// Displays the game over screen with final statistics.
+pub fn display_game_over_screen(stdout: &mut impl Write, game_state: &GameState, terminal_width: u16, terminal_height: u16) -> Result<()> {
+    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::Hide)?;
+    let lines_to_display = game_over_lines(game_state, terminal_width, terminal_height);
+    let total_lines_height = lines_to_display.len() as u16;
+    let start_row = terminal_height.saturating_sub(total_lines_height) / 2;
+    for (i, line) in lines_to_display.iter().enumerate() {
+        let padding = centered_column(line, terminal_width);
+        execute!(stdout, cursor::MoveTo(padding, start_row + i as u16), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Displays the session summary shown when leaving a `--cumulative` session.
+pub fn display_session_summary(stdout: &mut impl Write, session: &SessionResult, terminal_width: u16, terminal_height: u16) -> Result<()> {
+    execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::Hide)?;
+    let lines = session_summary_lines(session);
+    let start_row = terminal_height.saturating_sub(lines.len() as u16) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        execute!(stdout, cursor::MoveTo(centered_column(line, terminal_width), start_row + i as u16), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Builds the lines of the session summary, with the stats lined up as on the game-over screen.
+pub fn session_summary_lines(session: &SessionResult) -> Vec<String> {
+    let mut stats = vec![
+        format!("{:<12}{}", "Attempts:", session.attempts),
+        format!("{:<12}{:.0}", "Gross WPM:", session.gross_wpm),
+        format!("{:<12}{:.0}", "Net WPM:", session.net_wpm),
+        format!("{:<12}{:.2}%", "Accuracy:", session.accuracy),
+        format!("{:<12}{}", "Words:", session.words_completed),
+        format!("{:<12}{:02}:{:02}", "Time:", (session.duration / 60.0).floor() as u32, (session.duration % 60.0).floor() as u32),
+    ];
+    pad_to_common_width(&mut stats);
+    let mut lines = vec!["Session Summary".to_string(), "".to_string()];
+    lines.extend(stats);
+    lines.push("".to_string());
+    lines.push("Press any key to continue".to_string());
+    lines
+}
+
+/// Builds the lines of the game-over screen for a terminal of the given size.
+///
+/// The character breakdown and the WPM graph are extras: each is left out when it would
+/// not fit in `terminal_height` along with the main stats.
+pub fn game_over_lines(game_state: &GameState, terminal_width: u16, terminal_height: u16) -> Vec<String> {
+    let banner_text = if game_state.failure.is_some() { "Failed!" } else { "Game Over!" };
+    let font = FIGfont::standard().unwrap_or_else(|_| FIGfont::from_content(banner_text).expect("Figlet fallback font failed")); 
+    let game_over_banner = font.convert(banner_text).map(|figure| figure.to_string()).unwrap_or_default();
+    let mut lines_to_display: Vec<String> = game_over_banner.lines().map(str::to_string).collect();
+    pad_to_common_width(&mut lines_to_display);
+    lines_to_display.push("".to_string()); 
+    if let Some(reason) = failure_description(game_state) {
+        lines_to_display.push(reason);
+    }
+    let final_time = game_state.final_elapsed_time_seconds.unwrap_or_else(|| game_state.timer.elapsed_secs());
+    let scheme = &game_state.config.wpm_scheme;
+    let stats = calculate_wpm(
+        game_state.correct_chars_total, game_state.typed_chars_total, final_time, scheme, game_state.config.error_penalty);
+    // A time attack is scored by words completed, so that leads, ahead of the character-based stats.
+    if game_state.config.is_time_attack() {
+        lines_to_display.push(format!("Words completed: {} ({:.0} per minute)",
+            game_state.words_completed, words_per_minute(game_state.words_completed, final_time)));
+    }
+    let mut headline_stats = Vec::new();
+    if *scheme != WpmScheme::Raw {
+        headline_stats.push(format!("{:<12}{:.0}", "Gross WPM:", stats.gross_wpm));
+    }
+    headline_stats.push(format!("{:<12}{:.0}", format!("{}:", scheme.label()), stats.wpm));
+    headline_stats.push(format!("{:<12}{:.0}", "KPM:", calculate_kpm(game_state.typed_chars_total, final_time)));
+    headline_stats.push(format!("{:<12}{:.2}%", "Accuracy:", stats.accuracy));
+    headline_stats.push(format!("{:<12}{}", "Scoring:", scheme.description()));
+    // The labels are padded so the values line up; keep that alignment once centered.
+    pad_to_common_width(&mut headline_stats);
+    lines_to_display.extend(headline_stats);
+    let wpm_values: Vec<f64> = game_state.wpm_samples.iter().map(|&(_, wpm)| wpm).collect();
+    lines_to_display.push(format!("Consistency: {:.0}%", calculate_consistency(&wpm_values)));
+    lines_to_display.push(most_missed_line(&game_state.char_stats));
+    lines_to_display.push(format!("Time Taken: {:02}:{:02}", (final_time / 60.0).floor() as u32, (final_time % 60.0).floor() as u32));
+    if game_state.result_recorded {
+        // Personal bests and averages are kept in net WPM with the default penalty, whatever the scheme.
+        let unit = if *scheme == WpmScheme::Net { "WPM" } else { "net WPM" };
+        let recorded_wpm = calculate_wpm(game_state.correct_chars_total, game_state.typed_chars_total, final_time,
+            &WpmScheme::Net, DEFAULT_ERROR_PENALTY).net_wpm;
+        if let Some(average) = game_state.previous_average_wpm {
+            lines_to_display.push(average_comparison(recorded_wpm, average, unit));
+        }
+        lines_to_display.push(match game_state.previous_best_wpm {
+            None => "New personal best! (first run of this kind)".to_string(),
+            Some(best) if recorded_wpm > best => format!("New personal best! (previous: {:.0} {})", best, unit),
+            Some(best) => format!("Personal best: {:.0} {}", best, unit),
+        });
+    }
+    if let Some(passed) = game_state.config.meets_goals(stats.wpm, stats.accuracy) {
+        lines_to_display.push(format!("Goal ({}): {}", goal_description(&game_state.config), if passed { "PASSED" } else { "FAILED" }));
+    }
+    let mut attribution_lines: Vec<String> = Vec::new();
+    if let Some(attribution) = quote_attribution(game_state) {
+        attribution_lines.push("".to_string());
+        attribution_lines.push(attribution);
+    }
+    if let Some(marks) = quote_marks_line(game_state) {
+        attribution_lines.push(marks);
+    }
+    attribution_lines.push("".to_string());
+    // Raw counts behind the WPM figures, so they can be checked by hand.
+    let mut breakdown_lines = vec![
+        format!("Characters: {} typed, {} correct, {} incorrect",
+            game_state.typed_chars_total, game_state.correct_chars_total, game_state.wrong_keystrokes),
+        format!("Errors in net WPM penalty: {}", game_state.typed_chars_total.saturating_sub(game_state.correct_chars_total)),
+    ];
+    breakdown_lines.extend(slowest_words_line(game_state));
+    // One more row for the footer.
+    if lines_to_display.len() + breakdown_lines.len() + attribution_lines.len() < terminal_height as usize {
+        lines_to_display.extend(breakdown_lines);
+    }
+    lines_to_display.extend(attribution_lines);
+    let graph_lines = wpm_graph_lines(&game_state.wpm_samples, terminal_width);
+    // The graph is a nice-to-have: skip it rather than push the stats off a short terminal.
+    if !graph_lines.is_empty() && lines_to_display.len() + graph_lines.len() + 2 <= terminal_height as usize {
+        lines_to_display.extend(graph_lines);
+        lines_to_display.push("".to_string());
+    }
+    lines_to_display.push("Enter: repeat this test | R: new words | Esc: menu".to_string());
+    lines_to_display
+}
+
+/// Describes `wpm` against the player's `average`, e.g. "WPM 78 (+4 vs your avg)".
+///
+/// The difference is taken between the rounded figures, so it always matches the numbers shown.
+pub fn average_comparison(wpm: f64, average: f64, unit: &str) -> String {
+    let delta = wpm.round() as i64 - average.round() as i64;
+    let label = unit[..1].to_uppercase() + &unit[1..];
+    format!("{} {:.0} ({:+} vs your avg)", label, wpm, delta)
+}
+
+/// Returns up to `limit` characters with errors, as `(char, attempts, errors)`, worst first.
+///
+/// Characters are ranked by error rate, then by number of errors, then alphabetically so the
+/// order is stable. Characters that were never mistyped are left out.
+pub fn most_missed_chars(char_stats: &HashMap<char, (usize, usize)>, limit: usize) -> Vec<(char, usize, usize)> {
+    let mut missed: Vec<(char, usize, usize)> = char_stats
+        .iter()
+        .filter(|(_, &(_, errors))| errors > 0)
+        .map(|(&c, &(attempts, errors))| (c, attempts, errors))
+        .collect();
+    missed.sort_by(|a, b| {
+        let rate = |&(_, attempts, errors): &(char, usize, usize)| errors as f64 / attempts as f64;
+        rate(b).total_cmp(&rate(a)).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0))
+    });
+    missed.truncate(limit);
+    missed
+}
+
+/// Formats the "Most missed" line of the results screen.
+pub fn most_missed_line(char_stats: &HashMap<char, (usize, usize)>) -> String {
+    let missed = most_missed_chars(char_stats, MOST_MISSED_SHOWN);
+    if missed.is_empty() {
+        return "Most missed: none".to_string();
+    }
+    let entries: Vec<String> = missed
+        .iter()
+        .map(|&(c, attempts, errors)| {
+            let name = match c {
+                ' ' => "space".to_string(),
+                '\n' => "enter".to_string(),
+                _ => c.to_string(),
+            };
+            format!("{} {:.0}% ({}/{})", name, errors as f64 / attempts as f64 * 100.0, errors, attempts)
+        })
+        .collect();
+    format!("Most missed: {}", entries.join(", "))
+}
+
+/// Returns up to `limit` completed words with the time spent on each, as `(word, seconds)`,
+/// slowest first. Ties keep the order the words were typed in.
+pub fn slowest_words(game_state: &GameState, limit: usize) -> Vec<(&str, f64)> {
+    let mut timed: Vec<(&str, f64)> = game_state.words_to_type.iter()
+        .zip(&game_state.word_seconds)
+        .map(|(word, &seconds)| (word.as_str(), seconds))
+        .collect();
+    timed.sort_by(|a, b| b.1.total_cmp(&a.1));
+    timed.truncate(limit);
+    timed
+}
+
+/// Formats the "Slowest words" line of the results screen, or `None` if no word was completed.
+pub fn slowest_words_line(game_state: &GameState) -> Option<String> {
+    let slowest = slowest_words(game_state, SLOWEST_WORDS_SHOWN);
+    if slowest.is_empty() {
+        return None;
+    }
+    let entries: Vec<String> = slowest.iter().map(|(word, seconds)| format!("{} {:.1}s", word, seconds)).collect();
+    Some(format!("Slowest words: {}", entries.join(", ")))
+}
+
+/// Describes the configured goals, e.g. "80 WPM, 95% accuracy".
+pub fn goal_description(config: &GameConfig) -> String {
+    let mut goals = Vec::new();
+    if let Some(target) = config.target_wpm {
+        goals.push(format!("{} WPM", target));
+    }
+    if let Some(floor) = config.min_accuracy {
+        goals.push(format!("{}% accuracy", floor));
+    }
+    goals.join(", ")
+}
+
+/// Returns the line explaining why the run failed, or `None` if it did not fail.
+pub fn failure_description(game_state: &GameState) -> Option<String> {
+    match game_state.failure? {
+        RunFailure::AccuracyFloor => Some(format!("Failed: accuracy fell below {}%", game_state.config.min_accuracy.unwrap_or(0.0))),
+        RunFailure::ConsecutiveErrors => Some(format!("Failed: {} mistakes in a row", game_state.config.max_consecutive_errors.unwrap_or(0))),
+    }
+}
+
+/// Returns the line telling whether the quote just typed is a favorite or blacklisted, and how
+/// to change that, or `None` outside Quote mode.
+pub fn quote_marks_line(game_state: &GameState) -> Option<String> {
+    let quote = game_state.quote.as_ref()?;
+    let line = if game_state.quote_marks.is_favorite(quote) {
+        "Favorite quote (F: unfavorite | B: blacklist)"
+    } else if game_state.quote_marks.is_blacklisted(quote) {
+        "Blacklisted quote (B: allow again | F: favorite)"
+    } else {
+        "F: favorite this quote | B: never show it again"
+    };
+    Some(line.to_string())
+}
+
+/// Returns the "— source" line crediting the quote that was typed, if there is one to show.
+/// A marathon credits the sources of all its quotes, in order, separated by semicolons.
+///
+/// Non-quote runs and quotes without a source get no attribution line.
+fn quote_attribution(game_state: &GameState) -> Option<String> {
+    let sources = game_state.quote_sources();
+    (!sources.is_empty()).then(|| format!("\u{2014} {}", sources.join("; ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_loader::Quote;
+    use colored::Colorize;
+
+    #[test]
+    fn sparkline_scales_to_width_and_max() {
+        assert_eq!(render_sparkline(&[], 10), "");
+        assert_eq!(render_sparkline(&[0.0, 35.0, 70.0], 10), "▁▅█");
+        assert_eq!(render_sparkline(&[10.0, 10.0, 70.0, 70.0], 2), "▂█");
+        assert_eq!(render_sparkline(&[5.0; 100], 40).chars().count(), 40);
+    }
+
+    #[test]
+    fn visible_width_ignores_ansi_styling() {
+        colored::control::set_override(true);
+        let styled = format!("{}{} {}", "hé".green(), "l".on_red(), "lo".dimmed());
+        assert!(styled.len() > "hél lo".len());
+        assert_eq!(visible_width(&styled), "hél lo".chars().count() as u16);
+        assert_eq!(centered_column(&styled, 20), 7);
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        assert_eq!(visible_width("日本語"), 6);
+        assert_eq!(visible_width("ｆｕｌｌ"), 4 * 2);
+        assert_eq!(visible_width("abc 漢字"), 8);
+        colored::control::set_override(true);
+        assert_eq!(visible_width(&format!("{} {}", "かな".green(), "ok".dimmed())), 7);
+        assert_eq!(centered_column("日本語", 20), 7);
+    }
+
+    #[test]
+    fn wide_characters_wrap_and_place_the_caret_by_cell() {
+        let words: Vec<String> = "日本語 漢字 かな".split(' ').map(str::to_string).collect();
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+        // "[日]本語 漢字" would be 13 cells, more than the 10 available.
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 10, 3);
+        assert_eq!(lines, vec!["[日]本語", "漢字 かな"]);
+        assert_eq!(caret_position, Some((0, 1)));
+        state.handle_char('日');
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 10, 3);
+        assert_eq!(lines[0], "日[本]語");
+        assert_eq!(caret_position, Some((0, 3)));
+        // The 8-cell line is centered from column 16 of 41.
+        assert_eq!(caret_screen_position(&lines, caret_position.unwrap(), 2, 41), (19, 2));
+    }
+
+    #[test]
+    fn caret_styles_use_distinct_attributes() {
+        colored::control::set_override(true);
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        assert!(style_caret("a", &CaretStyle::Block, &theme).contains("\x1b[7m"));
+        assert!(style_caret("a", &CaretStyle::Underline, &theme).contains("\x1b[4m"));
+        assert_eq!(style_caret("a", &CaretStyle::Bar, &theme), "a");
+        assert_eq!(style_caret("a", &CaretStyle::Highlight, &theme), "a".black().on_yellow().to_string());
+    }
+
+    #[test]
+    fn typing_line_without_color_is_plain_text() {
+        let words = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        "one t".chars().for_each(|c| state.handle_char(c));
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 60, 3);
+        assert!(!lines[0].contains('\x1b'), "{lines:?}");
+        assert_eq!(lines, vec!["one t[w]o three"]);
+        assert_eq!(caret_position, Some((0, 6)));
+
+        state.handle_char('x');
+        let (lines, _) = render_typing_lines(&state, &theme, false, 60, 3);
+        assert_eq!(lines, vec!["one t[x]wo three"]);
+    }
+
+    #[test]
+    fn words_are_laid_out_into_lines_that_fit() {
+        assert_eq!(layout_word_lines(&[], 10, usize::MAX), vec![]);
+        // "the quick brown fox" at width 10: "the quick" and "brown fox".
+        assert_eq!(layout_word_lines(&[3, 5, 5, 3], 10, usize::MAX), vec![(0, 2), (2, 4)]);
+        // A word wider than the line gets a line of its own.
+        assert_eq!(layout_word_lines(&[2, 30, 2], 10, usize::MAX), vec![(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(layout_word_lines(&[1, 1, 1, 1, 1], 80, 2), vec![(0, 2), (2, 4), (4, 5)]);
+    }
+
+    #[test]
+    fn current_line_stays_put_while_completed_lines_scroll_up() {
+        assert_eq!(visible_line_range(0, 0, 3), (0, 0));
+        assert_eq!(visible_line_range(10, 0, 3), (0, 3));
+        assert_eq!(visible_line_range(10, 1, 3), (0, 3));
+        assert_eq!(visible_line_range(10, 2, 3), (1, 4));
+        assert_eq!(visible_line_range(10, 9, 3), (8, 10));
+        assert_eq!(visible_line_range(10, 12, 3), (8, 10));
+        assert_eq!(visible_line_range(10, 4, 1), (4, 5));
+
+        colored::control::set_override(true);
+        let words: Vec<String> = (0..60).map(|i| "w".repeat(1 + i % 7)).collect();
+        let widths: Vec<usize> = words.iter().map(|word| word.len()).collect();
+        let layout = layout_word_lines(&widths, 20, usize::MAX);
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words.clone());
+        for (index, word) in words.iter().enumerate() {
+            let current_line = layout.iter().position(|&(_, end)| index < end).unwrap();
+            let (lines, caret_position) = render_typing_lines(&state, &theme, true, 20, 3);
+            let (row, _) = caret_position.expect("the current character is always visible");
+            // Past the first line, the current line is always drawn on the second row.
+            assert_eq!(row, current_line.min(1), "word {index}");
+            let first = current_line - row;
+            let expected_widths: Vec<usize> = layout[first..(first + 3).min(layout.len())]
+                .iter()
+                .map(|&(start, end)| widths[start..end].iter().sum::<usize>() + (end - start - 1))
+                .collect();
+            let widths_drawn: Vec<usize> = lines.iter().map(|line| display_width(line)).collect();
+            assert_eq!(widths_drawn, expected_widths, "word {index}");
+            word.chars().chain([' ']).for_each(|c| state.handle_char(c));
+        }
+    }
+
+    #[test]
+    fn full_text_scrolls_to_keep_the_current_line_in_view() {
+        // Short texts are shown whole, wherever the current line is.
+        assert_eq!(full_text_line_range(4, 0, 10), (0, 4));
+        assert_eq!(full_text_line_range(4, 3, 10), (0, 4));
+        assert_eq!(full_text_line_range(0, 0, 10), (0, 0));
+        // Longer ones keep the current line a third of the way down...
+        assert_eq!(full_text_line_range(30, 2, 9), (0, 9));
+        assert_eq!(full_text_line_range(30, 3, 9), (0, 9));
+        assert_eq!(full_text_line_range(30, 4, 9), (1, 10));
+        assert_eq!(full_text_line_range(30, 15, 9), (12, 21));
+        // ...until the end of the text reaches the bottom row.
+        assert_eq!(full_text_line_range(30, 25, 9), (21, 30));
+        assert_eq!(full_text_line_range(30, 40, 9), (21, 30));
+        assert_eq!(full_text_line_range(30, 5, 1), (5, 6));
+
+        let words: Vec<String> = (0..40).map(|i| format!("w{}", i % 10)).collect();
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let config = GameConfig { game_type: GameType::Words, text_view: crate::config::TextView::Full, ..GameConfig::new() };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words.clone());
+        // Six words of two characters fit on each line of 19 columns, even with the current one bracketed, so 40 words take 7 lines.
+        let (lines, _) = render_typing_lines(&state, &theme, false, 19, 20);
+        assert_eq!(lines.len(), 7);
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 19, 3);
+        assert_eq!((lines.len(), caret_position), (3, Some((0, 1))));
+        words[..30].iter().for_each(|word| word.chars().chain([' ']).for_each(|c| state.handle_char(c)));
+        // Word 30 starts the sixth of seven lines, drawn below the last finished line.
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 19, 3);
+        assert_eq!(lines, ["w4 w5 w6 w7 w8 w9", "[w]0 w1 w2 w3 w4 w5", "w6 w7 w8 w9"]);
+        assert_eq!(caret_position, Some((1, 1)));
+
+        // Time mode keeps the window even with the full view chosen.
+        state.config.game_type = GameType::Time;
+        assert!(!state.config.shows_full_text());
+    }
+
+    #[test]
+    fn case_errors_are_told_apart_from_wrong_letters() {
+        assert!(is_case_error('t', Some('T')));
+        assert!(is_case_error('É', Some('é')));
+        assert!(!is_case_error('T', Some('T')));
+        assert!(!is_case_error('x', Some('T')));
+        assert!(!is_case_error('t', None));
+
+        colored::control::set_override(true);
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["The".to_string()]);
+        "tx".chars().for_each(|c| state.handle_char(c));
+        let (lines, _) = render_typing_lines(&state, &theme, true, 60, 3);
+        let expected = format!("{}{}", theme.wrong_case.paint("t"), theme.incorrect.paint("x"));
+        assert!(lines[0].starts_with(&expected), "{:?}", lines[0]);
+    }
+
+    #[test]
+    fn blind_mode_draws_errors_like_correct_input() {
+        colored::control::set_override(true);
+        let words = vec!["one".to_string(), "two".to_string()];
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let mut state = GameState::new(GameConfig { blind: true, ..GameConfig::new() }, Vec::new(), Vec::new(), words);
+        "one tx".chars().for_each(|c| state.handle_char(c));
+
+        let (plain, _) = render_typing_lines(&state, &theme, false, 60, 3);
+        assert_eq!(plain, vec!["one tx[w]o"]);
+        let (colored_lines, _) = render_typing_lines(&state, &theme, true, 60, 3);
+        let colored_line = &colored_lines[0];
+        let correct_t = theme.correct.paint("t").to_string();
+        let correct_x = theme.correct.paint("x").to_string();
+        assert!(colored_line.contains(&format!("{correct_t}{correct_x}")), "{colored_line:?}");
+        assert!(!colored_line.contains(&theme.incorrect.paint("x").to_string()));
+
+        state.config.blind = false;
+        let (plain, _) = render_typing_lines(&state, &theme, false, 60, 3);
+        assert_eq!(plain, vec!["one t[x]wo"]);
+    }
+
+    #[test]
+    fn mistakes_without_backspace_are_marked_in_place() {
+        let words = vec!["one".to_string(), "two".to_string()];
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let mut state = GameState::new(GameConfig { no_backspace: true, ..GameConfig::new() }, Vec::new(), Vec::new(), words);
+        "one tx".chars().for_each(|c| state.handle_char(c));
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 60, 3);
+        assert_eq!(lines, vec!["one t[w][o]"]);
+        assert_eq!(caret_position, Some((0, 9)));
+
+        state.config.blind = true;
+        let (lines, _) = render_typing_lines(&state, &theme, false, 60, 3);
+        assert_eq!(lines, vec!["one tw[o]"]);
+    }
+
+    #[test]
+    fn most_missed_handles_a_clean_run() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["ok".to_string()]);
+        "ok".chars().for_each(|c| state.handle_char(c));
+        assert!(most_missed_chars(&state.char_stats, 3).is_empty());
+        assert_eq!(most_missed_line(&state.char_stats), "Most missed: none");
+    }
+
+    #[test]
+    fn footer_suggests_backspace_once_errors_linger() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["ok".to_string()]);
+        state.handle_char('x');
+        state.errors_started_at = Some(2.0);
+        assert_eq!(footer_text(&state, 2.5), "Press Esc to quit");
+        assert_eq!(footer_text(&state, 3.0), "Backspace to fix errors | Esc to quit");
+    }
+
+    #[test]
+    fn session_summary_lists_the_totals() {
+        let session = SessionResult {
+            attempts: 3, gross_wpm: 71.6, net_wpm: 68.2, accuracy: 97.5, duration: 95.0, words_completed: 104,
+        };
+        let lines = session_summary_lines(&session);
+        assert_eq!(lines[0], "Session Summary");
+        assert_eq!(&lines[2..8], [
+            "Attempts:   3     ", "Gross WPM:  72    ", "Net WPM:    68    ",
+            "Accuracy:   97.50%", "Words:      104   ", "Time:       01:35 ",
+        ]);
+        assert_eq!(lines.last().unwrap(), "Press any key to continue");
+    }
+
+    #[test]
+    fn slowest_words_come_first() {
+        let words = ["the", "quick", "brown", "fox"].map(str::to_string).to_vec();
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+        assert_eq!(slowest_words_line(&state), None);
+        // The last word is still being typed, so it has no time yet.
+        state.word_seconds = vec![0.5, 1.25, 0.5];
+        assert_eq!(slowest_words(&state, 2), vec![("quick", 1.25), ("the", 0.5)]);
+        assert_eq!(slowest_words_line(&state).unwrap(), "Slowest words: quick 1.2s, the 0.5s, brown 0.5s");
+    }
+
+    #[test]
+    fn game_over_screen_breaks_down_the_character_counts() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["ab".to_string(), "cd".to_string()]);
+        "ax".chars().for_each(|c| state.handle_char(c));
+        state.handle_backspace();
+        "b cd".chars().for_each(|c| state.handle_char(c));
+        state.game_over = true;
+        state.final_elapsed_time_seconds = Some(10.0);
+
+        let lines = game_over_lines(&state, 80, 100);
+        assert!(lines.contains(&"Characters: 6 typed, 5 correct, 1 incorrect".to_string()), "{lines:#?}");
+        assert!(lines.contains(&"Errors in net WPM penalty: 1".to_string()));
+
+        let short = game_over_lines(&state, 80, 5);
+        assert!(short.iter().any(|line| line.starts_with("Net WPM")));
+        assert!(!short.iter().any(|line| line.starts_with("Characters:") || line.starts_with("Errors in")));
+    }
+
+    #[test]
+    fn time_attack_leads_with_words_completed() {
+        let words: Vec<String> = ["ab", "cd", "ef"].iter().map(|w| w.to_string()).collect();
+        let mut config = GameConfig::new();
+        config.time_attack = true;
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        "ab cd e".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(progress_indicator(&state).as_deref(), Some("Words: 2"));
+        state.game_over = true;
+        state.final_elapsed_time_seconds = Some(30.0);
+        let lines = game_over_lines(&state, 80, 100);
+        let words_line = lines.iter().position(|line| line == "Words completed: 2 (4 per minute)").unwrap();
+        let wpm_line = lines.iter().position(|line| line.starts_with("Gross WPM")).unwrap();
+        assert!(words_line < wpm_line, "{lines:#?}");
+
+        state.config.time_attack = false;
+        assert_eq!(progress_indicator(&state), None);
+        assert!(!game_over_lines(&state, 80, 100).iter().any(|line| line.starts_with("Words completed")));
+    }
+
+    #[test]
+    fn game_over_screen_explains_a_failed_run() {
+        let mut config = GameConfig::new();
+        config.max_consecutive_errors = Some(2);
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), vec!["ab".to_string()]);
+        assert_eq!(failure_description(&state), None);
+        "xx".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(failure_description(&state).as_deref(), Some("Failed: 2 mistakes in a row"));
+        assert!(game_over_lines(&state, 80, 100).contains(&"Failed: 2 mistakes in a row".to_string()));
+
+        state.config.min_accuracy = Some(90.0);
+        state.failure = Some(RunFailure::AccuracyFloor);
+        assert_eq!(failure_description(&state).as_deref(), Some("Failed: accuracy fell below 90%"));
+    }
+
+    #[test]
+    fn columns_only_split_terminals_wide_enough() {
+        assert_eq!(text_column_width(100, 1), 100);
+        assert_eq!(text_column_width(100, 2), 48);
+        assert_eq!(text_column_width(100, 3), 30);
+        assert_eq!(fitting_columns(3, 100), 3);
+        assert_eq!(fitting_columns(3, 97), 2, "three columns would be under the minimum width");
+        assert_eq!(fitting_columns(3, 63), 1);
+        assert_eq!(fitting_columns(2, 200), 2, "never more than requested");
+        assert_eq!(fitting_columns(1, 200), 1);
+    }
+
+    #[test]
+    fn lines_fill_each_column_top_to_bottom() {
+        let positions: Vec<(usize, usize)> = (0..7).map(|line| column_position(line, 3)).collect();
+        assert_eq!(positions, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0)]);
+
+        let lines: Vec<String> = ["one", "two", "three", "four", "five"].map(str::to_string).to_vec();
+        let (rows, caret) = arrange_in_columns(&lines, Some((4, 2)), 3, 2, 6);
+        assert_eq!(rows, ["one       four  ", "two       five  ", "three           "]);
+        // "five" is in the second column, which starts after a column of 6 and the gap.
+        assert_eq!(caret, Some((1, 12)));
+        assert_eq!(rows[caret.unwrap().0].chars().nth(caret.unwrap().1), Some('v'));
+
+        let (rows, caret) = arrange_in_columns(&lines[..2], Some((1, 0)), 3, 2, 6);
+        assert_eq!((rows.len(), caret), (2, Some((1, 0))), "short texts only take the rows they need");
+        let (rows, caret) = arrange_in_columns(&lines, Some((4, 2)), 3, 1, 6);
+        assert_eq!((rows, caret), (lines, Some((4, 2))), "one column is left alone");
+    }
+
+    #[test]
+    fn progress_signals_the_finish_line_below_the_threshold() {
+        let words: Vec<String> = ["ab", "cd", "ef", "gh", "ij"].map(str::to_string).to_vec();
+        let config = GameConfig { game_type: GameType::Words, finish_warning_words: 3, ..GameConfig::new() };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        let mut warnings = vec![near_finish(&state)];
+        for word in ["ab", "cd", "ef", "gh", "ij"] {
+            word.chars().for_each(|c| state.handle_char(c));
+            state.handle_char(' ');
+            warnings.push(near_finish(&state));
+        }
+        // Five, four and three words left are not fewer than three; two and one are; none is over.
+        assert_eq!(warnings, vec![false, false, false, true, true, false]);
+
+        state.current_word_index = 4;
+        state.config.game_type = GameType::Quote;
+        assert!(near_finish(&state));
+        state.config.game_type = GameType::Time;
+        assert!(!near_finish(&state), "only Words and Quote mode count down words");
+        state.config.game_type = GameType::Words;
+        state.config.finish_warning_words = 0;
+        assert!(!near_finish(&state), "a threshold of 0 turns it off");
+    }
+
+    #[test]
+    fn header_shows_progress_in_words_and_quote_modes() {
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let words = vec!["ab".to_string(), "cd".to_string(), "ef".to_string()];
+        let mut config = GameConfig::new();
+        config.game_type = GameType::Words;
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), words);
+        assert_eq!(progress_indicator(&state).as_deref(), Some("Words: 0/3"));
+        "ab cd ".chars().for_each(|c| state.handle_char(c));
+        assert_eq!(progress_indicator(&state).as_deref(), Some("Words: 2/3"));
+        let header = header_lines(&state, &theme, 5.0, 30);
+        assert_eq!(header.len(), 3);
+        assert_eq!(header[1], "Words: 2/3  █████████░░░░░");
+        // The text starts below the extra header row.
+        let (start, _) = text_layout(MIN_TERMINAL_HEIGHT, header.len() as u16, 3);
+        assert!(start >= header.len() as u16);
+
+        state.config.game_type = GameType::Quote;
+        assert_eq!(progress_indicator(&state).as_deref(), Some("Word 3"));
+        "ef".chars().for_each(|c| state.handle_char(c));
+        state.handle_char(' ');
+        assert_eq!(progress_indicator(&state).as_deref(), Some("Word 3"), "never counts past the last word");
+
+        state.config.game_type = GameType::Time;
+        assert_eq!(progress_indicator(&state), None);
+        state.config.time_seconds = Some(20);
+        let header = header_lines(&state, &theme, 5.0, MIN_TERMINAL_WIDTH);
+        assert_eq!(header.len(), 2);
+        assert_eq!(header[0], "Time Left: 00:15", "no room for a bar");
+    }
+
+    #[test]
+    fn caret_is_located_on_its_line() {
+        let words: Vec<String> = "the quick brown fox".split(' ').map(str::to_string).collect();
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 12, 3);
+        assert_eq!(lines, vec!["[t]he quick", "brown fox"]);
+        assert_eq!(caret_position, Some((0, 1)));
+        "the quick br".chars().for_each(|c| state.handle_char(c));
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 12, 3);
+        assert_eq!(lines, vec!["the quick", "br[o]wn fox"]);
+        assert_eq!(caret_position, Some((1, 3)));
+    }
+
+    #[test]
+    fn caret_maps_to_its_screen_position() {
+        let words: Vec<String> = "the quick brown fox".split(' ').map(str::to_string).collect();
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), words);
+        "the quick br".chars().for_each(|c| state.handle_char(c));
+        let (lines, caret_position) = render_typing_lines(&state, &theme, false, 12, 3);
+        // "br[o]wn fox" is 11 columns wide, so on 41 columns it starts at column 15.
+        assert_eq!(caret_screen_position(&lines, caret_position.unwrap(), 5, 41), (18, 6));
+        // Colored lines are centered by their visible width, ignoring the escape codes:
+        // "brown fox" starts at column 16.
+        let (lines, caret_position) = render_typing_lines(&state, &theme, true, 12, 3);
+        assert_eq!(caret_position, Some((1, 2)));
+        assert_eq!(caret_screen_position(&lines, caret_position.unwrap(), 5, 41), (18, 6));
+    }
+
+    #[test]
+    fn quote_attribution_is_shown_only_for_quotes_with_a_source() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["word".to_string()]);
+        assert_eq!(quote_attribution(&state), None);
+        state.quote = Some(Quote { text: "word".to_string(), source: "  ".to_string(), ..Quote::default() });
+        assert_eq!(quote_attribution(&state), None);
+        state.quote = Some(Quote { text: "word".to_string(), source: " Ada Lovelace ".to_string(), ..Quote::default() });
+        assert_eq!(quote_attribution(&state).as_deref(), Some("\u{2014} Ada Lovelace"));
+        let marathon = ["Ada Lovelace", "", "Grace Hopper"].map(|source| {
+            (0, Quote { text: "word".to_string(), source: source.to_string(), ..Quote::default() })
+        });
+        state.quote_starts = marathon.to_vec();
+        assert_eq!(quote_attribution(&state).as_deref(), Some("\u{2014} Ada Lovelace; Grace Hopper"));
+    }
+
+    #[test]
+    fn quote_marks_line_follows_the_marks() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["word".to_string()]);
+        assert_eq!(quote_marks_line(&state), None);
+        let quote = Quote { text: "word".to_string(), source: "Ada Lovelace".to_string(), ..Quote::default() };
+        state.quote = Some(quote.clone());
+        assert_eq!(quote_marks_line(&state).as_deref(), Some("F: favorite this quote | B: never show it again"));
+        state.quote_marks.toggle_favorite(&quote);
+        assert_eq!(quote_marks_line(&state).as_deref(), Some("Favorite quote (F: unfavorite | B: blacklist)"));
+        state.quote_marks.toggle_blacklisted(&quote);
+        assert_eq!(quote_marks_line(&state).as_deref(), Some("Blacklisted quote (B: allow again | F: favorite)"));
+    }
+
+    #[test]
+    fn progress_bar_fills_in_proportion() {
+        assert_eq!(render_progress_bar(0, 10, 8), "░░░░░░░░");
+        assert_eq!(render_progress_bar(5, 10, 8), "████░░░░");
+        assert_eq!(render_progress_bar(10, 10, 8), "████████");
+        assert_eq!(render_progress_bar(9, 10, 8), "███████░", "only full once done");
+        assert_eq!(render_progress_bar(12, 10, 4), "████");
+        assert_eq!(render_progress_bar(0, 0, 4), "░░░░");
+        assert_eq!(with_progress_bar("Time Left: 00:15".to_string(), 45, 60, 200),
+            format!("Time Left: 00:15  {}", render_progress_bar(3, 4, PROGRESS_BAR_MAX_WIDTH)));
+    }
+
+    #[test]
+    fn text_layout_never_underflows_on_tiny_terminals() {
+        assert_eq!(text_layout(1, 2, 1), (2, 0));
+        assert_eq!(text_layout(0, 2, 3), (2, 0));
+        assert_eq!(text_wrap_width(1), 10);
+        assert!(terminal_is_too_small(1, 1));
+        assert!(terminal_is_too_small(MIN_TERMINAL_WIDTH - 1, 24));
+        assert!(terminal_is_too_small(80, MIN_TERMINAL_HEIGHT - 1));
+    }
+
+    #[test]
+    fn text_layout_keeps_text_between_header_and_footer() {
+        // The smallest supported terminal: three header rows, then text, then the footer.
+        let (start, footer) = text_layout(MIN_TERMINAL_HEIGHT, 3, 3);
+        assert!(!terminal_is_too_small(MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT));
+        assert_eq!((start, footer), (3, 6));
+        assert!(start + 3 <= footer);
+        assert_eq!(text_wrap_width(MIN_TERMINAL_WIDTH), 16);
+
+        let (start, footer) = text_layout(24, 2, 3);
+        assert_eq!((start, footer), (11, 23));
+    }
+
+    #[test]
+    fn centering_clamps_lines_wider_than_the_terminal() {
+        assert_eq!(centered_column("abcd", 10), 3);
+        assert_eq!(centered_column("abcdefghijkl", 10), 0);
+        // Wide characters take two columns each.
+        assert_eq!(centered_column("日本", 10), 3);
+    }
+
+    #[test]
+    fn padded_blocks_center_at_one_column() {
+        colored::control::set_override(true);
+        let mut block = vec!["ab".to_string(), "abcdef".red().to_string(), "日".to_string()];
+        pad_to_common_width(&mut block);
+        let columns: Vec<u16> = block.iter().map(|line| centered_column(line, 40)).collect();
+        assert_eq!(columns, vec![17, 17, 17]);
+        assert!(block[0].starts_with("ab") && block[2].starts_with("日"));
+    }
+
+    #[test]
+    fn game_over_screen_names_the_wpm_scheme() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["ab".to_string()]);
+        "xab".chars().for_each(|c| state.handle_char(c));
+        state.game_over = true;
+        state.final_elapsed_time_seconds = Some(60.0);
+        // Lines are padded to a common width, so trailing spaces are trimmed.
+        let starting = |lines: &[String], label: &str| lines.iter().filter(|line| line.starts_with(label)).map(|line| line.trim_end().to_string()).collect::<Vec<_>>();
+
+        state.config.wpm_scheme = WpmScheme::Simple;
+        let lines = game_over_lines(&state, 80, 100);
+        assert_eq!(starting(&lines, "Simple WPM:"), vec!["Simple WPM: 0"]);
+        assert!(starting(&lines, "Scoring:")[0].ends_with("simple (gross times accuracy)"));
+        assert!(starting(&lines, "Net WPM:").is_empty());
+
+        state.config.wpm_scheme = WpmScheme::Raw;
+        let lines = game_over_lines(&state, 80, 100);
+        assert_eq!(starting(&lines, "Raw WPM:"), vec!["Raw WPM:    1"]);
+        assert!(starting(&lines, "Gross WPM:").is_empty(), "raw WPM is gross WPM, so it is not shown twice");
+    }
+
+    #[test]
+    fn average_comparison_shows_the_signed_difference() {
+        assert_eq!(average_comparison(78.2, 74.4, "WPM"), "WPM 78 (+4 vs your avg)");
+        assert_eq!(average_comparison(61.0, 70.6, "net WPM"), "Net WPM 61 (-10 vs your avg)");
+        assert_eq!(average_comparison(70.4, 69.6, "WPM"), "WPM 70 (+0 vs your avg)");
+    }
+
+    #[test]
+    fn game_over_screen_compares_to_the_average_only_with_history() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["hello".to_string()]);
+        "hello".chars().for_each(|c| state.handle_char(c));
+        state.game_over = true;
+        state.final_elapsed_time_seconds = Some(60.0);
+        state.result_recorded = true;
+        let comparisons = |state: &GameState| game_over_lines(state, 80, 100).into_iter()
+            .filter(|line| line.contains("vs your avg")).map(|line| line.trim().to_string()).collect::<Vec<_>>();
+        assert!(comparisons(&state).is_empty(), "a first run has no average to compare with");
+        state.previous_average_wpm = Some(0.4);
+        state.previous_best_wpm = Some(0.4);
+        assert_eq!(comparisons(&state), vec!["WPM 1 (+1 vs your avg)"]);
+    }
+
+    #[test]
+    fn game_over_stats_stay_aligned_when_centered() {
+        let mut state = GameState::new(GameConfig::new(), Vec::new(), Vec::new(), vec!["ab".to_string()]);
+        "ab".chars().for_each(|c| state.handle_char(c));
+        state.game_over = true;
+        state.final_elapsed_time_seconds = Some(60.0);
+        let lines = game_over_lines(&state, 80, 100);
+        let stat_columns: Vec<u16> = lines
+            .iter()
+            .filter(|line| ["Gross WPM:", "Net WPM:", "KPM:", "Accuracy:", "Scoring:"].iter().any(|label| line.starts_with(label)))
+            .map(|line| centered_column(line, 80))
+            .collect();
+        assert_eq!(stat_columns.len(), 5);
+        assert!(lines.iter().any(|line| line.trim_end() == "KPM:        2"), "{lines:?}");
+        assert!(stat_columns.iter().all(|&column| column == stat_columns[0]), "{stat_columns:?}");
+        let banner_end = lines.iter().position(|line| line.is_empty()).unwrap();
+        let banner_columns: Vec<u16> = lines[..banner_end].iter().map(|line| centered_column(line, 80)).collect();
+        assert!(banner_columns.iter().all(|&column| column == banner_columns[0]), "{banner_columns:?}");
+    }
+
+    #[test]
+    fn code_lines_keep_line_breaks_and_mark_line_ends() {
+        let lines = vec!["fn f() {".to_string(), "    x".to_string(), "}".to_string(), "".to_string(), "g()".to_string()];
+        let config = GameConfig { game_type: GameType::Code, ..GameConfig::new() };
+        let mut state = GameState::new(config, Vec::new(), Vec::new(), lines);
+        let theme = Theme::from_name(&crate::config::ThemeName::Default);
+        "fn f() {\n  ".chars().for_each(|c| state.handle_char(c));
+        let (rendered, caret) = render_code_lines(&state, &theme, false, 10);
+        assert_eq!(rendered, ["fn f() {", "  [ ] x", "}", "", "g()"]);
+        assert_eq!(caret, Some((1, 3)));
+
+        "  x".chars().for_each(|c| state.handle_char(c));
+        let (rendered, caret) = render_code_lines(&state, &theme, false, 10);
+        assert_eq!(rendered[1], "    x[↵]");
+        assert_eq!(caret, Some((1, 6)));
+
+        // Only `max_lines` lines are shown, with the current line kept in view.
+        "\n}\n".chars().for_each(|c| state.handle_char(c));
+        let (rendered, caret) = render_code_lines(&state, &theme, false, 2);
+        assert_eq!(rendered, ["[↵]", "g()"]);
+        assert_eq!(caret, Some((0, 1)));
+        assert_eq!(most_missed_line(&HashMap::from([('\n', (2, 1))])), "Most missed: enter 50% (1/2)");
+    }
+}